@@ -8,7 +8,7 @@
 //! - Functions for serializing these resources to JSON.
 //! - Functions for deserializing these resources from JSON.
 //!
-//! Note: Validation is light because the schema is specifically flexible. We only validate required fields, not field types (like email is actually an email)
+//! Note: Validation is light because the schema is specifically flexible. `validate` only checks required fields, not field types (like email is actually an email). Use `validate_strict` to also check field formats.
 //!
 //! ## Examples
 //!
@@ -91,15 +91,28 @@ pub mod models {
     pub mod service_provider_config;
     pub mod enterprise_user;
     pub mod scim_schema;
+    pub mod schema_uri;
     pub mod others;
     pub mod errors;
+    pub mod bulk;
+    pub mod resource;
 }
 
-/// Declaring the utils module which contains the error submodule
+/// Declaring the utils module which contains the bulk, error, filter, patch, query, sql and stream submodules
 pub mod utils {
+    pub mod bulk;
     pub mod error;
+    pub mod filter;
+    pub mod patch;
+    pub mod query;
+    pub mod sql;
+    pub mod stream;
 }
 
+/// An in-memory resource store for prototyping SCIM servers, available with the `server` feature
+#[cfg(feature = "server")]
+pub mod store;
+
 
 
 
@@ -0,0 +1,322 @@
+use std::io::{Read, Write};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::schema_uri;
+use crate::utils::error::{from_json_reader, from_json_str, SCIMError};
+
+/// Represents a SCIM bulk request (RFC 7644 §3.7).
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BulkRequest {
+    pub schemas: Vec<String>,
+    #[serde(rename = "failOnErrors", skip_serializing_if = "Option::is_none")]
+    pub fail_on_errors: Option<i64>,
+    #[serde(rename = "Operations")]
+    pub operations: Vec<BulkOperation>,
+}
+
+impl Default for BulkRequest {
+    fn default() -> Self {
+        BulkRequest {
+            schemas: vec![schema_uri::BULK_REQUEST.to_string()],
+            fail_on_errors: None,
+            operations: vec![],
+        }
+    }
+}
+
+/// A single operation within a `BulkRequest`.
+///
+/// `data` is kept as raw JSON because its shape depends on `method` and on the resource type at `path`.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Default)]
+pub struct BulkOperation {
+    pub method: String,
+    #[serde(rename = "bulkId", skip_serializing_if = "Option::is_none")]
+    pub bulk_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// Represents a SCIM bulk response (RFC 7644 §3.7).
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct BulkResponse {
+    pub schemas: Vec<String>,
+    #[serde(rename = "Operations")]
+    pub operations: Vec<BulkOperationResult>,
+}
+
+impl Default for BulkResponse {
+    fn default() -> Self {
+        BulkResponse {
+            schemas: vec![schema_uri::BULK_RESPONSE.to_string()],
+            operations: vec![],
+        }
+    }
+}
+
+/// The outcome of a single `BulkOperation`, as returned in a `BulkResponse`.
+///
+/// `response` carries the error body (or, optionally, the resource) for the operation.
+#[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Default)]
+pub struct BulkOperationResult {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    pub method: String,
+    #[serde(rename = "bulkId", skip_serializing_if = "Option::is_none")]
+    pub bulk_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response: Option<Value>,
+    pub status: String,
+}
+
+/// Converts a JSON string into a `BulkRequest` struct.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a `BulkRequest` object.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::bulk::BulkRequest;
+///
+/// let bulk_json = r#"{
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkRequest"],
+///     "Operations": [
+///         {"method": "DELETE", "path": "/Users/b7c14771-226c-4d05-8860-134711653041"}
+///     ]
+/// }"#;
+///
+/// match BulkRequest::try_from(bulk_json) {
+///     Ok(request) => println!("Successfully converted JSON to BulkRequest: {:?}", request),
+///     Err(e) => println!("Error converting from JSON to BulkRequest: {}", e),
+/// }
+/// ```
+impl TryFrom<&str> for BulkRequest {
+    type Error = SCIMError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        from_json_str(value)
+    }
+}
+
+impl BulkRequest {
+    /// Serializes the `BulkRequest` instance to a JSON string, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` contains
+    /// the JSON string representation of the `BulkRequest` instance, and `Err(SCIMError)` contains
+    /// the custom error encountered during serialization.
+    pub fn serialize(&self) -> Result<String, SCIMError> {
+        serde_json::to_string(&self).map_err(SCIMError::SerializationError)
+    }
+
+    /// Deserializes a JSON string into a `BulkRequest` instance, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<BulkRequest, SCIMError>`, where `Ok(BulkRequest)` is the deserialized
+    /// `BulkRequest` instance, and `Err(SCIMError)` is the custom error encountered during deserialization.
+    pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
+        from_json_str(json)
+    }
+
+    /// Deserializes a `BulkRequest` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `BulkRequest`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `BulkRequest` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+}
+
+/// Converts a JSON string into a `BulkResponse` struct.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON string cannot be parsed into a `BulkResponse` object.
+impl TryFrom<&str> for BulkResponse {
+    type Error = SCIMError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        from_json_str(value)
+    }
+}
+
+impl BulkResponse {
+    /// Serializes the `BulkResponse` instance to a JSON string, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` contains
+    /// the JSON string representation of the `BulkResponse` instance, and `Err(SCIMError)` contains
+    /// the custom error encountered during serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::bulk::{BulkOperationResult, BulkResponse};
+    ///
+    /// let response = BulkResponse {
+    ///     operations: vec![BulkOperationResult {
+    ///         location: Some("https://example.com/v2/Users/92b725cd-9465-4e7d-8c16-01f8e146b87a".to_string()),
+    ///         method: "POST".to_string(),
+    ///         bulk_id: Some("qwerty".to_string()),
+    ///         status: "201".to_string(),
+    ///         ..Default::default()
+    ///     }],
+    ///     ..Default::default()
+    /// };
+    ///
+    /// match response.serialize() {
+    ///     Ok(json) => println!("Serialized BulkResponse: {}", json),
+    ///     Err(e) => println!("Serialization error: {}", e),
+    /// }
+    /// ```
+    pub fn serialize(&self) -> Result<String, SCIMError> {
+        serde_json::to_string(&self).map_err(SCIMError::SerializationError)
+    }
+
+    /// Deserializes a JSON string into a `BulkResponse` instance, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<BulkResponse, SCIMError>`, where `Ok(BulkResponse)` is the deserialized
+    /// `BulkResponse` instance, and `Err(SCIMError)` is the custom error encountered during deserialization.
+    pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
+        from_json_str(json)
+    }
+
+    /// Deserializes a `BulkResponse` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `BulkResponse`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `BulkResponse` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn bulk_request_round_trips_two_post_operations() {
+        let json_data = json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkRequest"],
+            "failOnErrors": 1,
+            "Operations": [
+                {
+                    "method": "POST",
+                    "path": "/Users",
+                    "bulkId": "qwerty",
+                    "data": {
+                        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                        "userName": "Alice"
+                    }
+                },
+                {
+                    "method": "POST",
+                    "path": "/Groups",
+                    "bulkId": "ytrewq",
+                    "data": {
+                        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+                        "displayName": "Tour Guides",
+                        "members": [{"type": "User", "value": "bulkId:qwerty"}]
+                    }
+                }
+            ]
+        });
+
+        let request = BulkRequest::deserialize(&json_data.to_string()).unwrap();
+        assert_eq!(request.fail_on_errors, Some(1));
+        assert_eq!(request.operations.len(), 2);
+        assert_eq!(request.operations[0].method, "POST");
+        assert_eq!(request.operations[0].bulk_id.as_deref(), Some("qwerty"));
+        assert_eq!(request.operations[1].path, "/Groups");
+        assert_eq!(request.operations[1].data.as_ref().unwrap()["members"][0]["value"], "bulkId:qwerty");
+
+        let serialized: Value = serde_json::from_str(&request.serialize().unwrap()).unwrap();
+        assert_eq!(serialized, json_data);
+    }
+
+    #[test]
+    fn bulk_response_serializes_with_spec_field_names() {
+        let response = BulkResponse {
+            operations: vec![
+                BulkOperationResult {
+                    location: Some("https://example.com/v2/Users/92b725cd-9465-4e7d-8c16-01f8e146b87a".to_string()),
+                    method: "POST".to_string(),
+                    bulk_id: Some("qwerty".to_string()),
+                    version: Some("W/\"oY4m4wn58tkVjJxK\"".to_string()),
+                    status: "201".to_string(),
+                    ..Default::default()
+                },
+                BulkOperationResult {
+                    method: "POST".to_string(),
+                    bulk_id: Some("ytrewq".to_string()),
+                    response: Some(json!({
+                        "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+                        "scimType": "invalidSyntax",
+                        "status": "400"
+                    })),
+                    status: "400".to_string(),
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let serialized: Value = serde_json::from_str(&response.serialize().unwrap()).unwrap();
+        assert_eq!(serialized, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkResponse"],
+            "Operations": [
+                {
+                    "location": "https://example.com/v2/Users/92b725cd-9465-4e7d-8c16-01f8e146b87a",
+                    "method": "POST",
+                    "bulkId": "qwerty",
+                    "version": "W/\"oY4m4wn58tkVjJxK\"",
+                    "status": "201"
+                },
+                {
+                    "method": "POST",
+                    "bulkId": "ytrewq",
+                    "response": {
+                        "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+                        "scimType": "invalidSyntax",
+                        "status": "400"
+                    },
+                    "status": "400"
+                }
+            ]
+        }));
+    }
+}
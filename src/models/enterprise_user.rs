@@ -1,8 +1,12 @@
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::utils::error::SCIMError;
+use crate::utils::error::{from_json_reader, from_json_str, from_json_value, SCIMError};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct EnterpriseUser {
     #[serde(rename = "employeeNumber", skip_serializing_if = "Option::is_none")]
@@ -50,23 +54,33 @@ impl TryFrom<&str> for EnterpriseUser {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into an `EnterpriseUser` struct, avoiding a round trip through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON value cannot be converted into an `EnterpriseUser` object.
+impl TryFrom<Value> for EnterpriseUser {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value)
     }
 }
 
 impl EnterpriseUser {
     /// Validates an enterprise user.
     ///
-    /// This function checks if the enterprise user has `employee_number`, `cost_center`, `organization`, `division`, `department`, and `manager`. If any of these fields are missing, it returns an error.
-    ///
-    /// # Arguments
-    ///
-    /// * `enterprise_user` - A reference to an EnterpriseUser instance.
+    /// Every attribute of the enterprise extension is optional (RFC 7643 §4.3), so any combination of present
+    /// fields is valid and this always succeeds. Use `validate_fields` to check the format of the fields that are
+    /// present.
     ///
     /// # Returns
     ///
-    /// * `Ok(())` - If the enterprise user is valid.
-    /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Ok(())` - The enterprise user is valid.
     ///
     /// # Example
     ///
@@ -74,8 +88,7 @@ impl EnterpriseUser {
     /// use scim_v2::models::enterprise_user::EnterpriseUser;
     ///
     /// let enterprise_user = EnterpriseUser {
-    ///     // Initialize enterprise_user fields here...
-    ///     // ...
+    ///     department: Some("Tour Operations".to_string()),
     ///     ..Default::default()
     /// };
     ///
@@ -85,26 +98,45 @@ impl EnterpriseUser {
     /// }
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
-        if self.employee_number.is_none() {
-            return Err(SCIMError::MissingRequiredField("employee_number".to_string()));
-        }
-        if self.cost_center.is_none() {
-            return Err(SCIMError::MissingRequiredField("cost_center".to_string()));
-        }
-        if self.organization.is_none() {
-            return Err(SCIMError::MissingRequiredField("organization".to_string()));
-        }
-        if self.division.is_none() {
-            return Err(SCIMError::MissingRequiredField("division".to_string()));
-        }
-        if self.department.is_none() {
-            return Err(SCIMError::MissingRequiredField("department".to_string()));
-        }
-        if self.manager.is_none() {
-            return Err(SCIMError::MissingRequiredField("manager".to_string()));
+        Ok(())
+    }
+
+    /// Validates the format of the enterprise user's present fields.
+    ///
+    /// Absent fields are not an error. A `manager` reference, when present, must have a non-empty `value`
+    /// (the manager's id), which the schema requires. Some providers send a manager with only a `displayName`;
+    /// such payloads still deserialize, and are reported here instead.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every present field is well-formed.
+    /// * `Err(SCIMError::MissingRequiredField("manager.value"))` - If the manager reference has no or an empty
+    ///   `value`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::enterprise_user::EnterpriseUser;
+    /// use scim_v2::utils::error::SCIMError;
+    ///
+    /// let enterprise_user = EnterpriseUser::try_from(r#"{"manager": {"displayName": "John Smith"}}"#).unwrap();
+    ///
+    /// assert!(matches!(enterprise_user.validate_fields(), Err(SCIMError::MissingRequiredField(field)) if field == "manager.value"));
+    /// ```
+    pub fn validate_fields(&self) -> Result<(), SCIMError> {
+        if let Some(manager) = &self.manager {
+            if manager.value.as_deref().map_or(true, str::is_empty) {
+                return Err(SCIMError::MissingRequiredField("manager.value".to_string()));
+            }
         }
         Ok(())
     }
+
+    /// Returns the id of the user's manager, i.e. the manager reference's `value`, if set.
+    pub fn manager_id(&self) -> Option<&str> {
+        self.manager.as_ref()?.value.as_deref()
+    }
+
     /// Serializes the `EnterpriseUser` instance to a JSON string, using the custom SCIMError for error handling.
     ///
     /// # Returns
@@ -161,15 +193,199 @@ impl EnterpriseUser {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        from_json_str(json)
+    }
+
+    /// Deserializes a `EnterpriseUser` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `EnterpriseUser`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `EnterpriseUser` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Manager {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
-    #[serde(rename = "$ref")]
+    #[serde(rename = "$ref", alias = "ref", skip_serializing_if = "Option::is_none")]
     pub ref_: Option<String>,
-    #[serde(rename = "displayName")]
+    #[serde(rename = "displayName", skip_serializing_if = "Option::is_none")]
     pub display_name: Option<String>,
 }
+
+/// Builds an `EnterpriseUser` step by step, validating it once complete.
+///
+/// Attach the result to a user with `User::with_enterprise`, which also lists the extension's schema URN.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::enterprise_user::EnterpriseUser;
+/// use scim_v2::models::user::User;
+///
+/// let enterprise_user = EnterpriseUser::builder()
+///     .employee_number("701984")
+///     .department("Tour Operations")
+///     .manager("26118915-6090-4610-87e4-49d8ca9f808d", "John Smith")
+///     .build()
+///     .unwrap();
+///
+/// let user = User { user_name: "bjensen".to_string(), ..Default::default() }.with_enterprise(enterprise_user);
+/// assert!(user.validate_schema_consistency().is_ok());
+/// ```
+#[derive(Debug, Default)]
+pub struct EnterpriseUserBuilder {
+    enterprise_user: EnterpriseUser,
+}
+
+impl EnterpriseUserBuilder {
+    pub fn employee_number(mut self, employee_number: impl Into<String>) -> Self {
+        self.enterprise_user.employee_number = Some(employee_number.into());
+        self
+    }
+
+    pub fn cost_center(mut self, cost_center: impl Into<String>) -> Self {
+        self.enterprise_user.cost_center = Some(cost_center.into());
+        self
+    }
+
+    pub fn organization(mut self, organization: impl Into<String>) -> Self {
+        self.enterprise_user.organization = Some(organization.into());
+        self
+    }
+
+    pub fn division(mut self, division: impl Into<String>) -> Self {
+        self.enterprise_user.division = Some(division.into());
+        self
+    }
+
+    pub fn department(mut self, department: impl Into<String>) -> Self {
+        self.enterprise_user.department = Some(department.into());
+        self
+    }
+
+    /// Sets the manager reference from the manager's id (`value`) and `displayName`.
+    pub fn manager(mut self, value: impl Into<String>, display_name: impl Into<String>) -> Self {
+        self.enterprise_user.manager = Some(Manager {
+            value: Some(value.into()),
+            ref_: None,
+            display_name: Some(display_name.into()),
+        });
+        self
+    }
+
+    /// Validates and returns the built `EnterpriseUser`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `EnterpriseUser::validate_fields`, i.e. `SCIMError::MissingRequiredField` if the
+    /// manager's `value` is empty.
+    pub fn build(self) -> Result<EnterpriseUser, SCIMError> {
+        self.enterprise_user.validate_fields()?;
+        Ok(self.enterprise_user)
+    }
+}
+
+impl EnterpriseUser {
+    /// Returns an `EnterpriseUserBuilder` for constructing an `EnterpriseUser`.
+    pub fn builder() -> EnterpriseUserBuilder {
+        EnterpriseUserBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn validate_accepts_empty_extension() {
+        let enterprise_user = EnterpriseUser::default();
+
+        assert!(enterprise_user.validate().is_ok());
+        assert!(enterprise_user.validate_fields().is_ok());
+    }
+
+    #[test]
+    fn validate_accepts_partial_extension() {
+        let enterprise_user = EnterpriseUser::try_from(r#"{"employeeNumber": "701984", "department": "Tour Operations"}"#).unwrap();
+
+        assert!(enterprise_user.validate().is_ok());
+        assert!(enterprise_user.validate_fields().is_ok());
+    }
+
+    #[test]
+    fn validate_fields_accepts_manager_with_value() {
+        let enterprise_user = EnterpriseUser::try_from(r#"{"manager": {"value": "26118915", "displayName": "John Smith"}}"#).unwrap();
+
+        assert!(enterprise_user.validate_fields().is_ok());
+    }
+
+    #[test]
+    fn validate_fields_accepts_missing_manager() {
+        let enterprise_user = EnterpriseUser::try_from(r#"{"department": "Tour Operations"}"#).unwrap();
+
+        assert_eq!(enterprise_user.manager, None);
+        assert!(enterprise_user.validate_fields().is_ok());
+    }
+
+    #[test]
+    fn validate_fields_requires_manager_value() {
+        let enterprise_user =
+            EnterpriseUser::try_from(r#"{"manager": {"displayName": "John Smith", "$ref": "../Users/26118915"}}"#).unwrap();
+        assert_eq!(enterprise_user.manager.as_ref().and_then(|manager| manager.display_name.as_deref()), Some("John Smith"));
+
+        assert!(enterprise_user.validate().is_ok());
+        match enterprise_user.validate_fields() {
+            Err(SCIMError::MissingRequiredField(field)) => assert_eq!(field, "manager.value"),
+            other => panic!("expected MissingRequiredField, got {:?}", other),
+        }
+
+        let empty_value = EnterpriseUser {
+            manager: Some(Manager { value: Some("".to_string()), ref_: None, display_name: Some("John Smith".to_string()) }),
+            ..Default::default()
+        };
+        assert!(matches!(empty_value.validate_fields(), Err(SCIMError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn builder_sets_every_field() {
+        let enterprise_user = EnterpriseUser::builder()
+            .employee_number("701984")
+            .cost_center("4130")
+            .organization("Universal Studios")
+            .division("Theme Park")
+            .department("Tour Operations")
+            .manager("26118915-6090-4610-87e4-49d8ca9f808d", "John Smith")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&enterprise_user).unwrap(),
+            serde_json::json!({
+                "employeeNumber": "701984",
+                "costCenter": "4130",
+                "organization": "Universal Studios",
+                "division": "Theme Park",
+                "department": "Tour Operations",
+                "manager": {"value": "26118915-6090-4610-87e4-49d8ca9f808d", "displayName": "John Smith"}
+            })
+        );
+    }
+
+    #[test]
+    fn builder_rejects_empty_manager_value() {
+        let result = EnterpriseUser::builder().manager("", "John Smith").build();
+        assert!(matches!(result, Err(SCIMError::MissingRequiredField(_))));
+    }
+}
@@ -1,5 +1,8 @@
 use serde::{Deserialize, Serialize};
 
+use crate::models::schema_uri;
+use crate::utils::error::SCIMError;
+
 /// Represents a SCIM HTTP Error.
 ///
 /// This struct is used to represent an error message that conforms to the SCIM protocol specification.
@@ -8,6 +11,7 @@ use serde::{Deserialize, Serialize};
 /// The `detail` field is an optional string that provides more detailed human-readable information.
 /// The `status` field is a required string that is the HTTP status code expressed as a JSON string.
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ScimHttpError {
     pub schemas: Vec<String>,
     #[serde(rename = "scimType", skip_serializing_if = "Option::is_none")]
@@ -26,7 +30,7 @@ pub struct ScimHttpError {
 impl Default for ScimHttpError {
     fn default() -> Self {
         ScimHttpError {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:Error".to_string()],
+            schemas: vec![schema_uri::ERROR.to_string()],
             scim_type: None,
             detail: None,
             status: "".to_string(),
@@ -34,6 +38,60 @@ impl Default for ScimHttpError {
     }
 }
 
+/// Converts a `SCIMError` into the SCIM error response body.
+///
+/// The HTTP status and `scimType` come from `SCIMError::http_status` and `SCIMError::scim_type`, following
+/// RFC 7644 §3.12 (e.g. `MissingRequiredField` → 400 / `invalidValue`, `ConflictError` → 409 / `uniqueness`).
+/// The error's message is used as the `detail`.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::errors::ScimHttpError;
+/// use scim_v2::utils::error::SCIMError;
+///
+/// let error = ScimHttpError::from(SCIMError::MissingRequiredField("userName".to_string()));
+/// assert_eq!(error.status, "400");
+/// assert_eq!(error.scim_type, Some("invalidValue".to_string()));
+/// ```
+impl From<SCIMError> for ScimHttpError {
+    fn from(error: SCIMError) -> Self {
+        ScimHttpError {
+            scim_type: error.scim_type().map(str::to_string),
+            detail: Some(error.to_string()),
+            status: error.http_status().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+impl ScimHttpError {
+    /// Serializes the `ScimHttpError` instance to a JSON string, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` contains
+    /// the JSON string representation of the `ScimHttpError` instance, and `Err(SCIMError)` contains
+    /// the custom error encountered during serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::errors::ScimHttpError;
+    /// use scim_v2::utils::error::SCIMError;
+    ///
+    /// let error = ScimHttpError::from(SCIMError::NotFoundError("Resource 2819c223 not found".to_string()));
+    ///
+    /// match error.serialize() {
+    ///     Ok(json) => println!("Serialized ScimHttpError: {}", json),
+    ///     Err(e) => println!("Serialization error: {}", e),
+    /// }
+    /// ```
+    pub fn serialize(&self) -> Result<String, SCIMError> {
+        serde_json::to_string(&self).map_err(SCIMError::SerializationError)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
@@ -92,4 +150,44 @@ mod tests {
             "status": "400"
         }));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn scim_http_error_from_missing_required_field_is_invalid_value() {
+        let error = ScimHttpError::from(SCIMError::MissingRequiredField("userName".to_string()));
+
+        let json: serde_json::Value = serde_json::from_str(&error.serialize().unwrap()).unwrap();
+        assert_eq!(json, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+            "scimType": "invalidValue",
+            "detail": "Missing required field: userName",
+            "status": "400"
+        }));
+    }
+
+    #[test]
+    fn scim_http_error_from_invalid_path_is_invalid_path() {
+        let error = ScimHttpError::from(SCIMError::InvalidPath("emails[".to_string()));
+        assert_eq!(error.status, "400");
+        assert_eq!(error.scim_type, Some("invalidPath".to_string()));
+        assert_eq!(error.detail, Some("Invalid path: emails[".to_string()));
+    }
+
+    #[test]
+    fn scim_http_error_from_not_found_omits_scim_type() {
+        let error = ScimHttpError::from(SCIMError::NotFoundError("Resource 2819c223-7f76-453a-919d-413861904646 not found".to_string()));
+
+        let json: serde_json::Value = serde_json::from_str(&error.serialize().unwrap()).unwrap();
+        assert_eq!(json, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:Error"],
+            "detail": "Not found error: Resource 2819c223-7f76-453a-919d-413861904646 not found",
+            "status": "404"
+        }));
+    }
+
+    #[test]
+    fn scim_http_error_from_conflict_is_uniqueness() {
+        let error = ScimHttpError::from(SCIMError::ConflictError("userName bjensen is already taken".to_string()));
+        assert_eq!(error.status, "409");
+        assert_eq!(error.scim_type, Some("uniqueness".to_string()));
+    }
+}
@@ -1,13 +1,23 @@
 //Schema for group
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
-use crate::models::scim_schema::Meta;
-use crate::utils::error::SCIMError;
+use crate::models::resource::ScimResource;
+use crate::models::schema_uri;
+use crate::models::scim_schema::{ensure_meta, Meta};
+use crate::utils::error::{from_json_reader, from_json_str, from_json_value, SCIMError};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Group {
     pub schemas: Vec<String>,
     pub id: String,
+    #[serde(rename = "externalId", skip_serializing_if = "Option::is_none")]
+    pub external_id: Option<String>,
     #[serde(rename = "displayName")]
     pub display_name: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -19,8 +29,9 @@ pub struct Group {
 impl Default for Group {
     fn default() -> Self {
         Group {
-            schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:Group".to_string()],
+            schemas: vec![schema_uri::GROUP.to_string()],
             id: "default_id".to_string(),
+            external_id: None,
             display_name: "default_display_name".to_string(),
             members: None,
             meta: None,
@@ -28,12 +39,13 @@ impl Default for Group {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Member {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
-    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref", alias = "ref", skip_serializing_if = "Option::is_none")]
     pub ref_: Option<String>,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub type_: Option<String>,
@@ -41,6 +53,61 @@ pub struct Member {
     pub display: Option<String>,
 }
 
+/// The canonical values of a member's `type` (RFC 7643 §4.2): a user, or a nested group.
+///
+/// Values are matched case-insensitively. Anything other than `"Group"` is read as `User`, since providers
+/// commonly omit the type (or send their own) for plain user members.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(from = "String", into = "String")]
+pub enum MemberType {
+    User,
+    Group,
+}
+
+impl From<String> for MemberType {
+    fn from(value: String) -> Self {
+        if value.eq_ignore_ascii_case("Group") {
+            MemberType::Group
+        } else {
+            MemberType::User
+        }
+    }
+}
+
+impl From<MemberType> for String {
+    fn from(value: MemberType) -> Self {
+        match value {
+            MemberType::User => "User".to_string(),
+            MemberType::Group => "Group".to_string(),
+        }
+    }
+}
+
+// Members carry `type` as a plain string on the wire.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for MemberType {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "MemberType".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+impl Member {
+    /// Returns `type` as a `MemberType`, or `None` if no type is set.
+    ///
+    /// Unknown types are read as `MemberType::User`.
+    pub fn member_type(&self) -> Option<MemberType> {
+        self.type_.clone().map(MemberType::from)
+    }
+}
+
 
 /// Converts a JSON string into a `Group` struct.
 ///
@@ -67,7 +134,77 @@ impl TryFrom<&str> for Group {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `Group` struct, avoiding a round trip through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON value cannot be converted into a `Group` object.
+impl TryFrom<Value> for Group {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value)
+    }
+}
+
+/// Renders a concise, log-safe summary of the group: `Group(id=<id>, displayName=<displayName>)`.
+///
+/// Members are left out, so large groups do not flood logs.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::group::Group;
+///
+/// let group = Group {
+///     id: "e9e30dba-f08f-4109-8486-d5c6a331660a".to_string(),
+///     display_name: "Tour Guides".to_string(),
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(group.to_string(), "Group(id=e9e30dba-f08f-4109-8486-d5c6a331660a, displayName=Tour Guides)");
+/// ```
+impl Display for Group {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Group(id={}, displayName={})", self.id, self.display_name)
+    }
+}
+
+impl ScimResource for Group {
+    fn schemas(&self) -> &[String] {
+        &self.schemas
+    }
+
+    fn id(&self) -> Option<&str> {
+        Some(&self.id)
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = id;
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut Meta> {
+        self.meta.as_mut()
+    }
+
+    fn set_meta(&mut self, meta: Meta) {
+        self.meta = Some(meta);
+    }
+
+    fn resource_type(&self) -> &'static str {
+        "Group"
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        Group::validate(self)
     }
 }
 
@@ -75,6 +212,7 @@ impl Group {
     /// Validates a group.
     ///
     /// This function checks if the group has `schemas`, `id`, and `display_name`. If any of these fields are missing, it returns an error.
+    /// It then checks that no two `members` share the same `value`.
     ///
     /// # Arguments
     ///
@@ -84,6 +222,7 @@ impl Group {
     ///
     /// * `Ok(())` - If the group is valid.
     /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If the same member `value` appears more than once.
     ///
     /// # Example
     ///
@@ -113,9 +252,171 @@ impl Group {
         if self.display_name.is_empty() {
             return Err(SCIMError::MissingRequiredField("display_name".to_string()));
         }
+        if let Some(members) = &self.members {
+            let mut seen = HashSet::new();
+            for value in members.iter().filter_map(|member| member.value.as_deref()) {
+                if !seen.insert(value) {
+                    return Err(SCIMError::InvalidFieldValue(format!("members: duplicate value {}", value)));
+                }
+            }
+        }
         Ok(())
     }
 
+    /// Returns the `value` (member id) of each member, in order. Members without a `value` are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let group = Group {
+    ///     members: Some(vec![Member { value: Some("2819c223-7f76-453a-919d-413861904646".to_string()), ..Default::default() }]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(group.member_ids(), vec!["2819c223-7f76-453a-919d-413861904646"]);
+    /// ```
+    pub fn member_ids(&self) -> Vec<&str> {
+        self.members.iter().flatten().filter_map(|member| member.value.as_deref()).collect()
+    }
+
+    /// Returns `true` if any member's `value` is `id`.
+    pub fn contains_member(&self, id: &str) -> bool {
+        self.members.iter().flatten().any(|member| member.value.as_deref() == Some(id))
+    }
+
+    /// Returns the direct members that are users.
+    ///
+    /// Members without a `type`, or with a type other than `Group`, are treated as users.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let group = Group {
+    ///     members: Some(vec![
+    ///         Member { value: Some("bjensen".to_string()), ..Default::default() },
+    ///         Member { value: Some("trainees".to_string()), type_: Some("Group".to_string()), ..Default::default() },
+    ///     ]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(group.user_members().len(), 1);
+    /// assert_eq!(group.group_members()[0].value.as_deref(), Some("trainees"));
+    /// ```
+    pub fn user_members(&self) -> Vec<&Member> {
+        self.members.iter().flatten().filter(|member| member.member_type() != Some(MemberType::Group)).collect()
+    }
+
+    /// Returns the direct members that are nested groups, i.e. whose `type` is `Group`.
+    pub fn group_members(&self) -> Vec<&Member> {
+        self.members.iter().flatten().filter(|member| member.member_type() == Some(MemberType::Group)).collect()
+    }
+
+    /// Returns the ids of every user that is a member of this group, directly or through nested groups.
+    ///
+    /// Members whose `type` is `Group` are looked up with `resolver` and their members are included in turn;
+    /// all other members are treated as users. Each id appears once, in the order it is first reached. Groups
+    /// that have already been visited are skipped, so cyclic memberships terminate, and nested groups that
+    /// `resolver` cannot find are ignored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let member = |value: &str, type_: &str| Member {
+    ///     value: Some(value.to_string()),
+    ///     type_: Some(type_.to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let guides = Group {
+    ///     id: "guides".to_string(),
+    ///     members: Some(vec![member("bjensen", "User"), member("trainees", "Group")]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let user_ids = guides.flatten_members(|id| match id {
+    ///     "trainees" => Some(Group {
+    ///         id: "trainees".to_string(),
+    ///         members: Some(vec![member("jsmith", "User")]),
+    ///         ..Default::default()
+    ///     }),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(user_ids, vec!["bjensen", "jsmith"]);
+    /// ```
+    pub fn flatten_members(&self, resolver: impl Fn(&str) -> Option<Group>) -> Vec<String> {
+        let mut visited = HashSet::from([self.id.clone()]);
+        let mut seen = HashSet::new();
+        let mut user_ids = Vec::new();
+        // Each stack entry is a group's remaining members, so the walk is depth-first without recursion.
+        let mut stack = vec![self.members.clone().unwrap_or_default().into_iter()];
+        while let Some(members) = stack.last_mut() {
+            let member = match members.next() {
+                Some(member) => member,
+                None => {
+                    stack.pop();
+                    continue;
+                }
+            };
+            let is_group = member.member_type() == Some(MemberType::Group);
+            let value = match member.value {
+                Some(value) => value,
+                None => continue,
+            };
+            if !is_group {
+                if seen.insert(value.clone()) {
+                    user_ids.push(value);
+                }
+            } else if visited.insert(value.clone()) {
+                if let Some(group) = resolver(&value) {
+                    stack.push(group.members.unwrap_or_default().into_iter());
+                }
+            }
+        }
+        user_ids
+    }
+
+    /// Fills each member's `display` from `lookup`, keyed by the member's `value`.
+    ///
+    /// Members without a `value`, or whose `value` the lookup does not find, keep their existing `display`.
+    /// This is useful for refreshing stale display names right before returning a group.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::group::{Group, Member};
+    ///
+    /// let mut group = Group {
+    ///     members: Some(vec![Member { value: Some("2819c223-7f76-453a-919d-413861904646".to_string()), ..Default::default() }]),
+    ///     ..Default::default()
+    /// };
+    /// group.with_member_displays(|id| match id {
+    ///     "2819c223-7f76-453a-919d-413861904646" => Some("Babs Jensen".to_string()),
+    ///     _ => None,
+    /// });
+    ///
+    /// assert_eq!(group.members.unwrap()[0].display.as_deref(), Some("Babs Jensen"));
+    /// ```
+    pub fn with_member_displays(&mut self, lookup: impl Fn(&str) -> Option<String>) {
+        for member in self.members.iter_mut().flatten() {
+            if let Some(display) = member.value.as_deref().and_then(&lookup) {
+                member.display = Some(display);
+            }
+        }
+    }
+
+    /// Populates `meta.resourceType` and `meta.location` if `meta` is missing or has no `resourceType`.
+    ///
+    /// `resourceType` is set to `Group` and, when the group has an `id` and no `location` yet, `location` is set
+    /// to `<base_url>/Groups/<id>`. Timestamps and `version` are left untouched.
+    pub fn ensure_meta(&mut self, base_url: &str) {
+        ensure_meta(&mut self.meta, "Group", "Groups", base_url, Some(&self.id));
+    }
+
     /// Serializes the `Group` instance to a JSON string, using the custom SCIMError for error handling.
     ///
     /// # Returns
@@ -169,7 +470,123 @@ impl Group {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        from_json_str(json)
+    }
+
+    /// Deserializes a `Group` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `Group`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `Group` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+}
+
+/// Builds a `Group` step by step, validating it once complete.
+///
+/// `schemas` defaults to the core Group URN. Since groups require an `id`, set one with `id`, or with the
+/// `server` feature call `generate_id` to assign a random UUID at build time.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::group::Group;
+///
+/// let group = Group::builder()
+///     .id("e9e30dba-f08f-4109-8486-d5c6a331660a")
+///     .display_name("Tour Guides")
+///     .add_member("2819c223-7f76-453a-919d-413861904646", Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"), Some("Babs Jensen"))
+///     .build();
+///
+/// match group {
+///     Ok(group) => println!("Built Group: {:?}", group),
+///     Err(e) => println!("Group is invalid: {}", e),
+/// }
+/// ```
+#[derive(Debug)]
+pub struct GroupBuilder {
+    group: Group,
+    #[cfg(feature = "server")]
+    generate_id: bool,
+}
+
+impl Default for GroupBuilder {
+    fn default() -> Self {
+        GroupBuilder {
+            group: Group {
+                id: String::new(),
+                display_name: String::new(),
+                ..Default::default()
+            },
+            #[cfg(feature = "server")]
+            generate_id: false,
+        }
+    }
+}
+
+impl GroupBuilder {
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.group.id = id.into();
+        self
+    }
+
+    /// Assigns a random UUID as the `id` at build time, unless one was set with `id`.
+    ///
+    /// Only available with the `server` feature.
+    #[cfg(feature = "server")]
+    pub fn generate_id(mut self) -> Self {
+        self.generate_id = true;
+        self
+    }
+
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.group.display_name = display_name.into();
+        self
+    }
+
+    pub fn external_id(mut self, external_id: impl Into<String>) -> Self {
+        self.group.external_id = Some(external_id.into());
+        self
+    }
+
+    /// Adds a member with the given `value` (member id), and optionally its `$ref` URI and `display` name.
+    pub fn add_member(mut self, value: impl Into<String>, ref_url: Option<&str>, display: Option<&str>) -> Self {
+        self.group.members.get_or_insert_with(Vec::new).push(Member {
+            value: Some(value.into()),
+            ref_: ref_url.map(str::to_string),
+            display: display.map(str::to_string),
+            ..Default::default()
+        });
+        self
+    }
+
+    /// Validates and returns the built `Group`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `Group::validate` if the group is invalid, e.g. `SCIMError::MissingRequiredField`
+    /// when no `id` or `display_name` was set, or `SCIMError::InvalidFieldValue` for a duplicate member.
+    #[cfg_attr(not(feature = "server"), allow(unused_mut))]
+    pub fn build(mut self) -> Result<Group, SCIMError> {
+        #[cfg(feature = "server")]
+        if self.generate_id && self.group.id.is_empty() {
+            self.group.id = uuid::Uuid::new_v4().to_string();
+        }
+        self.group.validate()?;
+        Ok(self.group)
+    }
+}
+
+impl Group {
+    /// Returns a `GroupBuilder` for constructing a `Group`.
+    pub fn builder() -> GroupBuilder {
+        GroupBuilder::default()
     }
 }
 
@@ -230,8 +647,14 @@ mod tests {
         // Check meta
         let meta = group.meta.unwrap();
         assert_eq!(meta.resource_type, Some("Group".to_string()));
+        #[cfg(not(feature = "chrono"))]
         assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".to_string()));
+        #[cfg(not(feature = "chrono"))]
         assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".to_string()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
         assert_eq!(meta.version, Some("W/\"3694e05e9dff592\"".to_string()));
         assert_eq!(meta.location, Some("https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()));
     }
@@ -301,4 +724,230 @@ mod tests {
         assert!(group.members.is_none());
         assert!(group.meta.is_none());
     }
-}
\ No newline at end of file
+
+    fn group_with_members(values: &[&str]) -> Group {
+        Group {
+            members: Some(values.iter().map(|value| Member { value: Some(value.to_string()), ..Default::default() }).collect()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_group_succeeds_without_members() {
+        let group = Group { members: None, ..Default::default() };
+        assert!(group.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_group_succeeds_with_unique_members() {
+        let group = group_with_members(&["2819c223-7f76-453a-919d-413861904646", "902c246b-6245-4190-8e05-00816be7344a"]);
+        assert!(group.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_group_fails_with_duplicate_member() {
+        let group = group_with_members(&["2819c223-7f76-453a-919d-413861904646", "902c246b-6245-4190-8e05-00816be7344a", "2819c223-7f76-453a-919d-413861904646"]);
+        match group.validate() {
+            Err(SCIMError::InvalidFieldValue(msg)) => assert_eq!(msg, "members: duplicate value 2819c223-7f76-453a-919d-413861904646"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_group_checks_required_fields_before_members() {
+        let group = Group { display_name: "".to_string(), ..group_with_members(&["a", "a"]) };
+        assert!(matches!(group.validate(), Err(SCIMError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn group_member_helpers_return_member_ids() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "id": "e9e30dba-f08f-4109-8486-d5c6a331660a",
+            "displayName": "Tour Guides",
+            "members": [
+                {
+                    "value": "2819c223-7f76-453a-919d-413861904646",
+                    "$ref": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646",
+                    "display": "Babs Jensen"
+                },
+                {
+                    "value": "902c246b-6245-4190-8e05-00816be7344a",
+                    "$ref": "https://example.com/v2/Users/902c246b-6245-4190-8e05-00816be7344a",
+                    "display": "Mandy Pepperidge"
+                }
+            ]
+        }"#;
+
+        let group = Group::deserialize(json_data).unwrap();
+
+        assert_eq!(group.member_ids(), vec!["2819c223-7f76-453a-919d-413861904646", "902c246b-6245-4190-8e05-00816be7344a"]);
+        assert!(group.contains_member("902c246b-6245-4190-8e05-00816be7344a"));
+        assert!(!group.contains_member("e9e30dba-f08f-4109-8486-d5c6a331660a"));
+    }
+
+    #[test]
+    fn group_member_helpers_handle_missing_members() {
+        let group = Group { members: None, ..Default::default() };
+
+        assert!(group.member_ids().is_empty());
+        assert!(!group.contains_member("2819c223-7f76-453a-919d-413861904646"));
+    }
+
+    #[test]
+    fn ensure_meta_fills_group_resource_type_and_location() {
+        let mut group = Group {
+            id: "e9e30dba-f08f-4109-8486-d5c6a331660a".to_string(),
+            display_name: "Tour Guides".to_string(),
+            ..Default::default()
+        };
+
+        group.ensure_meta("https://example.com/v2");
+
+        let meta = group.meta.unwrap();
+        assert_eq!(meta.resource_type.as_deref(), Some("Group"));
+        assert_eq!(meta.location.as_deref(), Some("https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a"));
+    }
+
+    #[test]
+    fn ensure_meta_normalizes_base_url_and_keeps_existing_group_meta() {
+        let mut group = Group { id: "e9e30dba".to_string(), display_name: "Tour Guides".to_string(), ..Default::default() };
+        group.ensure_meta("https://example.com/v2/");
+        let location = group.meta.as_ref().and_then(|meta| meta.location.as_deref()).unwrap();
+        assert!(location.ends_with("/Groups/e9e30dba"), "{}", location);
+        assert!(!location.contains("v2//"), "{}", location);
+
+        let existing = Meta { resource_type: Some("Group".to_string()), location: Some("https://other.example.com/Groups/1".to_string()), ..Default::default() };
+        let mut group = Group { id: "e9e30dba".to_string(), meta: Some(existing.clone()), ..Default::default() };
+        group.ensure_meta("https://example.com/v2");
+        assert_eq!(group.meta, Some(existing));
+    }
+
+    fn member(value: &str, type_: &str) -> Member {
+        Member { value: Some(value.to_string()), type_: Some(type_.to_string()), ..Default::default() }
+    }
+
+    fn nested_group(id: &str, members: Vec<Member>) -> Group {
+        Group { id: id.to_string(), display_name: id.to_string(), members: Some(members), ..Default::default() }
+    }
+
+    #[test]
+    fn flatten_members_walks_two_levels_of_nesting() {
+        let root = nested_group("staff", vec![member("alice", "User"), member("engineering", "Group")]);
+        let resolver = |id: &str| match id {
+            "engineering" => Some(nested_group("engineering", vec![member("bob", "User"), member("sre", "Group")])),
+            "sre" => Some(nested_group("sre", vec![member("carol", "User"), member("alice", "User")])),
+            _ => None,
+        };
+
+        assert_eq!(root.flatten_members(resolver), vec!["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn flatten_members_terminates_on_cycles() {
+        let root = nested_group("a", vec![member("b", "Group"), member("alice", "User")]);
+        let resolver = |id: &str| match id {
+            "a" => Some(nested_group("a", vec![member("b", "Group"), member("alice", "User")])),
+            "b" => Some(nested_group("b", vec![member("a", "Group"), member("bob", "User"), member("b", "Group")])),
+            _ => None,
+        };
+
+        assert_eq!(root.flatten_members(resolver), vec!["bob", "alice"]);
+    }
+
+    #[test]
+    fn with_member_displays_fills_found_members_only() {
+        let mut group = nested_group("guides", vec![member("bjensen", "User"), member("jsmith", "User"), member("mpepperidge", "User")]);
+        group.members.as_mut().unwrap()[2].display = Some("Mary Pepperidge".to_string());
+
+        group.with_member_displays(|id| match id {
+            "bjensen" => Some("Babs Jensen".to_string()),
+            "jsmith" => Some("John Smith".to_string()),
+            _ => None,
+        });
+
+        let displays: Vec<Option<&str>> = group.members.iter().flatten().map(|member| member.display.as_deref()).collect();
+        assert_eq!(displays, vec![Some("Babs Jensen"), Some("John Smith"), Some("Mary Pepperidge")]);
+    }
+
+    #[test]
+    fn group_reads_from_and_writes_to_io() {
+        let group = nested_group("guides", vec![member("bjensen", "User")]);
+
+        let mut body = std::io::Cursor::new(Vec::new());
+        group.to_writer(&mut body).unwrap();
+        body.set_position(0);
+        let read = Group::from_reader(body).unwrap();
+
+        assert_eq!(read.id, "guides");
+        assert_eq!(read.member_ids(), vec!["bjensen"]);
+    }
+
+    #[test]
+    fn user_and_group_members_split_mixed_membership() {
+        let mut untyped = member("mpepperidge", "User");
+        untyped.type_ = None;
+        let group = nested_group("guides", vec![member("bjensen", "User"), member("trainees", "group"), untyped, member("svc", "Robot")]);
+
+        let user_ids: Vec<Option<&str>> = group.user_members().iter().map(|member| member.value.as_deref()).collect();
+        assert_eq!(user_ids, vec![Some("bjensen"), Some("mpepperidge"), Some("svc")]);
+        let group_ids: Vec<Option<&str>> = group.group_members().iter().map(|member| member.value.as_deref()).collect();
+        assert_eq!(group_ids, vec![Some("trainees")]);
+
+        let members = group.members.as_ref().unwrap();
+        assert_eq!(members[1].member_type(), Some(MemberType::Group));
+        assert_eq!(members[2].member_type(), None);
+        assert_eq!(members[3].member_type(), Some(MemberType::User));
+    }
+
+    #[test]
+    fn member_type_serializes_canonical_strings() {
+        assert_eq!(serde_json::to_value(MemberType::Group).unwrap(), serde_json::json!("Group"));
+        assert_eq!(serde_json::from_value::<MemberType>(serde_json::json!("user")).unwrap(), MemberType::User);
+    }
+
+    #[test]
+    fn display_shows_id_and_display_name_only() {
+        let group = nested_group("guides", vec![member("bjensen", "User")]);
+
+        assert_eq!(group.to_string(), "Group(id=guides, displayName=guides)");
+    }
+
+    #[test]
+    fn builder_builds_group_with_two_members() {
+        let group = Group::builder()
+            .id("e9e30dba-f08f-4109-8486-d5c6a331660a")
+            .display_name("Tour Guides")
+            .external_id("tour-guides")
+            .add_member("2819c223-7f76-453a-919d-413861904646", Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"), Some("Babs Jensen"))
+            .add_member("902c246b-6245-4190-8e05-00816be7344a", None, None)
+            .build()
+            .unwrap();
+
+        assert_eq!(group.schemas, vec![schema_uri::GROUP.to_string()]);
+        assert_eq!(group.external_id.as_deref(), Some("tour-guides"));
+        assert_eq!(group.member_ids(), vec!["2819c223-7f76-453a-919d-413861904646", "902c246b-6245-4190-8e05-00816be7344a"]);
+        let first = &group.members.as_ref().unwrap()[0];
+        assert_eq!(first.ref_.as_deref(), Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"));
+        assert_eq!(first.display.as_deref(), Some("Babs Jensen"));
+    }
+
+    #[test]
+    fn builder_validates_group() {
+        let missing_id = Group::builder().display_name("Tour Guides").build();
+        assert!(matches!(missing_id, Err(SCIMError::MissingRequiredField(field)) if field == "id"));
+
+        let duplicate_member = Group::builder().id("e9e30dba").display_name("Tour Guides").add_member("2819c223", None, None).add_member("2819c223", None, None).build();
+        assert!(matches!(duplicate_member, Err(SCIMError::InvalidFieldValue(_))));
+    }
+
+    #[cfg(feature = "server")]
+    #[test]
+    fn builder_generates_id() {
+        let group = Group::builder().generate_id().display_name("Tour Guides").build().unwrap();
+        assert_eq!(group.id.len(), 36);
+
+        let group = Group::builder().generate_id().id("e9e30dba").display_name("Tour Guides").build().unwrap();
+        assert_eq!(group.id, "e9e30dba");
+    }
+}
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
-
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 use crate::models::group::Group;
 use crate::models::resource_types::ResourceType;
+use crate::models::schema_uri;
 use crate::models::scim_schema::Schema;
 use crate::models::user::User;
+use crate::utils::error::{from_json_value, SCIMError};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SearchRequest {
     pub schemas: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -24,7 +25,7 @@ pub struct SearchRequest {
 impl Default for SearchRequest {
     fn default() -> Self {
         SearchRequest {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:SearchRequest".to_string()],
+            schemas: vec![schema_uri::SEARCH_REQUEST.to_string()],
             attributes: None,
             excluded_attributes: None,
             filter: "".to_string(),
@@ -35,6 +36,7 @@ impl Default for SearchRequest {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ListQuery {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub filter: Option<String>,
@@ -61,6 +63,7 @@ impl Default for ListQuery {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub enum Resource {
     User(Box<User>),
@@ -69,8 +72,68 @@ pub enum Resource {
     ResourceType(Box<ResourceType>),
 }
 
+/// Converts a JSON string into a `Resource`, choosing the variant from the core schema URN in `schemas`.
+///
+/// Deserializing a `Resource` directly tries each variant in turn, so an ambiguous document can end up as the
+/// wrong type. This conversion instead inspects `schemas` (ignoring extension URNs) and deserializes into the
+/// matching model.
+///
+/// # Errors
+///
+/// * `SCIMError::MissingRequiredField` - If the document has no `schemas` array.
+/// * `SCIMError::UnsupportedSchema` - If `schemas` does not contain a supported core schema URN.
+/// * `SCIMError::DeserializationError` - If the JSON is malformed or does not match the model for its schema.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::Resource;
+///
+/// let json = r#"{
+///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+///     "id": "e9e30dba-f08f-4109-8486-d5c6a331660a",
+///     "displayName": "Tour Guides"
+/// }"#;
+///
+/// match Resource::try_from(json) {
+///     Ok(Resource::Group(group)) => println!("Group: {}", group.display_name),
+///     Ok(other) => println!("Another resource: {:?}", other),
+///     Err(e) => println!("Error converting from JSON to Resource: {}", e),
+/// }
+/// ```
+impl TryFrom<&str> for Resource {
+    type Error = SCIMError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value: Value = serde_json::from_str(value).map_err(SCIMError::DeserializationError)?;
+        let schemas: Vec<String> = match value.get("schemas") {
+            Some(schemas) => from_json_value(schemas.clone())?,
+            None => return Err(SCIMError::MissingRequiredField("schemas".to_string())),
+        };
+        let has_schema = |uri: &str| schemas.iter().any(|schema| schema.eq_ignore_ascii_case(uri));
+
+        let resource = if has_schema(schema_uri::USER) {
+            Resource::User(Box::new(from_json_value(value)?))
+        } else if has_schema(schema_uri::GROUP) {
+            Resource::Group(Box::new(from_json_value(value)?))
+        } else if has_schema(schema_uri::RESOURCE_TYPE) {
+            Resource::ResourceType(Box::new(from_json_value(value)?))
+        } else if has_schema(schema_uri::SCHEMA) {
+            Resource::Schema(Box::new(from_json_value(value)?))
+        } else {
+            return Err(SCIMError::UnsupportedSchema(schemas.join(", ")));
+        };
+        Ok(resource)
+    }
+}
+
+/// Represents a SCIM list response (RFC 7644 §3.4.2).
+///
+/// The resource type defaults to the heterogeneous `Resource` enum, but any serializable model can be used,
+/// e.g. `ListResponse<User>` for the `/Users` endpoint.
 #[derive(Serialize, Deserialize, Debug)]
-pub struct ListResponse {
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ListResponse<T = Resource> {
     #[serde(rename = "itemsPerPage")]
     pub items_per_page: i64,
     #[serde(rename = "totalResults")]
@@ -79,23 +142,52 @@ pub struct ListResponse {
     pub start_index: i64,
     pub schemas: Vec<String>,
     #[serde(rename = "Resources")]
-    pub resources: Vec<Resource>,
+    pub resources: Vec<T>,
 }
 
-impl Default for ListResponse {
+impl<T> Default for ListResponse<T> {
     fn default() -> Self {
         ListResponse {
             items_per_page: 0,
             total_results: 0,
             start_index: 1,
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:ListResponse".to_string()],
+            schemas: vec![schema_uri::LIST_RESPONSE.to_string()],
             resources: vec![],
         }
     }
 }
 
+impl<T> ListResponse<T> {
+    /// Creates a list response for one page of results.
+    ///
+    /// `items_per_page` is set to the number of resources in the page, while `total_results` is the total number
+    /// of matching resources across all pages.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use scim_v2::models::others::ListResponse;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let users = vec![User { user_name: "bjensen@example.com".to_string(), ..Default::default() }];
+    /// let response = ListResponse::new(users, 10, 1);
+    /// assert_eq!(response.items_per_page, 1);
+    /// assert_eq!(response.total_results, 10);
+    /// ```
+    pub fn new(resources: Vec<T>, total_results: i64, start_index: i64) -> Self {
+        ListResponse {
+            items_per_page: resources.len() as i64,
+            total_results,
+            start_index,
+            resources,
+            ..Default::default()
+        }
+    }
+}
+
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PatchOp {
     pub schemas: Vec<String>,
     #[serde(rename = "Operations")]
@@ -105,26 +197,284 @@ pub struct PatchOp {
 impl Default for PatchOp {
     fn default() -> Self {
         PatchOp {
-            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:PatchOp".to_string()],
+            schemas: vec![schema_uri::PATCH_OP.to_string()],
             operations: vec![PatchOperations::default()],
         }
     }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct PatchOperations {
     pub op: String,
-    pub value: HashMap<String, Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Value>,
 }
 
 impl Default for PatchOperations {
     fn default() -> Self {
         PatchOperations {
             op: "".to_string(),
-            value: HashMap::new(),
+            path: None,
+            value: None,
+        }
+    }
+}
+
+/// A builder for the `PatchOp` body of a SCIM PATCH request (RFC 7644 §3.5.2).
+///
+/// Operations are emitted in the order they are added, each with a lowercase `op`. The PatchOp schema URN is
+/// always present in `schemas`.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::others::PatchOp;
+/// use serde_json::json;
+///
+/// let body = PatchOp::builder()
+///     .replace("displayName", json!("Babs"))
+///     .add("emails", json!([{"value": "babs@jensen.org", "type": "home"}]))
+///     .remove("emails[type eq \"work\"]")
+///     .build_json();
+///
+/// match body {
+///     Ok(json) => println!("PATCH body: {}", json),
+///     Err(e) => println!("Serialization error: {}", e),
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct PatchOpBuilder {
+    operations: Vec<PatchOperations>,
+}
+
+impl PatchOpBuilder {
+    fn operation(mut self, op: &str, path: Option<String>, value: Option<Value>) -> Self {
+        self.operations.push(PatchOperations { op: op.to_string(), path, value });
+        self
+    }
+
+    /// Adds an `add` operation for `value` at `path`.
+    pub fn add(self, path: impl Into<String>, value: Value) -> Self {
+        self.operation("add", Some(path.into()), Some(value))
+    }
+
+    /// Adds a `replace` operation for `value` at `path`.
+    pub fn replace(self, path: impl Into<String>, value: Value) -> Self {
+        self.operation("replace", Some(path.into()), Some(value))
+    }
+
+    /// Adds a `remove` operation for the attribute or values selected by `path`.
+    pub fn remove(self, path: impl Into<String>) -> Self {
+        self.operation("remove", Some(path.into()), None)
+    }
+
+    /// Returns the built `PatchOp`.
+    pub fn build(self) -> PatchOp {
+        PatchOp {
+            schemas: vec![schema_uri::PATCH_OP.to_string()],
+            operations: self.operations,
         }
     }
+
+    /// Builds the `PatchOp` and serializes it to a JSON string.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the `PatchOp` cannot be serialized.
+    pub fn build_json(self) -> Result<String, SCIMError> {
+        serde_json::to_string(&self.build()).map_err(SCIMError::SerializationError)
+    }
+}
+
+impl PatchOp {
+    /// Returns a `PatchOpBuilder` with no operations.
+    pub fn builder() -> PatchOpBuilder {
+        PatchOpBuilder::default()
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn list_response_of_users_serializes_with_spec_field_names() {
+        let users = vec![
+            User {
+                id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+                user_name: "bjensen".to_string(),
+                ..Default::default()
+            },
+            User {
+                id: Some("c75ad752-64ae-4823-840d-ffa80929976c".to_string()),
+                user_name: "jsmith".to_string(),
+                ..Default::default()
+            },
+        ];
+        let response = ListResponse::new(users, 2, 1);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+            "totalResults": 2,
+            "itemsPerPage": 2,
+            "startIndex": 1,
+            "Resources": [
+                {
+                    "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                    "id": "2819c223-7f76-453a-919d-413861904646",
+                    "userName": "bjensen"
+                },
+                {
+                    "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                    "id": "c75ad752-64ae-4823-840d-ffa80929976c",
+                    "userName": "jsmith"
+                }
+            ]
+        }));
+    }
+
+    #[test]
+    fn list_response_with_no_results_still_emits_resources() {
+        let response: ListResponse<User> = ListResponse::new(vec![], 0, 1);
+
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+            "totalResults": 0,
+            "itemsPerPage": 0,
+            "startIndex": 1,
+            "Resources": []
+        }));
+    }
+
+    #[test]
+    fn list_response_deserializes_typed_resources() {
+        let json = r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+            "totalResults": 5,
+            "itemsPerPage": 1,
+            "startIndex": 3,
+            "Resources": [{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}]
+        }"#;
+
+        let response: ListResponse<User> = serde_json::from_str(json).unwrap();
+        assert_eq!(response.total_results, 5);
+        assert_eq!(response.start_index, 3);
+        assert_eq!(response.resources[0].user_name, "bjensen");
+    }
+
+    #[test]
+    fn resource_try_from_classifies_user() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen",
+            "displayName": "Babs Jensen"
+        }"#;
+
+        match Resource::try_from(json_data) {
+            Ok(Resource::User(user)) => assert_eq!(user.user_name, "bjensen"),
+            other => panic!("expected a User, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resource_try_from_classifies_group() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "id": "e9e30dba-f08f-4109-8486-d5c6a331660a",
+            "displayName": "Tour Guides"
+        }"#;
 
+        match Resource::try_from(json_data) {
+            Ok(Resource::Group(group)) => assert_eq!(group.display_name, "Tour Guides"),
+            other => panic!("expected a Group, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn resource_try_from_rejects_unknown_schema() {
+        let json_data = r#"{
+            "schemas": ["urn:example:schemas:core:1.0:Device"],
+            "id": "c5b2c3a1"
+        }"#;
+
+        match Resource::try_from(json_data) {
+            Err(SCIMError::UnsupportedSchema(schema)) => assert_eq!(schema, "urn:example:schemas:core:1.0:Device"),
+            other => panic!("expected UnsupportedSchema, got {:?}", other),
+        }
+        assert!(matches!(Resource::try_from(r#"{"id": "c5b2c3a1"}"#), Err(SCIMError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn resource_try_from_names_failing_attribute() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+            "id": "e9e30dba",
+            "displayName": "Tour Guides",
+            "members": [{"value": 2819}]
+        }"#;
+
+        match Resource::try_from(json_data) {
+            Err(SCIMError::DeserializationError(err)) => assert!(err.to_string().starts_with("members[0].value: "), "{}", err),
+            other => panic!("expected DeserializationError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn patch_op_builder_matches_rfc_example() {
+        let json = PatchOp::builder()
+            .add("members", json!([{
+                "display": "Babs Jensen",
+                "$ref": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646",
+                "value": "2819c223-7f76-453a-919d-413861904646"
+            }]))
+            .build_json()
+            .unwrap();
+
+        let serialized: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(serialized, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {
+                    "op": "add",
+                    "path": "members",
+                    "value": [
+                        {
+                            "display": "Babs Jensen",
+                            "$ref": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646",
+                            "value": "2819c223-7f76-453a-919d-413861904646"
+                        }
+                    ]
+                }
+            ]
+        }));
+    }
+
+    #[test]
+    fn patch_op_builder_emits_operations_in_order() {
+        let json = PatchOp::builder()
+            .replace("displayName", json!("Babs"))
+            .add("emails", json!([{"value": "babs@jensen.org", "type": "home"}]))
+            .remove("emails[type eq \"work\"]")
+            .build_json()
+            .unwrap();
 
+        let serialized: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(serialized, json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {"op": "replace", "path": "displayName", "value": "Babs"},
+                {"op": "add", "path": "emails", "value": [{"value": "babs@jensen.org", "type": "home"}]},
+                {"op": "remove", "path": "emails[type eq \"work\"]"}
+            ]
+        }));
+    }
+}
@@ -0,0 +1,93 @@
+//! The `ScimResource` trait, implemented by the resource models so that servers can handle them generically.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use scim_v2::models::group::Group;
+//! use scim_v2::models::resource::ScimResource;
+//! use scim_v2::models::user::User;
+//!
+//! let resources: Vec<Box<dyn ScimResource>> = vec![Box::new(User::default()), Box::new(Group::default())];
+//! for resource in &resources {
+//!     println!("{} {:?}", resource.resource_type(), resource.id());
+//! }
+//! ```
+
+use crate::models::scim_schema::Meta;
+use crate::utils::error::SCIMError;
+
+/// A SCIM resource (RFC 7643 §3): a document with `schemas`, an `id` and `meta`.
+///
+/// The trait is object safe, so different resource types can be held together as `Box<dyn ScimResource>`.
+pub trait ScimResource {
+    /// Returns the schema URNs the resource lists in `schemas`.
+    fn schemas(&self) -> &[String];
+
+    /// Returns the resource's id, if it has one.
+    fn id(&self) -> Option<&str>;
+
+    /// Sets the resource's id.
+    fn set_id(&mut self, id: String);
+
+    /// Returns the resource's `meta`, if it has one.
+    fn meta(&self) -> Option<&Meta>;
+
+    /// Returns the resource's `meta` for modification, if it has one.
+    fn meta_mut(&mut self) -> Option<&mut Meta>;
+
+    /// Replaces the resource's `meta`.
+    fn set_meta(&mut self, meta: Meta);
+
+    /// Returns the resource type reported in `meta.resourceType`, e.g. `User`.
+    fn resource_type(&self) -> &'static str;
+
+    /// Validates the resource, as the resource type's own `validate` method does.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of the resource type's `validate`, e.g. `SCIMError::MissingRequiredField`.
+    fn validate(&self) -> Result<(), SCIMError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::group::Group;
+    use crate::models::schema_uri;
+    use crate::models::user::User;
+
+    #[test]
+    fn trait_objects_report_their_resource_types() {
+        let mut resources: Vec<Box<dyn ScimResource>> = vec![
+            Box::new(User { user_name: "bjensen".to_string(), ..Default::default() }),
+            Box::new(Group { display_name: "Tour Guides".to_string(), ..Default::default() }),
+        ];
+        for (i, resource) in resources.iter_mut().enumerate() {
+            resource.set_id(format!("id-{}", i));
+            resource.set_meta(Meta { resource_type: Some(resource.resource_type().to_string()), ..Default::default() });
+        }
+
+        let types: Vec<&str> = resources.iter().map(|resource| resource.resource_type()).collect();
+        assert_eq!(types, vec!["User", "Group"]);
+        let ids: Vec<Option<&str>> = resources.iter().map(|resource| resource.id()).collect();
+        assert_eq!(ids, vec![Some("id-0"), Some("id-1")]);
+        assert_eq!(resources[0].schemas(), &[schema_uri::USER.to_string()]);
+        assert_eq!(resources[1].schemas(), &[schema_uri::GROUP.to_string()]);
+        assert_eq!(resources[1].meta().unwrap().resource_type.as_deref(), Some("Group"));
+        resources[0].meta_mut().unwrap().version = Some("W/\"1\"".to_string());
+        assert_eq!(resources[0].meta().unwrap().version.as_deref(), Some("W/\"1\""));
+    }
+
+    #[test]
+    fn validate_dispatches_through_trait_objects() {
+        let resources: Vec<Box<dyn ScimResource>> = vec![
+            Box::new(User::default()),
+            Box::new(Group { display_name: "Tour Guides".to_string(), ..Default::default() }),
+        ];
+
+        assert!(matches!(resources[0].validate(), Err(SCIMError::MissingRequiredField(_))));
+        assert!(resources[1].validate().is_ok());
+    }
+}
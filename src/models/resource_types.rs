@@ -1,9 +1,15 @@
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
+use crate::models::others::ListResponse;
+use crate::models::schema_uri;
 use crate::models::scim_schema::Meta;
-use crate::utils::error::SCIMError;
+use crate::utils::error::{from_json_reader, from_json_str, from_json_value, SCIMError};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ResourceType {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<String>,
@@ -33,6 +39,7 @@ impl Default for ResourceType {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SchemaExtension {
     pub schema: String,
     pub required: bool,
@@ -47,6 +54,51 @@ impl Default for SchemaExtension {
     }
 }
 
+impl ResourceType {
+    /// Returns the canonical User resource type (RFC 7643 §8.6), declaring the enterprise extension as optional.
+    ///
+    /// `meta.location` depends on where the server is deployed and is left unset.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::resource_types::ResourceType;
+    ///
+    /// let user = ResourceType::user();
+    /// assert_eq!(user.endpoint, "/Users");
+    /// assert!(!user.schema_extensions.unwrap()[0].required);
+    /// ```
+    pub fn user() -> Self {
+        ResourceType {
+            id: Some("User".to_string()),
+            name: "User".to_string(),
+            description: Some("User Account".to_string()),
+            endpoint: "/Users".to_string(),
+            schema: schema_uri::USER.to_string(),
+            schema_extensions: Some(vec![SchemaExtension {
+                schema: schema_uri::ENTERPRISE_USER.to_string(),
+                required: false,
+            }]),
+            meta: Some(Meta { resource_type: Some("ResourceType".to_string()), ..Default::default() }),
+        }
+    }
+
+    /// Returns the canonical Group resource type (RFC 7643 §8.6).
+    ///
+    /// `meta.location` depends on where the server is deployed and is left unset.
+    pub fn group() -> Self {
+        ResourceType {
+            id: Some("Group".to_string()),
+            name: "Group".to_string(),
+            description: Some("Group".to_string()),
+            endpoint: "/Groups".to_string(),
+            schema: schema_uri::GROUP.to_string(),
+            schema_extensions: None,
+            meta: Some(Meta { resource_type: Some("ResourceType".to_string()), ..Default::default() }),
+        }
+    }
+}
+
 /// Returns a vector of `ResourceType` instances based on the provided resource type names.
 ///
 /// This function creates `ResourceType` instances for "user" and "group" with default values if their names are included in the `resource_type_names` vector.
@@ -87,48 +139,27 @@ pub fn get_resource_types(mut resource_type_names: Vec<&str>) -> Result<Vec<Reso
     for resource_type_name in resource_type_names {
         match resource_type_name {
             "user" => {
-                let user_resource_type = ResourceType {
-                    id: Some("User".to_string()),
-                    name: "User".to_string(),
-                    endpoint: "/Users".to_string(),
-                    description: Some("User Account".to_string()),
-                    schema: "urn:ietf:params:scim:schemas:core:2.0:User".to_string(),
-                    schema_extensions: if has_enterprise_user {
-                        Some(vec![
-                            SchemaExtension {
-                                schema: "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User".to_string(),
-                                required: true,
-                            },
-                        ])
-                    } else {
-                        None
-                    },
-                    meta: Some(Meta {
-                        location: Some("https://example.com/v2/ResourceTypes/User".to_string()),
-                        resource_type: Some("ResourceType".to_string()),
-                        created: None,
-                        last_modified: None,
-                        version: None,
-                    }),
+                let mut user_resource_type = ResourceType::user();
+                user_resource_type.schema_extensions = if has_enterprise_user {
+                    Some(vec![SchemaExtension {
+                        schema: schema_uri::ENTERPRISE_USER.to_string(),
+                        required: true,
+                    }])
+                } else {
+                    None
                 };
+                user_resource_type.meta = Some(Meta {
+                    location: Some("https://example.com/v2/ResourceTypes/User".to_string()),
+                    ..user_resource_type.meta.unwrap_or_default()
+                });
                 resource_types.push(user_resource_type);
             }
             "group" => {
-                let group_resource_type = ResourceType {
-                    id: Some("Group".to_string()),
-                    name: "Group".to_string(),
-                    endpoint: "/Groups".to_string(),
-                    description: Some("Group".to_string()),
-                    schema: "urn:ietf:params:scim:schemas:core:2.0:Group".to_string(),
-                    schema_extensions: None,
-                    meta: Some(Meta {
-                        location: Some("https://example.com/v2/ResourceTypes/Group".to_string()),
-                        resource_type: Some("ResourceType".to_string()),
-                        created: None,
-                        last_modified: None,
-                        version: None,
-                    }),
-                };
+                let mut group_resource_type = ResourceType::group();
+                group_resource_type.meta = Some(Meta {
+                    location: Some("https://example.com/v2/ResourceTypes/Group".to_string()),
+                    ..group_resource_type.meta.unwrap_or_default()
+                });
                 resource_types.push(group_resource_type);
             }
             _ => return Err(SCIMError::ResourceTypeNotFound(resource_type_name.to_string())),
@@ -137,6 +168,44 @@ pub fn get_resource_types(mut resource_type_names: Vec<&str>) -> Result<Vec<Reso
     Ok(resource_types)
 }
 
+/// Parses a `/ResourceTypes` response, a `ListResponse` of resource types, and returns its `Resources`.
+///
+/// Each resource type is checked with `ResourceType::validate`.
+///
+/// # Errors
+///
+/// * `SCIMError::DeserializationError` - If `json` is not a valid `ListResponse` of resource types.
+/// * `SCIMError::MissingRequiredField` - If a resource type lacks a required field. The field is reported with the
+///   index of the resource type, e.g. `Resources[1].endpoint`.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::resource_types::json_to_resource_types;
+///
+/// let json = r#"{
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+///     "totalResults": 1,
+///     "itemsPerPage": 1,
+///     "startIndex": 1,
+///     "Resources": [
+///         {"id": "Group", "name": "Group", "endpoint": "/Groups", "schema": "urn:ietf:params:scim:schemas:core:2.0:Group"}
+///     ]
+/// }"#;
+///
+/// let resource_types = json_to_resource_types(json).unwrap();
+/// assert_eq!(resource_types[0].endpoint, "/Groups");
+/// ```
+pub fn json_to_resource_types(json: &str) -> Result<Vec<ResourceType>, SCIMError> {
+    let response: ListResponse<ResourceType> = from_json_str(json)?;
+    for (index, resource_type) in response.resources.iter().enumerate() {
+        resource_type.validate().map_err(|error| match error {
+            SCIMError::MissingRequiredField(field) => SCIMError::MissingRequiredField(format!("Resources[{}].{}", index, field)),
+            error => error,
+        })?;
+    }
+    Ok(response.resources)
+}
 
 /// Converts a JSON string into a `ResourceType` struct.
 ///
@@ -184,7 +253,20 @@ impl TryFrom<&str> for ResourceType {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `ResourceType` struct, avoiding a round trip through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON value cannot be converted into a `ResourceType` object.
+impl TryFrom<Value> for ResourceType {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value)
     }
 }
 
@@ -232,6 +314,45 @@ impl ResourceType {
         }
         Ok(())
     }
+
+    /// Validates a resource type, additionally checking that every schema it references is known.
+    ///
+    /// This function performs the same checks as `validate`, then checks that the primary `schema` and the
+    /// `schema` of each entry in `schema_extensions` appear in `known_schemas` (typically the ids of the schemas
+    /// served at `/Schemas`).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the resource type is valid.
+    /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::UnsupportedSchema)` - Naming the first referenced schema that is not in `known_schemas`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::resource_types::ResourceType;
+    ///
+    /// let resource_type = ResourceType {
+    ///     name: "User".to_string(),
+    ///     endpoint: "/Users".to_string(),
+    ///     schema: "urn:ietf:params:scim:schemas:core:2.0:User".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let known_schemas = vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()];
+    ///
+    /// assert!(resource_type.validate_strict(&known_schemas).is_ok());
+    /// ```
+    pub fn validate_strict(&self, known_schemas: &[String]) -> Result<(), SCIMError> {
+        self.validate()?;
+        let extensions = self.schema_extensions.iter().flatten().map(|extension| &extension.schema);
+        for schema in std::iter::once(&self.schema).chain(extensions) {
+            if !known_schemas.contains(schema) {
+                return Err(SCIMError::UnsupportedSchema(schema.clone()));
+            }
+        }
+        Ok(())
+    }
+
     /// Serializes the `ResourceType` instance to a JSON string, using the custom SCIMError for error handling.
     ///
     /// # Returns
@@ -308,7 +429,21 @@ impl ResourceType {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        from_json_str(json)
+    }
+
+    /// Deserializes a `ResourceType` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `ResourceType`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `ResourceType` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
     }
 }
 
@@ -319,6 +454,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn resource_type_deserialization_user() {
         let json_data = r#"{
                 "schemas":
@@ -417,4 +553,131 @@ mod tests {
         std::assert_eq!(group_resource_type.schema, "urn:ietf:params:scim:schemas:core:2.0:Group");
         assert!(group_resource_type.schema_extensions.is_none());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn get_resource_types_matches_canonical_constructors() {
+        let resource_types = get_resource_types(vec!["user", "group"]).unwrap();
+
+        let (user, group) = (ResourceType::user(), ResourceType::group());
+        for (built, canonical) in resource_types.iter().zip([&user, &group]) {
+            std::assert_eq!(built.id, canonical.id);
+            std::assert_eq!(built.name, canonical.name);
+            std::assert_eq!(built.description, canonical.description);
+            std::assert_eq!(built.endpoint, canonical.endpoint);
+            std::assert_eq!(built.schema, canonical.schema);
+        }
+        assert!(resource_types[0].schema_extensions.is_none());
+        std::assert_eq!(
+            resource_types[1].meta.as_ref().and_then(|meta| meta.location.as_deref()),
+            Some("https://example.com/v2/ResourceTypes/Group")
+        );
+    }
+
+    fn known_schemas() -> Vec<String> {
+        vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string(), schema_uri::GROUP.to_string()]
+    }
+
+    #[test]
+    fn validate_strict_succeeds_when_all_schemas_known() {
+        let resource_types = get_resource_types(vec!["user", "group", "enterprise_user"]).unwrap();
+
+        for resource_type in resource_types {
+            assert!(resource_type.validate_strict(&known_schemas()).is_ok());
+        }
+    }
+
+    #[test]
+    fn validate_strict_fails_for_unknown_extension_schema() {
+        let resource_type = ResourceType {
+            name: "User".to_string(),
+            endpoint: "/Users".to_string(),
+            schema: schema_uri::USER.to_string(),
+            schema_extensions: Some(vec![SchemaExtension {
+                schema: "urn:example:params:scim:schemas:extension:custom:2.0:User".to_string(),
+                required: false,
+            }]),
+            ..Default::default()
+        };
+
+        match resource_type.validate_strict(&known_schemas()) {
+            Err(SCIMError::UnsupportedSchema(schema)) => assert_eq!(schema, "urn:example:params:scim:schemas:extension:custom:2.0:User"),
+            other => panic!("expected UnsupportedSchema, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn canonical_constructors_match_rfc_example() {
+        // RFC 7643 §8.6, less `schemas` (not modelled by `ResourceType`) and `meta.location` (deployment specific),
+        // and with the enterprise extension optional.
+        let expected = serde_json::json!([
+            {
+                "id": "User",
+                "name": "User",
+                "endpoint": "/Users",
+                "description": "User Account",
+                "schema": "urn:ietf:params:scim:schemas:core:2.0:User",
+                "schemaExtensions": [
+                    {
+                        "schema": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User",
+                        "required": false
+                    }
+                ],
+                "meta": {"resourceType": "ResourceType"}
+            },
+            {
+                "id": "Group",
+                "name": "Group",
+                "endpoint": "/Groups",
+                "description": "Group",
+                "schema": "urn:ietf:params:scim:schemas:core:2.0:Group",
+                "meta": {"resourceType": "ResourceType"}
+            }
+        ]);
+
+        let serialized = serde_json::to_value([ResourceType::user(), ResourceType::group()]).unwrap();
+
+        assert_eq!(serialized, expected);
+        assert!(ResourceType::user().validate().is_ok());
+        assert!(ResourceType::group().validate().is_ok());
+    }
+
+    fn resource_types_response(resources: &str) -> String {
+        format!(
+            r#"{{
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+                "totalResults": 2,
+                "itemsPerPage": 2,
+                "startIndex": 1,
+                "Resources": {}
+            }}"#,
+            resources
+        )
+    }
+
+    #[test]
+    fn json_to_resource_types_parses_list_response() {
+        let json = resource_types_response(&format!("[{}, {}]", ResourceType::user().serialize().unwrap(), ResourceType::group().serialize().unwrap()));
+
+        let resource_types = json_to_resource_types(&json).unwrap();
+
+        let endpoints: Vec<&str> = resource_types.iter().map(|resource_type| resource_type.endpoint.as_str()).collect();
+        assert_eq!(endpoints, vec!["/Users", "/Groups"]);
+        assert_eq!(resource_types[0].schema_extensions.as_ref().unwrap()[0].schema, schema_uri::ENTERPRISE_USER);
+    }
+
+    #[test]
+    fn json_to_resource_types_reports_index_of_invalid_entry() {
+        let json = resource_types_response(
+            r#"[
+                {"name": "User", "endpoint": "/Users", "schema": "urn:ietf:params:scim:schemas:core:2.0:User"},
+                {"name": "Group", "endpoint": "", "schema": "urn:ietf:params:scim:schemas:core:2.0:Group"}
+            ]"#,
+        );
+
+        match json_to_resource_types(&json) {
+            Err(SCIMError::MissingRequiredField(field)) => assert_eq!(field, "Resources[1].endpoint"),
+            other => panic!("expected MissingRequiredField, got {:?}", other),
+        }
+        assert!(matches!(json_to_resource_types(r#"{"Resources": 42}"#), Err(SCIMError::DeserializationError(_))));
+    }
+}
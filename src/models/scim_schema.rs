@@ -1,25 +1,268 @@
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
 
 use crate::{ENTERPRISE_USER_SCHEMA, GROUP_SCHEMA, USER_SCHEMA};
-use crate::utils::error::SCIMError;
+use crate::utils::error::{from_json_reader, from_json_str, SCIMError};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The type used for `Meta` timestamps.
+///
+/// With the `chrono` feature enabled this is `chrono::DateTime<Utc>`, (de)serialized as an RFC 3339
+/// `xsd:dateTime` string. Otherwise the timestamp is kept as the raw `String`.
+#[cfg(feature = "chrono")]
+pub type DateTime = chrono::DateTime<chrono::Utc>;
+#[cfg(not(feature = "chrono"))]
+pub type DateTime = String;
+
+/// Returns the current time as a `Meta` timestamp.
+#[cfg(feature = "chrono")]
+pub(crate) fn now() -> DateTime {
+    DateTime::from(std::time::SystemTime::now())
+}
+
+/// Returns the current time as a `Meta` timestamp, formatted as an RFC 3339 UTC `xsd:dateTime`.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn now() -> DateTime {
+    let elapsed = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap_or_default();
+    let (days, seconds) = ((elapsed.as_secs() / 86_400) as i64, elapsed.as_secs() % 86_400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60,
+        seconds % 60
+    )
+}
+
+/// Converts days since the Unix epoch to a proleptic Gregorian `(year, month, day)` (Howard Hinnant's
+/// `civil_from_days`). Without the `chrono` feature, this and `days_from_civil` are the crate's only calendar math.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    (year_of_era + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Converts a proleptic Gregorian date to days since the Unix epoch (Howard Hinnant's `days_from_civil`), the
+/// inverse of `civil_from_days`.
+#[cfg(not(feature = "chrono"))]
+pub(crate) fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let shifted_year = if month <= 2 { year - 1 } else { year };
+    let era = shifted_year.div_euclid(400);
+    let year_of_era = shifted_year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Meta {
     #[serde(rename = "resourceType", skip_serializing_if = "Option::is_none")]
     pub resource_type: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub created: Option<String>,
+    #[cfg_attr(feature = "chrono", serde(default, with = "datetime_format"))]
+    #[cfg_attr(all(feature = "chrono", feature = "schemars"), schemars(with = "Option<DateTime>"))]
+    pub created: Option<DateTime>,
     #[serde(rename = "lastModified", skip_serializing_if = "Option::is_none")]
-    pub last_modified: Option<String>,
+    #[cfg_attr(feature = "chrono", serde(default, with = "datetime_format"))]
+    #[cfg_attr(all(feature = "chrono", feature = "schemars"), schemars(with = "Option<DateTime>"))]
+    pub last_modified: Option<DateTime>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub location: Option<String>,
 }
 
+impl Meta {
+    /// Builds a resource's `location` URI, `<base_url>/<resource_type>/<id>`.
+    ///
+    /// Slashes at the joins are normalised, so a `base_url` with or without a trailing slash (and a
+    /// `resource_type` with or without surrounding slashes) gives the same result.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::scim_schema::Meta;
+    ///
+    /// let location = Meta::build_location("https://example.com/v2/", "Users", "2819c223-7f76-453a-919d-413861904646");
+    /// assert_eq!(location, "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646");
+    /// ```
+    pub fn build_location(base_url: &str, resource_type: &str, id: &str) -> String {
+        format!("{}/{}/{}", base_url.trim_end_matches('/'), resource_type.trim_matches('/'), id)
+    }
+}
+
+/// Rebuilds every object in `value` with its keys in sorted order, at every level of nesting.
+///
+/// `serde_json::Map` only sorts keys while the `preserve_order` feature is off, and Cargo feature unification
+/// means any crate in the build can turn it on. Serializing the result of this function is byte-stable regardless.
+pub(crate) fn canonicalize(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.into_iter().map(canonicalize).collect()),
+        serde_json::Value::Object(fields) => {
+            let sorted: std::collections::BTreeMap<String, serde_json::Value> =
+                fields.into_iter().map(|(key, field)| (key, canonicalize(field))).collect();
+            serde_json::Value::Object(sorted.into_iter().collect())
+        }
+        other => other,
+    }
+}
+
+/// Computes a weak entity tag (`W/"<hash>"`) for a resource's JSON representation, for use as `meta.version`.
+///
+/// `meta.version` itself is left out of the hash (and a `meta` holding nothing else is treated as absent) so
+/// that storing the result does not change it. The hash is a 64-bit FNV-1a over the canonical (key-sorted)
+/// JSON, which is stable across platforms and compiler versions.
+pub(crate) fn weak_etag(resource: &serde_json::Value) -> String {
+    let mut resource = resource.clone();
+    if let Some(fields) = resource.as_object_mut() {
+        if let Some(meta) = fields.get_mut("meta").and_then(serde_json::Value::as_object_mut) {
+            meta.remove("version");
+            if meta.is_empty() {
+                fields.remove("meta");
+            }
+        }
+    }
+
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in canonicalize(resource).to_string().bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("W/\"{:016x}\"", hash)
+}
+
+/// Reports whether an `If-Match` or `If-None-Match` header lists an entity tag equal to `version`.
+///
+/// Tags are compared weakly (RFC 7232 §2.3.2): a `W/` prefix on either side is ignored. The `*` wildcard is not
+/// handled here.
+///
+/// # Errors
+///
+/// Returns `SCIMError::RequestError` if the header is not a comma-separated list of quoted entity tags.
+pub(crate) fn etag_list_contains(header: &str, version: &str) -> Result<bool, SCIMError> {
+    fn opaque_tag(tag: &str) -> &str {
+        tag.strip_prefix("W/").unwrap_or(tag)
+    }
+
+    let malformed = || SCIMError::RequestError(format!("malformed entity tag list {}", header));
+    let version = opaque_tag(version.trim());
+    let mut rest = header.trim();
+    let mut found = false;
+    while !rest.is_empty() {
+        // Each opaque tag is a quoted string without inner quotes, but it may contain commas.
+        let tag_start = if rest.starts_with("W/") { 2 } else { 0 };
+        if !rest[tag_start..].starts_with('"') {
+            return Err(malformed());
+        }
+        let tag_end = rest[tag_start + 1..].find('"').ok_or_else(malformed)? + tag_start + 2;
+        found |= opaque_tag(&rest[..tag_end]) == version;
+        rest = rest[tag_end..].trim_start();
+        if let Some(next) = rest.strip_prefix(',') {
+            rest = next.trim_start();
+        } else if !rest.is_empty() {
+            return Err(malformed());
+        }
+    }
+    Ok(found)
+}
+
+/// Fills in `resourceType` and `location` when `meta` is absent or has no `resourceType`.
+///
+/// `location` is `<base_url>/<endpoint>/<id>`, and is only set if the resource has an id and no location yet.
+/// Timestamps and `version` are never touched.
+pub(crate) fn ensure_meta(meta: &mut Option<Meta>, resource_type: &str, endpoint: &str, base_url: &str, id: Option<&str>) {
+    let meta = meta.get_or_insert_with(Meta::default);
+    if meta.resource_type.as_deref().map_or(false, |resource_type| !resource_type.is_empty()) {
+        return;
+    }
+    meta.resource_type = Some(resource_type.to_string());
+    if let (None, Some(id)) = (&meta.location, id.filter(|id| !id.is_empty())) {
+        meta.location = Some(Meta::build_location(base_url, endpoint, id));
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod datetime_format {
+    use chrono::{DateTime, SecondsFormat, Utc};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_rfc3339_opts(SecondsFormat::AutoSi, true)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+        let value: Option<String> = Option::deserialize(deserializer)?;
+        value
+            .map(|value| {
+                DateTime::parse_from_rfc3339(&value)
+                    .map(|parsed| parsed.with_timezone(&Utc))
+                    .map_err(serde::de::Error::custom)
+            })
+            .transpose()
+    }
+}
+
+/// Whether and how an attribute may be modified (RFC 7643 §7, "mutability").
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Mutability {
+    /// The attribute SHALL NOT be modified.
+    ReadOnly,
+    /// The attribute MAY be updated and read at any time.
+    ReadWrite,
+    /// The attribute MAY be defined at resource creation or at record replacement, but SHALL NOT be updated afterwards.
+    Immutable,
+    /// The attribute MAY be updated at any time but is never returned.
+    WriteOnly,
+}
+
+/// When an attribute is returned in a response (RFC 7643 §7, "returned").
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Returned {
+    /// The attribute is always returned, regardless of the `attributes` or `excludedAttributes` parameters.
+    Always,
+    /// The attribute is never returned.
+    Never,
+    /// The attribute is returned by default unless excluded.
+    Default,
+    /// The attribute is returned only when explicitly requested.
+    Request,
+}
+
+/// How the service provider enforces uniqueness of an attribute value (RFC 7643 §7, "uniqueness").
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "camelCase")]
+pub enum Uniqueness {
+    /// Values are not required to be unique.
+    None,
+    /// The value SHOULD be unique within the context of the current service provider.
+    Server,
+    /// The value SHOULD be globally unique.
+    Global,
+}
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Schema {
     pub id: String,
     pub name: String,
@@ -29,6 +272,7 @@ pub struct Schema {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Attributes {
     pub name: String,
     #[serde(rename = "type")]
@@ -44,11 +288,11 @@ pub struct Attributes {
     #[serde(rename = "caseExact", skip_serializing_if = "Option::is_none")]
     pub case_exact: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub mutability: Option<String>,
+    pub mutability: Option<Mutability>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub returned: Option<String>,
+    pub returned: Option<Returned>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub uniqueness: Option<String>,
+    pub uniqueness: Option<Uniqueness>,
     #[serde(rename = "subAttributes", skip_serializing_if = "Option::is_none")]
     pub sub_attributes: Option<Vec<SubAttributes>>,
     #[serde(rename = "referenceTypes", skip_serializing_if = "Option::is_none")]
@@ -56,6 +300,7 @@ pub struct Attributes {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct SubAttributes {
     pub name: String,
     #[serde(rename = "type")]
@@ -71,15 +316,32 @@ pub struct SubAttributes {
     #[serde(rename = "caseExact", skip_serializing_if = "Option::is_none")]
     pub case_exact: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub mutability: Option<String>,
+    pub mutability: Option<Mutability>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub returned: Option<String>,
+    pub returned: Option<Returned>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub uniqueness: Option<String>,
+    pub uniqueness: Option<Uniqueness>,
     #[serde(rename = "referenceTypes", skip_serializing_if = "Option::is_none")]
     pub reference_types: Option<Vec<String>>,
 }
 
+/// Returns the canonical core User schema (`urn:ietf:params:scim:schemas:core:2.0:User`) as published in RFC 7643 §8.7.1.
+///
+/// This is the document a service provider serves at `/Schemas/urn:ietf:params:scim:schemas:core:2.0:User`.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::scim_schema::{core_user_schema, Mutability};
+///
+/// let schema = core_user_schema();
+/// let user_name = schema.attributes.iter().find(|attribute| attribute.name == "userName").unwrap();
+/// assert_eq!(user_name.mutability, Some(Mutability::ReadWrite));
+/// ```
+pub fn core_user_schema() -> Schema {
+    serde_json::from_str(USER_SCHEMA).expect("the embedded User schema is valid")
+}
+
 /// Retrieves a list of `Schema` instances based on the provided schema names.
 ///
 /// This function takes a vector of schema names as input and attempts to retrieve the corresponding `Schema` instances.
@@ -257,7 +519,7 @@ impl TryFrom<&str> for Schema {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
     }
 }
 
@@ -273,7 +535,7 @@ impl Schema {
     /// # Examples
     ///
     /// ```
-    /// use scim_v2::models::scim_schema::{Schema, Attributes, Meta};
+    /// use scim_v2::models::scim_schema::{Schema, Attributes, Meta, Mutability, Returned, Uniqueness};
     ///
     ///     let user = Schema {
     ///     id: "urn:ietf:params:scim:schemas:core:2.0:User".to_string(),
@@ -288,9 +550,9 @@ impl Schema {
     ///             required: Some(true),
     ///             canonical_values: None,
     ///             case_exact: Some(false),
-    ///             mutability: Some("readWrite".to_string()),
-    ///             returned: Some("default".to_string()),
-    ///             uniqueness: Some("server".to_string()),
+    ///             mutability: Some(Mutability::ReadWrite),
+    ///             returned: Some(Returned::Default),
+    ///             uniqueness: Some(Uniqueness::Server),
     ///             sub_attributes: None,
     ///             reference_types: None,
     ///         },
@@ -437,7 +699,21 @@ impl Schema {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        from_json_str(json)
+    }
+
+    /// Deserializes a `Schema` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `Schema`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `Schema` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
     }
 }
 
@@ -445,6 +721,40 @@ impl Schema {
 mod tests {
     use super::*;
 
+    #[test]
+    #[cfg(not(feature = "chrono"))]
+    fn civil_date_conversions_round_trip() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2011, 5, 13), 15_107);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(days_from_civil(2000, 2, 29)), (2000, 2, 29));
+        assert_eq!(days_from_civil(2100, 3, 1) - days_from_civil(2100, 2, 28), 1);
+
+        // Every day from 1600-01-01 to 2400-12-31, which covers each kind of leap year.
+        let (mut year, mut month, mut day) = (1600, 1, 1);
+        for days in days_from_civil(1600, 1, 1)..=days_from_civil(2400, 12, 31) {
+            assert_eq!(civil_from_days(days), (year, month, day));
+            assert_eq!(days_from_civil(year, month, day), days);
+            let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+            let days_in_month = match month {
+                2 if leap_year => 29,
+                2 => 28,
+                4 | 6 | 9 | 11 => 30,
+                _ => 31,
+            };
+            day += 1;
+            if day > days_in_month {
+                day = 1;
+                month += 1;
+                if month > 12 {
+                    month = 1;
+                    year += 1;
+                }
+            }
+        }
+    }
+
     #[test]
     fn get_schemas_returns_correct_schemas_for_valid_input() {
         let schemas = get_schemas(vec!["user"]).unwrap();
@@ -457,6 +767,60 @@ mod tests {
         assert_eq!(schemas[0].meta.location.as_ref(), Some(&"/v2/Schemas/urn:ietf:params:scim:schemas:core:2.0:User".to_string()));
     }
 
+    #[test]
+    fn meta_deserializes_with_only_resource_type() {
+        let meta: Meta = serde_json::from_str(r#"{"resourceType":"User"}"#).unwrap();
+        assert_eq!(meta.resource_type.as_deref(), Some("User"));
+        assert!(meta.created.is_none());
+        assert!(meta.last_modified.is_none());
+        assert!(meta.version.is_none());
+        assert!(meta.location.is_none());
+        assert_eq!(serde_json::to_string(&meta).unwrap(), r#"{"resourceType":"User"}"#);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn meta_timestamps_round_trip_as_datetime() {
+        use chrono::{TimeZone, Utc};
+
+        let json = r#"{"resourceType":"User","created":"2010-01-23T04:56:22Z","lastModified":"2011-05-13T04:42:34.123Z"}"#;
+        let meta: Meta = serde_json::from_str(json).unwrap();
+        assert_eq!(meta.created, Some(Utc.with_ymd_and_hms(2010, 1, 23, 4, 56, 22).unwrap()));
+        assert!(meta.last_modified > meta.created);
+        assert_eq!(serde_json::to_string(&meta).unwrap(), json);
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn meta_rejects_malformed_timestamp() {
+        let result: Result<Meta, _> = serde_json::from_str(r#"{"created":"23/01/2010"}"#);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn core_user_schema_serializes_to_published_json() {
+        let schema = core_user_schema();
+        let serialized: serde_json::Value = serde_json::from_str(&schema.serialize().unwrap()).unwrap();
+        let published: serde_json::Value = serde_json::from_str(USER_SCHEMA).unwrap();
+        assert_eq!(serialized, published);
+    }
+
+    #[test]
+    fn core_user_schema_exposes_typed_attribute_characteristics() {
+        let schema = core_user_schema();
+        let user_name = schema.attributes.iter().find(|attribute| attribute.name == "userName").unwrap();
+        assert_eq!(user_name.uniqueness, Some(Uniqueness::Server));
+        let password = schema.attributes.iter().find(|attribute| attribute.name == "password").unwrap();
+        assert_eq!(password.mutability, Some(Mutability::WriteOnly));
+        assert_eq!(password.returned, Some(Returned::Never));
+    }
+
+    #[test]
+    fn attribute_characteristics_reject_unknown_values() {
+        let result: Result<Mutability, _> = serde_json::from_str(r#""readMostly""#);
+        assert!(result.is_err());
+    }
+
     #[test]
     fn get_schemas_returns_error_for_invalid_input() {
         let result = get_schemas(vec!["invalid"]);
@@ -468,4 +832,31 @@ mod tests {
         let result = get_schemas(vec!["missing"]);
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn build_location_joins_segments_with_single_slashes() {
+        let id = "2819c223-7f76-453a-919d-413861904646";
+
+        assert_eq!(Meta::build_location("https://example.com/v2", "Users", id), format!("https://example.com/v2/Users/{}", id));
+        assert_eq!(Meta::build_location("https://example.com/v2/", "Users", id), format!("https://example.com/v2/Users/{}", id));
+        assert_eq!(
+            Meta::build_location("https://example.com/v2/", "/Groups/", "e9e30dba"),
+            "https://example.com/v2/Groups/e9e30dba"
+        );
+    }
+
+    #[cfg(not(feature = "chrono"))]
+    #[test]
+    fn now_formats_an_rfc3339_utc_timestamp() {
+        let timestamp = now();
+
+        assert_eq!(timestamp.len(), "2011-05-13T04:42:34Z".len());
+        assert!(timestamp.ends_with('Z'));
+        assert!(timestamp.as_str() > "2024-01-01T00:00:00Z");
+        let (date, time) = timestamp.trim_end_matches('Z').split_once('T').unwrap();
+        let date: Vec<u32> = date.split('-').map(|part| part.parse().unwrap()).collect();
+        let time: Vec<u32> = time.split(':').map(|part| part.parse().unwrap()).collect();
+        assert!((1..=12).contains(&date[1]) && (1..=31).contains(&date[2]));
+        assert!(time[0] < 24 && time[1] < 60 && time[2] < 60);
+    }
+}
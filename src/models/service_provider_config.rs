@@ -1,9 +1,13 @@
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 
 use crate::models::scim_schema::Meta;
-use crate::utils::error::SCIMError;
+use crate::utils::error::{from_json_reader, from_json_str, from_json_value, SCIMError};
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct ServiceProviderConfig {
     #[serde(rename = "documentationUri", skip_serializing_if = "Option::is_none")]
     pub documentation_uri: Option<String>,
@@ -44,6 +48,7 @@ impl Default for ServiceProviderConfig {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct AuthenticationScheme {
     pub name: String,
     #[serde(rename = "type")]
@@ -70,7 +75,15 @@ impl Default for AuthenticationScheme {
     }
 }
 
+impl AuthenticationScheme {
+    /// Returns `true` if the scheme is marked as the preferred one.
+    pub fn is_primary(&self) -> bool {
+        self.primary == Some(true)
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Filter {
     pub supported: bool,
     #[serde(rename = "maxResults")]
@@ -87,6 +100,7 @@ impl Default for Filter {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Bulk {
     pub supported: bool,
     #[serde(rename = "maxOperations")]
@@ -106,6 +120,7 @@ impl Default for Bulk {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Supported {
     pub supported: bool,
@@ -167,23 +182,67 @@ impl TryFrom<&str> for ServiceProviderConfig {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into a `ServiceProviderConfig` struct, avoiding a round trip through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON value cannot be converted into a `ServiceProviderConfig` object.
+impl TryFrom<Value> for ServiceProviderConfig {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value)
     }
 }
 
 impl ServiceProviderConfig {
-    /// Validates a service provider config.
+    /// Returns a config advertising the features this crate helps to implement.
+    ///
+    /// PATCH (`utils::patch`), filtering (`utils::filter`), sorting (`utils::query`) and ETags
+    /// (`User::compute_version`) are advertised as supported, as is bulk (`utils::bulk`) with a limit of 1000
+    /// operations and a 1 MiB payload. Filtering returns at most 200 results. Password changes are not
+    /// advertised, and `authentication_schemes` is left empty for the server to fill in.
+    ///
+    /// # Example
     ///
-    /// This function checks if the service provider config has `patch`, `bulk`, `filter`, `change_password`, `sort`, and `etag`. If any of these fields are missing, it returns an error.
+    /// ```
+    /// use scim_v2::models::service_provider_config::ServiceProviderConfig;
     ///
-    /// # Arguments
+    /// let config = ServiceProviderConfig::default_supported();
+    /// assert!(config.patch.supported);
+    /// assert!(config.validate().is_ok());
+    /// ```
+    pub fn default_supported() -> Self {
+        ServiceProviderConfig {
+            patch: Supported { supported: true },
+            bulk: Bulk {
+                supported: true,
+                ..Default::default()
+            },
+            filter: Filter {
+                supported: true,
+                max_results: 200,
+            },
+            sort: Supported { supported: true },
+            etag: Supported { supported: true },
+            ..Default::default()
+        }
+    }
+
+    /// Validates a service provider config.
     ///
-    /// * `config` - A reference to a ServiceProviderConfig instance.
+    /// A feature that is not supported is valid; only the limits of supported features are checked. A supported
+    /// `bulk` must allow at least one operation and a non-zero payload, and a supported `filter` must allow at
+    /// least one result.
     ///
     /// # Returns
     ///
     /// * `Ok(())` - If the service provider config is valid.
-    /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a supported feature has a limit that is not positive.
     ///
     /// # Example
     ///
@@ -202,27 +261,82 @@ impl ServiceProviderConfig {
     /// }
     /// ```
     pub fn validate(&self) -> Result<(), SCIMError> {
-        if !self.patch.supported {
-            return Err(SCIMError::MissingRequiredField("patch".to_string()));
-        }
-        if !self.bulk.supported {
-            return Err(SCIMError::MissingRequiredField("bulk".to_string()));
+        if self.bulk.supported && self.bulk.max_operations <= 0 {
+            return Err(SCIMError::InvalidFieldValue("bulk.maxOperations: must be positive".to_string()));
         }
-        if !self.filter.supported {
-            return Err(SCIMError::MissingRequiredField("filter".to_string()));
+        if self.bulk.supported && self.bulk.max_payload_size <= 0 {
+            return Err(SCIMError::InvalidFieldValue("bulk.maxPayloadSize: must be positive".to_string()));
         }
-        if !self.change_password.supported {
-            return Err(SCIMError::MissingRequiredField("change_password".to_string()));
+        if self.filter.supported && self.filter.max_results <= 0 {
+            return Err(SCIMError::InvalidFieldValue("filter.maxResults: must be positive".to_string()));
         }
-        if !self.sort.supported {
-            return Err(SCIMError::MissingRequiredField("sort".to_string()));
+        Ok(())
+    }
+
+    /// Validates a service provider config, additionally checking the advertised authentication schemes.
+    ///
+    /// This function performs the same checks as `validate`, then checks that `authentication_schemes` lists at
+    /// least one scheme, so that clients know how to authenticate, and that at most one of them is `primary`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the service provider config is valid.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a supported feature has a limit that is not positive, or more than
+    ///   one authentication scheme is primary.
+    /// * `Err(SCIMError::MissingRequiredField)` - If no authentication scheme is listed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::service_provider_config::{AuthenticationScheme, ServiceProviderConfig};
+    ///
+    /// let mut config = ServiceProviderConfig::default_supported();
+    /// assert!(config.validate_strict().is_err());
+    ///
+    /// config.authentication_schemes.push(AuthenticationScheme {
+    ///     name: "OAuth Bearer Token".to_string(),
+    ///     type_: "oauthbearertoken".to_string(),
+    ///     description: "Authentication scheme using the OAuth Bearer Token Standard".to_string(),
+    ///     spec_uri: "http://www.rfc-editor.org/info/rfc6750".to_string(),
+    ///     primary: Some(true),
+    ///     ..Default::default()
+    /// });
+    /// assert!(config.validate_strict().is_ok());
+    /// ```
+    pub fn validate_strict(&self) -> Result<(), SCIMError> {
+        self.validate()?;
+        if self.authentication_schemes.is_empty() {
+            return Err(SCIMError::MissingRequiredField("authenticationSchemes".to_string()));
         }
-        if !self.etag.supported {
-            return Err(SCIMError::MissingRequiredField("etag".to_string()));
+        if self.authentication_schemes.iter().filter(|scheme| scheme.is_primary()).count() > 1 {
+            return Err(SCIMError::InvalidFieldValue("authenticationSchemes: at most one scheme may be primary".to_string()));
         }
         Ok(())
     }
 
+    /// Returns the authentication scheme marked `primary`, or the first scheme if none is.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use scim_v2::models::service_provider_config::{AuthenticationScheme, ServiceProviderConfig};
+    ///
+    /// let mut config = ServiceProviderConfig::default();
+    /// assert!(config.primary_authentication_scheme().is_none());
+    ///
+    /// config.authentication_schemes = vec![
+    ///     AuthenticationScheme { type_: "httpbasic".to_string(), ..Default::default() },
+    ///     AuthenticationScheme { type_: "oauthbearertoken".to_string(), primary: Some(true), ..Default::default() },
+    /// ];
+    /// assert_eq!(config.primary_authentication_scheme().unwrap().type_, "oauthbearertoken");
+    /// ```
+    pub fn primary_authentication_scheme(&self) -> Option<&AuthenticationScheme> {
+        self.authentication_schemes
+            .iter()
+            .find(|scheme| scheme.is_primary())
+            .or_else(|| self.authentication_schemes.first())
+    }
+
     /// Serializes the `ServiceProviderConfig` instance to a JSON string, using the custom SCIMError for error handling.
     ///
     /// # Returns
@@ -304,7 +418,21 @@ impl ServiceProviderConfig {
     /// }
     /// ```
     pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+        from_json_str(json)
+    }
+
+    /// Deserializes a `ServiceProviderConfig` from a reader without buffering it into a string first.
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `ServiceProviderConfig`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `ServiceProviderConfig` as JSON into a writer.
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
     }
 }
 
@@ -383,4 +511,60 @@ mod tests {
         assert_eq!(http_scheme.documentation_uri, Some("http://example.com/help/httpBasic.html".to_string()));
         assert_eq!(http_scheme.type_, "httpbasic");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn default_supported_advertises_crate_features() {
+        let config = ServiceProviderConfig::default_supported();
+
+        let json: Value = serde_json::from_str(&config.serialize().unwrap()).unwrap();
+        assert_eq!(json["patch"], serde_json::json!({"supported": true}));
+        assert_eq!(json["bulk"], serde_json::json!({"supported": true, "maxOperations": 1000, "maxPayloadSize": 1048576}));
+        assert_eq!(json["filter"], serde_json::json!({"supported": true, "maxResults": 200}));
+        assert_eq!(json["changePassword"], serde_json::json!({"supported": false}));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_accepts_unsupported_features_and_checks_limits() {
+        assert!(ServiceProviderConfig::default().validate().is_ok());
+
+        let mut config = ServiceProviderConfig::default_supported();
+        config.filter.max_results = 0;
+        match config.validate() {
+            Err(SCIMError::InvalidFieldValue(message)) => assert_eq!(message, "filter.maxResults: must be positive"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+    }
+
+    fn scheme(type_: &str, primary: Option<bool>) -> AuthenticationScheme {
+        AuthenticationScheme { name: type_.to_string(), type_: type_.to_string(), primary, ..Default::default() }
+    }
+
+    #[test]
+    fn validate_strict_requires_an_authentication_scheme() {
+        let mut config = ServiceProviderConfig::default_supported();
+        assert!(config.validate().is_ok());
+        match config.validate_strict() {
+            Err(SCIMError::MissingRequiredField(field)) => assert_eq!(field, "authenticationSchemes"),
+            other => panic!("expected MissingRequiredField, got {:?}", other),
+        }
+
+        config.authentication_schemes.push(scheme("httpbasic", None));
+        assert!(config.validate_strict().is_ok());
+    }
+
+    #[test]
+    fn validate_strict_rejects_two_primary_schemes() {
+        let mut config = ServiceProviderConfig::default_supported();
+        config.authentication_schemes = vec![scheme("oauthbearertoken", Some(true)), scheme("httpbasic", Some(true))];
+
+        match config.validate_strict() {
+            Err(SCIMError::InvalidFieldValue(message)) => assert_eq!(message, "authenticationSchemes: at most one scheme may be primary"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+
+        config.authentication_schemes[1].primary = Some(false);
+        assert!(config.validate_strict().is_ok());
+        assert_eq!(config.primary_authentication_scheme().unwrap().type_, "oauthbearertoken");
+    }
+}
@@ -1,12 +1,32 @@
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::io::{Read, Write};
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 
 use crate::models::enterprise_user::EnterpriseUser;
-use crate::models::scim_schema::Meta;
-use crate::utils::error::SCIMError;
+use crate::models::group;
+use crate::models::resource::ScimResource;
+use crate::models::schema_uri;
+use crate::models::scim_schema::{canonicalize, ensure_meta, etag_list_contains, now, weak_etag, Meta};
+use crate::utils::error::{from_json_reader, from_json_str, from_json_value, SCIMError};
 
-#[derive(Serialize, Deserialize, Debug)]
+// Attribute names as the `User` model serializes them. Attribute names are case-insensitive (RFC 7643 §2.1),
+// so these are used to map whatever casing a client sent onto the key serde expects.
+pub(crate) const ATTRIBUTE_NAMES: &[&str] = &[
+    "schemas", "id", "userName", "name", "displayName", "nickName", "profileUrl", "title", "userType",
+    "preferredLanguage", "locale", "timezone", "active", "password", "emails", "addresses", "phoneNumbers",
+    "ims", "photos", "groups", "entitlements", "roles", "x509Certificates", "meta",
+    "formatted", "familyName", "givenName", "middleName", "honorificPrefix", "honorificSuffix",
+    "value", "display", "type", "primary", "$ref", "streetAddress", "locality", "region", "postalCode", "country",
+    "resourceType", "created", "lastModified", "version", "location",
+    "employeeNumber", "costCenter", "organization", "division", "department", "manager",
+];
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct User {
     // urn:ietf:params:scim:schemas:core:2.0:User
     pub schemas: Vec<String>,
@@ -32,38 +52,41 @@ pub struct User {
     pub locale: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timezone: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default, deserialize_with = "deserialize_active", skip_serializing_if = "Option::is_none")]
     pub active: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub emails: Option<Vec<Email>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub addresses: Option<Vec<Address>>,
-    #[serde(rename = "phoneNumbers", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "phoneNumbers", skip_serializing_if = "is_none_or_empty")]
     pub phone_numbers: Option<Vec<PhoneNumber>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub ims: Option<Vec<Im>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub photos: Option<Vec<Photo>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub groups: Option<Vec<Group>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub entitlements: Option<Vec<Entitlement>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "is_none_or_empty")]
     pub roles: Option<Vec<Role>>,
-    #[serde(rename = "x509Certificates", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "x509Certificates", skip_serializing_if = "is_none_or_empty")]
     pub x509_certificates: Option<Vec<X509Certificate>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub meta: Option<Meta>,
     #[serde(rename = "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User", skip_serializing_if = "Option::is_none")]
     pub enterprise_user: Option<EnterpriseUser>,
+    // Custom schema extensions, keyed by their URN.
+    #[serde(flatten, deserialize_with = "deserialize_extensions", skip_serializing_if = "HashMap::is_empty")]
+    pub extensions: HashMap<String, Value>,
 }
 
 impl Default for User {
     fn default() -> Self {
         User {
-            schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
+            schemas: vec![schema_uri::USER.to_string()],
             user_name: "".to_string(),
             id: None,
             name: None,
@@ -88,13 +111,16 @@ impl Default for User {
             x509_certificates: None,
             meta: None,
             enterprise_user: None,
+            extensions: HashMap::new(),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Name {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub formatted: Option<String>,
     #[serde(rename = "familyName", skip_serializing_if = "Option::is_none")]
     pub family_name: Option<String>,
@@ -109,7 +135,8 @@ pub struct Name {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Email {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -123,7 +150,8 @@ pub struct Email {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Address {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -140,10 +168,13 @@ pub struct Address {
     pub country: Option<String>,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub type_: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary: Option<bool>,
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct PhoneNumber {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -157,7 +188,8 @@ pub struct PhoneNumber {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Im {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -171,7 +203,8 @@ pub struct Im {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Photo {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -185,21 +218,23 @@ pub struct Photo {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Group {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub value: Option<String>,
-    #[serde(rename = "$ref", skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "$ref", alias = "ref", skip_serializing_if = "Option::is_none")]
     pub ref_: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub display: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub type_: Option<String>,
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Entitlement {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -213,7 +248,8 @@ pub struct Entitlement {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct Role {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -227,7 +263,8 @@ pub struct Role {
 }
 
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Default)]
 pub struct X509Certificate {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -241,6 +278,371 @@ pub struct X509Certificate {
 }
 
 
+/// The `value`, `type` and `primary` sub-attributes shared by the multi-valued attributes of a user (RFC 7643 §2.4),
+/// so that helpers over them can be written once.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::{find_primary, Email, MultiValued};
+///
+/// let emails = vec![
+///     Email { value: Some("babs@jensen.org".to_string()), ..Default::default() },
+///     Email { value: Some("bjensen@example.com".to_string()), primary: Some(true), ..Default::default() },
+/// ];
+///
+/// assert_eq!(find_primary(&emails).and_then(MultiValued::value), Some("bjensen@example.com"));
+/// ```
+pub trait MultiValued {
+    /// Returns the attribute's significant value, e.g. the address of an email.
+    fn value(&self) -> Option<&str>;
+    /// Returns the label of the value's function, e.g. `work` or `home`.
+    fn type_(&self) -> Option<&str>;
+    /// Returns `true` if the value is marked as the preferred one of its attribute.
+    fn is_primary(&self) -> bool;
+}
+
+macro_rules! impl_multi_valued {
+    ($($type:ty),*) => {
+        $(
+            impl MultiValued for $type {
+                fn value(&self) -> Option<&str> {
+                    self.value.as_deref()
+                }
+
+                fn type_(&self) -> Option<&str> {
+                    self.type_.as_deref()
+                }
+
+                fn is_primary(&self) -> bool {
+                    self.primary == Some(true)
+                }
+            }
+        )*
+    };
+}
+
+impl_multi_valued!(Email, PhoneNumber, Im, Photo, Entitlement, Role, X509Certificate);
+
+/// Returns the first value marked `primary`, if any.
+pub fn find_primary<T: MultiValued>(values: &[T]) -> Option<&T> {
+    values.iter().find(|value| value.is_primary())
+}
+
+/// What a `Reference` returned by `User::references` points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefKind {
+    /// A group the user is a member of (`groups`).
+    Group,
+    /// The user's manager (the enterprise extension's `manager`).
+    Manager,
+}
+
+/// A resource a user refers to by id, as returned by `User::references`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub kind: RefKind,
+    /// The id of the referenced resource.
+    pub value: String,
+    /// The URI of the referenced resource (`$ref`), if given.
+    pub ref_url: Option<String>,
+}
+
+/// The canonical values of an email's `type` (RFC 7643 §4.1.2).
+///
+/// Canonical values are matched case-insensitively; anything else, including the canonical `"other"`, is kept
+/// verbatim in `Other`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum EmailType {
+    Work,
+    Home,
+    Other(String),
+}
+
+impl From<String> for EmailType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "work" => EmailType::Work,
+            "home" => EmailType::Home,
+            _ => EmailType::Other(value),
+        }
+    }
+}
+
+impl From<EmailType> for String {
+    fn from(value: EmailType) -> Self {
+        match value {
+            EmailType::Work => "work".to_string(),
+            EmailType::Home => "home".to_string(),
+            EmailType::Other(value) => value,
+        }
+    }
+}
+
+// `EmailType`, `PhoneType` and `ImType` are serialized through `String`, so their schema is a plain string.
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for EmailType {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "EmailType".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+/// The canonical values of a phone number's `type` (RFC 7643 §4.1.2).
+///
+/// Canonical values are matched case-insensitively; anything else, including the canonical `"other"`, is kept
+/// verbatim in `Other`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum PhoneType {
+    Work,
+    Home,
+    Mobile,
+    Fax,
+    Pager,
+    Other(String),
+}
+
+impl From<String> for PhoneType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "work" => PhoneType::Work,
+            "home" => PhoneType::Home,
+            "mobile" => PhoneType::Mobile,
+            "fax" => PhoneType::Fax,
+            "pager" => PhoneType::Pager,
+            _ => PhoneType::Other(value),
+        }
+    }
+}
+
+impl From<PhoneType> for String {
+    fn from(value: PhoneType) -> Self {
+        match value {
+            PhoneType::Work => "work".to_string(),
+            PhoneType::Home => "home".to_string(),
+            PhoneType::Mobile => "mobile".to_string(),
+            PhoneType::Fax => "fax".to_string(),
+            PhoneType::Pager => "pager".to_string(),
+            PhoneType::Other(value) => value,
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for PhoneType {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "PhoneType".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+/// The canonical values of an instant messaging address's `type` (RFC 7643 §4.1.2).
+///
+/// Canonical values are matched case-insensitively; anything else is kept verbatim in `Other`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+pub enum ImType {
+    Aim,
+    Gtalk,
+    Icq,
+    Xmpp,
+    Msn,
+    Skype,
+    Qq,
+    Yahoo,
+    Other(String),
+}
+
+impl From<String> for ImType {
+    fn from(value: String) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "aim" => ImType::Aim,
+            "gtalk" => ImType::Gtalk,
+            "icq" => ImType::Icq,
+            "xmpp" => ImType::Xmpp,
+            "msn" => ImType::Msn,
+            "skype" => ImType::Skype,
+            "qq" => ImType::Qq,
+            "yahoo" => ImType::Yahoo,
+            _ => ImType::Other(value),
+        }
+    }
+}
+
+impl From<ImType> for String {
+    fn from(value: ImType) -> Self {
+        match value {
+            ImType::Aim => "aim".to_string(),
+            ImType::Gtalk => "gtalk".to_string(),
+            ImType::Icq => "icq".to_string(),
+            ImType::Xmpp => "xmpp".to_string(),
+            ImType::Msn => "msn".to_string(),
+            ImType::Skype => "skype".to_string(),
+            ImType::Qq => "qq".to_string(),
+            ImType::Yahoo => "yahoo".to_string(),
+            ImType::Other(value) => value,
+        }
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for ImType {
+    fn is_referenceable() -> bool {
+        false
+    }
+
+    fn schema_name() -> String {
+        "ImType".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        String::json_schema(generator)
+    }
+}
+
+impl Email {
+    /// Returns `type` as a `EmailType`, or `None` if no type is set.
+    pub fn type_enum(&self) -> Option<EmailType> {
+        self.type_.clone().map(EmailType::from)
+    }
+}
+
+impl PhoneNumber {
+    /// Returns `type` as a `PhoneType`, or `None` if no type is set.
+    pub fn type_enum(&self) -> Option<PhoneType> {
+        self.type_.clone().map(PhoneType::from)
+    }
+
+    /// Strips formatting characters from `value`, producing E.164 (`+15555555555`) when a country code is present.
+    ///
+    /// Spaces, dashes, dots, slashes and parentheses are removed, as is an RFC 3966 `tel:` prefix. A leading `+`
+    /// (or `00` international prefix) is kept as `+`; without one the country is unknown, so only the digits are
+    /// kept. Values containing letters are left untouched so that `validate` can reject them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::PhoneNumber;
+    ///
+    /// let mut phone = PhoneNumber { value: Some("+1 (555) 555-5555".to_string()), ..Default::default() };
+    /// phone.normalize();
+    /// assert_eq!(phone.value.as_deref(), Some("+15555555555"));
+    /// ```
+    pub fn normalize(&mut self) {
+        let normalized = match &self.value {
+            Some(value) => {
+                let number = value.strip_prefix("tel:").unwrap_or(value).trim();
+                if number.chars().any(char::is_alphabetic) {
+                    return;
+                }
+                let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+                if number.starts_with('+') {
+                    format!("+{}", digits)
+                } else if let Some(digits) = digits.strip_prefix("00") {
+                    format!("+{}", digits)
+                } else {
+                    digits
+                }
+            }
+            None => return,
+        };
+        self.value = Some(normalized);
+    }
+
+    /// Validates the phone number's `value`.
+    ///
+    /// The value may contain digits, an optional leading `+` or `tel:` prefix, and the formatting characters
+    /// removed by `normalize`. A `tel:` URI may also carry RFC 3966 parameters such as `;ext=1234`. Letters are
+    /// otherwise rejected, as is a value without any digits.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the value is absent or looks like a phone number.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If the value contains letters or no digits.
+    pub fn validate(&self) -> Result<(), SCIMError> {
+        let value = match &self.value {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        let (number, parameters) = match value.strip_prefix("tel:") {
+            Some(uri) => {
+                let mut parts = uri.split(';');
+                (parts.next().unwrap_or_default(), parts.collect())
+            }
+            None => (value.as_str(), Vec::new()),
+        };
+        let number = number.trim();
+        let number = number.strip_prefix('+').unwrap_or(number);
+        let well_formed = number.chars().any(|c| c.is_ascii_digit())
+            && number.chars().all(|c| c.is_ascii_digit() || " -.()/".contains(c))
+            && parameters.iter().all(|parameter| {
+                let name = parameter.split('=').next().unwrap_or_default();
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+        if well_formed {
+            Ok(())
+        } else {
+            Err(SCIMError::InvalidFieldValue(format!("phoneNumbers: {}", value)))
+        }
+    }
+}
+
+impl Address {
+    /// Validates the address's `country`.
+    ///
+    /// SCIM recommends ISO 3166-1 alpha-2 codes (RFC 7643 §4.1.2), but free-form names are common in practice. A
+    /// two-character `country` is therefore taken to be a code and must be an assigned, uppercase one; longer values
+    /// are accepted as names.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If `country` is absent, a known code, or not two characters long.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `country` is two characters but not a known uppercase code.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::Address;
+    ///
+    /// let address = Address { country: Some("US".to_string()), ..Default::default() };
+    /// assert!(address.validate().is_ok());
+    ///
+    /// let address = Address { country: Some("XX".to_string()), ..Default::default() };
+    /// assert!(address.validate().is_err());
+    /// ```
+    pub fn validate(&self) -> Result<(), SCIMError> {
+        match &self.country {
+            Some(country) if country.chars().count() == 2 && ISO_3166_ALPHA_2.binary_search(&country.as_str()).is_err() => {
+                Err(SCIMError::InvalidFieldValue(format!("addresses.country: {}", country)))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Im {
+    /// Returns `type` as a `ImType`, or `None` if no type is set.
+    pub fn type_enum(&self) -> Option<ImType> {
+        self.type_.clone().map(ImType::from)
+    }
+}
+
 /// Converts a JSON string into a `User` struct.
 ///
 /// This method attempts to parse a JSON string to construct a `User` object. It's useful for scenarios where
@@ -266,15 +668,378 @@ impl TryFrom<&str> for User {
     type Error = SCIMError;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        serde_json::from_str(value).map_err(SCIMError::DeserializationError)
+        from_json_str(value)
+    }
+}
+
+/// Converts an already-parsed JSON value into an `User` struct, avoiding a round trip through a string.
+///
+/// # Errors
+///
+/// Returns `SCIMError::DeserializationError` if the provided JSON value cannot be converted into an `User` object.
+impl TryFrom<Value> for User {
+    type Error = SCIMError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        from_json_value(value)
+    }
+}
+
+/// Converts a `User` into its JSON representation.
+impl From<&User> for Value {
+    fn from(user: &User) -> Self {
+        // Every field serializes to JSON and all map keys are strings, so this cannot fail.
+        serde_json::to_value(user).expect("User serializes to JSON")
+    }
+}
+
+/// Formats every field except `password`, whose value is replaced with `***REDACTED***` so that logging a user
+/// with `{:?}` cannot leak it.
+impl fmt::Debug for User {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        const REDACTED: &str = "***REDACTED***";
+        f.debug_struct("User")
+            .field("schemas", &self.schemas)
+            .field("id", &self.id)
+            .field("user_name", &self.user_name)
+            .field("name", &self.name)
+            .field("display_name", &self.display_name)
+            .field("nick_name", &self.nick_name)
+            .field("profile_url", &self.profile_url)
+            .field("title", &self.title)
+            .field("user_type", &self.user_type)
+            .field("preferred_language", &self.preferred_language)
+            .field("locale", &self.locale)
+            .field("timezone", &self.timezone)
+            .field("active", &self.active)
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .field("emails", &self.emails)
+            .field("addresses", &self.addresses)
+            .field("phone_numbers", &self.phone_numbers)
+            .field("ims", &self.ims)
+            .field("photos", &self.photos)
+            .field("groups", &self.groups)
+            .field("entitlements", &self.entitlements)
+            .field("roles", &self.roles)
+            .field("x509_certificates", &self.x509_certificates)
+            .field("meta", &self.meta)
+            .field("enterprise_user", &self.enterprise_user)
+            .field("extensions", &self.extensions)
+            .finish()
+    }
+}
+
+/// Renders a concise, log-safe summary of the user: `User(id=<id>, userName=<userName>)`.
+///
+/// Only the identifying attributes are included, so secrets such as `password` never end up in logs. `id` is
+/// omitted for users that have not been assigned one yet.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+///
+/// let user = User {
+///     id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+///     user_name: "bjensen@example.com".to_string(),
+///     ..Default::default()
+/// };
+///
+/// assert_eq!(user.to_string(), "User(id=2819c223-7f76-453a-919d-413861904646, userName=bjensen@example.com)");
+/// ```
+impl Display for User {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.id {
+            Some(id) => write!(f, "User(id={}, userName={})", id, self.user_name),
+            None => write!(f, "User(userName={})", self.user_name),
+        }
+    }
+}
+
+impl ScimResource for User {
+    fn schemas(&self) -> &[String] {
+        &self.schemas
+    }
+
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn set_id(&mut self, id: String) {
+        self.id = Some(id);
+    }
+
+    fn meta(&self) -> Option<&Meta> {
+        self.meta.as_ref()
+    }
+
+    fn meta_mut(&mut self) -> Option<&mut Meta> {
+        self.meta.as_mut()
+    }
+
+    fn set_meta(&mut self, meta: Meta) {
+        self.meta = Some(meta);
+    }
+
+    fn resource_type(&self) -> &'static str {
+        "User"
+    }
+
+    fn validate(&self) -> Result<(), SCIMError> {
+        User::validate(self)
+    }
+}
+
+/// Normalizes a `userName` for comparison: Unicode NFC (with the `unicode` feature) followed by lowercasing.
+///
+/// `userName` is not case-exact (RFC 7643 §4.1.1), and PRECIS (RFC 8265) compares usernames after Unicode
+/// normalization, so that e.g. a composed `é` and an `e` followed by a combining acute accent are the same name.
+/// Without the `unicode` feature only the lowercasing is applied.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::normalize_username;
+///
+/// assert_eq!(normalize_username("BJensen@Example.com"), "bjensen@example.com");
+/// ```
+pub fn normalize_username(user_name: &str) -> String {
+    #[cfg(feature = "unicode")]
+    let user_name: String = unicode_normalization::UnicodeNormalization::nfc(user_name).collect();
+    user_name.to_lowercase()
+}
+
+/// Merges the partial user `delta` onto `base`; see `User::merge`.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::{merge_user, User};
+///
+/// let mut base = User { user_name: "bjensen".to_string(), ..Default::default() };
+/// merge_user(&mut base, &User { display_name: Some("Babs Jensen".to_string()), ..Default::default() });
+/// assert_eq!(base.display_name.as_deref(), Some("Babs Jensen"));
+/// ```
+pub fn merge_user(base: &mut User, delta: &User) {
+    base.merge(delta);
+}
+
+/// Returns a JSON Schema (draft 7) describing the JSON representation of a `User`, for validating SCIM payloads
+/// with other tools. Properties use the serialized attribute names, e.g. `userName`.
+///
+/// Only available with the `schemars` feature.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::user_json_schema;
+///
+/// let schema = user_json_schema();
+/// assert!(schema["properties"]["userName"].is_object());
+/// ```
+#[cfg(feature = "schemars")]
+pub fn user_json_schema() -> Value {
+    serde_json::to_value(schemars::schema_for!(User)).expect("JSON schemas serialize to JSON")
+}
+
+/// Multi-valued attributes with no values are unassigned (RFC 7643 §2.4), so they are omitted like `None`.
+fn is_none_or_empty<T>(values: &Option<Vec<T>>) -> bool {
+    values.as_ref().map_or(true, Vec::is_empty)
+}
+
+/// The bucket `group_by_type` puts values without a `type` in.
+const UNTYPED: &str = "(none)";
+
+/// Groups multi-valued attribute values by their lowercased `type`, keeping their order within each group.
+fn group_by_type<T: MultiValued>(values: &Option<Vec<T>>) -> HashMap<String, Vec<&T>> {
+    let mut groups: HashMap<String, Vec<&T>> = HashMap::new();
+    for value in values.iter().flatten() {
+        let key = value.type_().map_or_else(|| UNTYPED.to_string(), str::to_ascii_lowercase);
+        groups.entry(key).or_default().push(value);
+    }
+    groups
+}
+
+/// Removes later elements that are equal to an earlier one, keeping the order of first occurrence.
+fn dedup_values<T: PartialEq>(values: &mut Option<Vec<T>>) {
+    if let Some(values) = values {
+        let mut unique: Vec<T> = Vec::with_capacity(values.len());
+        for value in values.drain(..) {
+            if !unique.contains(&value) {
+                unique.push(value);
+            }
+        }
+        *values = unique;
+    }
+}
+
+/// Collects the top-level members that no `User` field claimed, keeping only schema extensions (`urn:` keys).
+fn deserialize_extensions<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HashMap<String, Value>, D::Error> {
+    let mut extensions = HashMap::<String, Value>::deserialize(deserializer)?;
+    extensions.retain(|key, _| key.starts_with("urn:"));
+    Ok(extensions)
+}
+
+/// Reads `active` as a boolean, also accepting the strings `"true"` and `"false"` (in any case) that some
+/// identity providers send, so one non-conforming field does not fail the whole user.
+fn deserialize_active<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Bool(active)) => Ok(Some(active)),
+        Some(Value::String(text)) if text.eq_ignore_ascii_case("true") => Ok(Some(true)),
+        Some(Value::String(text)) if text.eq_ignore_ascii_case("false") => Ok(Some(false)),
+        Some(other) => Err(serde::de::Error::invalid_value(serde::de::Unexpected::Other(&other.to_string()), &"a boolean")),
+    }
+}
+
+/// Rewrites object keys to their canonical casing, leaving schema extension URNs untouched.
+fn normalize_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => fields
+            .into_iter()
+            .map(|(key, value)| {
+                let key = match ATTRIBUTE_NAMES.iter().find(|name| name.eq_ignore_ascii_case(&key)) {
+                    Some(name) if !key.starts_with("urn:") => name.to_string(),
+                    _ => key,
+                };
+                (key, normalize_keys(value))
+            })
+            .collect(),
+        Value::Array(items) => Value::Array(items.into_iter().map(normalize_keys).collect()),
+        value => value,
+    }
+}
+
+// The officially assigned ISO 3166-1 alpha-2 country codes, sorted for binary search.
+const ISO_3166_ALPHA_2: &[&str] = &[
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AO", "AQ", "AR", "AS", "AT", "AU", "AW", "AX", "AZ", "BA", "BB", "BD",
+    "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ", "BR", "BS", "BT", "BV", "BW", "BY", "BZ", "CA",
+    "CC", "CD", "CF", "CG", "CH", "CI", "CK", "CL", "CM", "CN", "CO", "CR", "CU", "CV", "CW", "CX", "CY", "CZ", "DE",
+    "DJ", "DK", "DM", "DO", "DZ", "EC", "EE", "EG", "EH", "ER", "ES", "ET", "FI", "FJ", "FK", "FM", "FO", "FR", "GA",
+    "GB", "GD", "GE", "GF", "GG", "GH", "GI", "GL", "GM", "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK",
+    "HM", "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM", "JO", "JP",
+    "KE", "KG", "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC", "LI", "LK", "LR", "LS", "LT",
+    "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK", "ML", "MM", "MN", "MO", "MP", "MQ", "MR", "MS",
+    "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA", "NC", "NE", "NF", "NG", "NI", "NL", "NO", "NP", "NR", "NU", "NZ",
+    "OM", "PA", "PE", "PF", "PG", "PH", "PK", "PL", "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "RE", "RO", "RS",
+    "RU", "RW", "SA", "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS", "ST",
+    "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO", "TR", "TT", "TV", "TW",
+    "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG", "VI", "VN", "VU", "WF", "WS", "YE", "YT", "ZA",
+    "ZM", "ZW",
+];
+
+fn is_valid_email(value: &str) -> bool {
+    match value.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && !domain.is_empty() && !domain.contains('@'),
+        None => false,
+    }
+}
+
+/// Checks that `value` is a URI reference (RFC 3986 §4.1): an absolute URI such as
+/// `https://example.com/v2/Groups/e9e30dba`, or a relative reference such as `../Groups/e9e30dba`.
+///
+/// Only the character set, percent-encoding and scheme are checked, which is enough to reject malformed values like
+/// `htt p://`; hosts and ports are not parsed.
+fn is_valid_uri_reference(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    if bytes.is_empty() {
+        return false;
+    }
+    for (index, &b) in bytes.iter().enumerate() {
+        let valid = match b {
+            b'%' => bytes.get(index + 1..index + 3).map_or(false, |hex| hex.iter().all(u8::is_ascii_hexdigit)),
+            _ => b.is_ascii_alphanumeric() || b"-._~:/?#[]@!$&'()*+,;=".contains(&b),
+        };
+        if !valid {
+            return false;
+        }
+    }
+    // A colon before the first `/`, `?` or `#` ends a scheme, which must start with a letter.
+    let first_segment = value.split(['/', '?', '#']).next().unwrap_or_default();
+    match first_segment.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.bytes().all(|b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+        }
+        None => true,
+    }
+}
+
+/// Checks for the common `language[-REGION]` form of a BCP 47 tag: a two or three letter lowercase language,
+/// optionally followed by a two letter uppercase region. Full BCP 47 (scripts, variants, extensions) is not supported.
+fn is_valid_language_tag(tag: &str) -> bool {
+    let (language, region) = match tag.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (tag, None),
+    };
+    let is_language = (2..=3).contains(&language.len()) && language.bytes().all(|b| b.is_ascii_lowercase());
+    let is_region = region.map_or(true, |region| region.len() == 2 && region.bytes().all(|b| b.is_ascii_uppercase()));
+    is_language && is_region
+}
+
+/// Which checks `User::validate_with` runs, so that each deployment can choose its own strictness.
+///
+/// Start from a preset and override individual flags with struct update syntax.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::models::user::ValidationPolicy;
+///
+/// let policy = ValidationPolicy { check_email_format: true, ..ValidationPolicy::lenient() };
+/// assert!(!policy.check_language_tags);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValidationPolicy {
+    /// Require `schemas` to be non-empty.
+    pub require_schemas: bool,
+    /// Allow at most one `primary` value per multi-valued attribute (see `User::validate_primary_uniqueness`).
+    pub check_primary_uniqueness: bool,
+    /// Require each email `value` to have a single `@` with a non-empty local part and domain.
+    pub check_email_format: bool,
+    /// Check each phone number with `PhoneNumber::validate`.
+    pub check_phone_numbers: bool,
+    /// Check each address's `country` with `Address::validate`.
+    pub check_country_codes: bool,
+    /// Require `preferredLanguage` and `locale` to be `language[-REGION]` tags.
+    pub check_language_tags: bool,
+    /// Require each `$ref` of `groups` and the enterprise `manager` to be a URI reference, absolute or relative.
+    pub check_references: bool,
+}
+
+impl ValidationPolicy {
+    /// Every check enabled, as `User::validate_strict` does.
+    pub fn strict() -> Self {
+        ValidationPolicy {
+            require_schemas: true,
+            check_primary_uniqueness: true,
+            check_email_format: true,
+            check_phone_numbers: true,
+            check_country_codes: true,
+            check_language_tags: true,
+            check_references: true,
+        }
+    }
+
+    /// Only the required attributes checked, as `User::validate` does.
+    pub fn lenient() -> Self {
+        ValidationPolicy {
+            require_schemas: true,
+            check_primary_uniqueness: false,
+            check_email_format: false,
+            check_phone_numbers: false,
+            check_country_codes: false,
+            check_language_tags: false,
+            check_references: false,
+        }
     }
 }
 
 impl User {
     /// Validates a user.
     ///
-    /// This function checks if the user has a `name` and `user_name`. If either is missing, it returns an error.
-    /// It also checks if the `emails` field is present and if each email in the vector is in a valid email format.
+    /// This function checks if the user has `schemas` and a `user_name`. If either is missing, it returns an error.
+    /// Field formats are not checked; use `validate_strict` for that.
     ///
     /// # Arguments
     ///
@@ -306,115 +1071,1381 @@ impl User {
     /// # Note
     ///
     /// The actual validation requirements will depend on the specifics of your application and the SCIM (System for Cross-domain Identity Management) protocol you are implementing.
-
     pub fn validate(&self) -> Result<(), SCIMError> {
-        // Pretty much every field is optional in the schema except for 2. We'll check for those here.
-        if self.schemas.is_empty() {
-            return Err(SCIMError::MissingRequiredField("schemas".to_string()));
-        }
-        if self.user_name.is_empty() {
-            return Err(SCIMError::MissingRequiredField("user_name".to_string()));
+        self.validate_with(&ValidationPolicy::lenient())
+    }
+
+    /// Validates a user, additionally checking the format of populated fields.
+    ///
+    /// This function performs the same checks as `validate`, then checks that no multi-valued attribute has more
+    /// than one primary value, that each email `value` contains a single `@` with a non-empty local part and domain,
+    /// that each phone number `value` passes `PhoneNumber::validate`, that each address passes `Address::validate`,
+    /// that `preferred_language` and `locale` are language tags of the form `language[-REGION]` (e.g. `fr` or
+    /// `en-US`), and that each `$ref` of `groups` and the enterprise `manager` is a URI reference, absolute or
+    /// relative (e.g. `../Groups/e9e30dba`). This is `validate_with` using `ValidationPolicy::strict()`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the user is valid.
+    /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a field value is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     emails: Some(vec![Email {
+    ///         value: Some("jdoe@example.com".to_string()),
+    ///         ..Default::default()
+    ///     }]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// match user.validate_strict() {
+    ///     Ok(_) => println!("User is valid."),
+    ///     Err(e) => println!("User is invalid: {}", e),
+    /// }
+    /// ```
+    pub fn validate_strict(&self) -> Result<(), SCIMError> {
+        self.validate_with(&ValidationPolicy::strict())
+    }
+
+    /// Validates a user, running the checks that `policy` enables.
+    ///
+    /// `userName` is always required. Every other check is controlled by a `ValidationPolicy` flag;
+    /// `validate` and `validate_strict` are this method with the `lenient` and `strict` presets.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the user passes every enabled check.
+    /// * `Err(SCIMError::MissingRequiredField)` - If a required field is missing.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If a field value is invalid.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{User, ValidationPolicy};
+    ///
+    /// let user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     locale: Some("en_US".to_string()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let policy = ValidationPolicy { check_language_tags: false, ..ValidationPolicy::strict() };
+    /// assert!(user.validate_with(&policy).is_ok());
+    /// assert!(user.validate_strict().is_err());
+    /// ```
+    pub fn validate_with(&self, policy: &ValidationPolicy) -> Result<(), SCIMError> {
+        match self.violations(policy).into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
         }
-        Ok(())
     }
 
-    /// Serializes the `User` instance to a JSON string, using the custom SCIMError for error handling.
+    /// Validates a user like `validate_strict`, but reports every violation instead of only the first.
+    ///
+    /// This lets a client fix all problems with a request in one round trip.
     ///
     /// # Returns
     ///
-    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` contains
-    /// the JSON string representation of the `User` instance, and `Err(SCIMError)` contains
-    /// the custom error encountered during serialization.
+    /// * `Ok(())` - If the user is valid.
+    /// * `Err(errors)` - Every violation found, in the order `validate_strict` checks them. Each multi-valued
+    ///   attribute with more than one primary value is reported separately.
     ///
-    /// # Examples
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let user = User {
+    ///     schemas: vec![],
+    ///     emails: Some(vec![Email { value: Some("not-an-email".to_string()), ..Default::default() }]),
+    ///     ..Default::default()
+    /// };
     ///
+    /// assert_eq!(user.validate_all().unwrap_err().len(), 3);
     /// ```
+    pub fn validate_all(&self) -> Result<(), Vec<SCIMError>> {
+        let violations = self.violations(&ValidationPolicy::strict());
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    fn violations(&self, policy: &ValidationPolicy) -> Vec<SCIMError> {
+        let mut violations = Vec::new();
+        // Pretty much every field is optional in the schema except for 2. We'll check for those here.
+        if policy.require_schemas && self.schemas.is_empty() {
+            violations.push(SCIMError::MissingRequiredField("schemas".to_string()));
+        }
+        if self.user_name.is_empty() {
+            violations.push(SCIMError::MissingRequiredField("user_name".to_string()));
+        }
+        if policy.check_primary_uniqueness {
+            violations.extend(self.primary_violations());
+        }
+        if policy.check_email_format {
+            for email in self.emails.iter().flatten() {
+                if let Some(value) = &email.value {
+                    if !is_valid_email(value) {
+                        violations.push(SCIMError::InvalidFieldValue(format!("emails: {}", value)));
+                    }
+                }
+            }
+        }
+        if policy.check_phone_numbers {
+            violations.extend(self.phone_numbers.iter().flatten().filter_map(|phone_number| phone_number.validate().err()));
+        }
+        if policy.check_country_codes {
+            violations.extend(self.addresses.iter().flatten().filter_map(|address| address.validate().err()));
+        }
+        if policy.check_language_tags {
+            for (name, tag) in [("preferredLanguage", &self.preferred_language), ("locale", &self.locale)] {
+                if let Some(tag) = tag {
+                    if !is_valid_language_tag(tag) {
+                        violations.push(SCIMError::InvalidFieldValue(format!("{}: {}", name, tag)));
+                    }
+                }
+            }
+        }
+        if policy.check_references {
+            let group_refs = self.groups.iter().flatten().filter_map(|group| group.ref_.as_deref()).map(|ref_url| ("groups.$ref", ref_url));
+            let manager_ref = self
+                .enterprise_user
+                .as_ref()
+                .and_then(|enterprise_user| enterprise_user.manager.as_ref())
+                .and_then(|manager| manager.ref_.as_deref())
+                .map(|ref_url| ("manager.$ref", ref_url));
+            for (name, ref_url) in group_refs.chain(manager_ref) {
+                if !is_valid_uri_reference(ref_url) {
+                    violations.push(SCIMError::InvalidFieldValue(format!("{}: {}", name, ref_url)));
+                }
+            }
+        }
+        violations
+    }
+
+    /// Checks that `timezone`, when present, names a zone in the IANA time zone database, e.g. `America/Los_Angeles`.
+    ///
+    /// Only available with the `tz-validate` feature.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If `timezone` is absent or a known zone.
+    /// * `Err(SCIMError::InvalidFieldValue)` - If `timezone` is not a known zone.
+    ///
+    /// # Example
+    ///
+    /// ```rust
     /// use scim_v2::models::user::User;
     ///
     /// let user = User {
-    ///     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
-    ///     user_name: "jdoe@example.com".to_string(),
-    ///     // Initialize other fields as necessary...
+    ///     user_name: "bjensen".to_string(),
+    ///     timezone: Some("America/Los_Angeles".to_string()),
     ///     ..Default::default()
     /// };
     ///
-    /// match user.serialize() {
-    ///     Ok(json) => println!("Serialized User: {}", json),
-    ///     Err(e) => println!("Serialization error: {}", e),
+    /// assert!(user.validate_timezone().is_ok());
+    /// ```
+    #[cfg(feature = "tz-validate")]
+    pub fn validate_timezone(&self) -> Result<(), SCIMError> {
+        match &self.timezone {
+            Some(timezone) if timezone.parse::<chrono_tz::Tz>().is_err() => {
+                Err(SCIMError::InvalidFieldValue(format!("timezone: {}", timezone)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that no other user in `existing` has the same `user_name`.
+    ///
+    /// `userName` is unique within a service provider and is not case-exact (RFC 7643 §4.1.1), so names are
+    /// compared after `normalize_username`: case-insensitively and, with the `unicode` feature, after Unicode
+    /// normalization. A user in `existing` with the same `id` as this one is ignored, so that
+    /// validating an update against the current collection does not conflict with itself.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the name is not taken by another user.
+    /// * `Err(SCIMError::ConflictError)` - If another user already has the name.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::User;
+    ///
+    /// let existing = vec![User {
+    ///     id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+    ///     user_name: "bjensen".to_string(),
+    ///     ..Default::default()
+    /// }];
+    /// let candidate = User { user_name: "BJensen".to_string(), ..Default::default() };
+    ///
+    /// match candidate.validate_unique_username(&existing) {
+    ///     Ok(_) => println!("userName is available."),
+    ///     Err(e) => println!("userName is taken: {}", e),
     /// }
     /// ```
-    pub fn serialize(&self) -> Result<String, SCIMError> {
-        serde_json::to_string(&self).map_err(SCIMError::SerializationError)
+    pub fn validate_unique_username(&self, existing: &[User]) -> Result<(), SCIMError> {
+        let user_name = normalize_username(&self.user_name);
+        let conflict = existing.iter().any(|other| {
+            let same_user = self.id.is_some() && other.id == self.id;
+            !same_user && normalize_username(&other.user_name) == user_name
+        });
+        if conflict {
+            return Err(SCIMError::ConflictError(format!("userName: {} is already in use", self.user_name)));
+        }
+        Ok(())
     }
 
-    /// Deserializes a JSON string into a `User` instance, using the custom SCIMError for error handling.
+    /// Checks that at most one element of each multi-valued attribute is marked `primary`.
     ///
-    /// # Parameters
+    /// # Returns
     ///
-    /// * `json` - A string slice that holds the JSON representation of a `User`.
+    /// * `Ok(())` - If no multi-valued attribute has more than one primary value.
+    /// * `Err(SCIMError::InvalidFieldValue)` - Naming the first attribute with multiple primary values.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let primary_email = |value: &str| Email {
+    ///     value: Some(value.to_string()),
+    ///     primary: Some(true),
+    ///     ..Default::default()
+    /// };
+    /// let user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     emails: Some(vec![primary_email("jdoe@example.com"), primary_email("john@example.org")]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert!(user.validate_primary_uniqueness().is_err());
+    /// ```
+    pub fn validate_primary_uniqueness(&self) -> Result<(), SCIMError> {
+        match self.primary_violations().into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    fn primary_violations(&self) -> Vec<SCIMError> {
+        fn primary_count<T>(values: &Option<Vec<T>>, primary: impl Fn(&T) -> Option<bool>) -> usize {
+            values.iter().flatten().filter(|value| primary(value) == Some(true)).count()
+        }
+
+        let counts = [
+            ("emails", primary_count(&self.emails, |v| v.primary)),
+            ("phoneNumbers", primary_count(&self.phone_numbers, |v| v.primary)),
+            ("ims", primary_count(&self.ims, |v| v.primary)),
+            ("photos", primary_count(&self.photos, |v| v.primary)),
+            ("addresses", primary_count(&self.addresses, |v| v.primary)),
+            ("entitlements", primary_count(&self.entitlements, |v| v.primary)),
+            ("roles", primary_count(&self.roles, |v| v.primary)),
+            ("x509Certificates", primary_count(&self.x509_certificates, |v| v.primary)),
+        ];
+        counts
+            .iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(attribute, _)| SCIMError::InvalidFieldValue(format!("{}: multiple primary values", attribute)))
+            .collect()
+    }
+
+    /// Checks that the enterprise extension URN is listed in `schemas` exactly when the extension is present.
+    ///
+    /// Some identity providers send the enterprise extension object without listing its URN in `schemas` (or the
+    /// reverse). Both still deserialize, so this method exists to detect the inconsistency.
     ///
     /// # Returns
     ///
-    /// This method returns a `Result<User, SCIMError>`, where `Ok(User)` is the deserialized `User` instance,
-    /// and `Err(SCIMError)` is the custom error encountered during deserialization.
+    /// * `Ok(())` - If `schemas` and `enterprise_user` agree.
+    /// * `Err(SCIMError::InvalidValue)` - If one is present without the other.
     ///
-    /// # Examples
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::EnterpriseUser;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     enterprise_user: Some(EnterpriseUser::default()),
+    ///     ..Default::default()
+    /// };
     ///
+    /// assert!(user.validate_schema_consistency().is_err());
     /// ```
+    pub fn validate_schema_consistency(&self) -> Result<(), SCIMError> {
+        let listed = self.schemas.iter().any(|schema| schema.eq_ignore_ascii_case(schema_uri::ENTERPRISE_USER));
+        match (listed, self.enterprise_user.is_some()) {
+            (false, true) => Err(SCIMError::InvalidValue(format!("schemas: missing {}", schema_uri::ENTERPRISE_USER))),
+            (true, false) => Err(SCIMError::InvalidValue(format!("schemas: {} listed without extension attributes", schema_uri::ENTERPRISE_USER))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Populates `meta.resourceType` and `meta.location` if `meta` is missing or has no `resourceType`.
+    ///
+    /// `resourceType` is set to `User` and, when the user has an `id` and no `location` yet, `location` is set
+    /// to `<base_url>/Users/<id>`. Timestamps and `version` are left untouched, as is a `meta` that already
+    /// names its resource type.
+    ///
+    /// # Example
+    ///
+    /// ```rust
     /// use scim_v2::models::user::User;
     ///
-    /// let user_json = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jdoe@example.com"}"#;
-    /// match User::deserialize(user_json) {
-    ///     Ok(user) => println!("Deserialized User: {:?}", user),
-    ///     Err(e) => println!("Deserialization error: {}", e),
-    /// }
+    /// let mut user = User {
+    ///     id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+    ///     user_name: "bjensen@example.com".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// user.ensure_meta("https://example.com/v2");
+    ///
+    /// let meta = user.meta.unwrap();
+    /// assert_eq!(meta.resource_type.as_deref(), Some("User"));
+    /// assert_eq!(meta.location.as_deref(), Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"));
     /// ```
-    pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
-        serde_json::from_str(json).map_err(SCIMError::DeserializationError)
+    pub fn ensure_meta(&mut self, base_url: &str) {
+        ensure_meta(&mut self.meta, "User", "Users", base_url, self.id.as_deref());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    // Import everything from the outer module
-    use pretty_assertions::assert_eq;
+    /// Returns the custom schema extension object for `urn`, if the user has one.
+    ///
+    /// The enterprise extension has its own field, `enterprise_user`, and is not returned here.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User::try_from(r#"{
+    ///     "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:acme:params:scim:schemas:extension:2.0:User"],
+    ///     "userName": "bjensen",
+    ///     "urn:acme:params:scim:schemas:extension:2.0:User": {"badgeNumber": "42"}
+    /// }"#).unwrap();
+    ///
+    /// let acme = user.extension("urn:acme:params:scim:schemas:extension:2.0:User").unwrap();
+    /// assert_eq!(acme["badgeNumber"], "42");
+    /// ```
+    pub fn extension(&self, urn: &str) -> Option<&Value> {
+        self.extensions.get(urn)
+    }
 
-    use super::*;
+    /// Attaches the enterprise extension, listing its schema URN in `schemas` if it is not there already.
+    ///
+    /// Any extension the user already had is replaced.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::EnterpriseUser;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+    /// user.set_enterprise(EnterpriseUser { employee_number: Some("701984".to_string()), ..Default::default() });
+    ///
+    /// assert!(user.validate_schema_consistency().is_ok());
+    /// ```
+    pub fn set_enterprise(&mut self, enterprise_user: EnterpriseUser) {
+        if !self.schemas.iter().any(|schema| schema.eq_ignore_ascii_case(schema_uri::ENTERPRISE_USER)) {
+            self.schemas.push(schema_uri::ENTERPRISE_USER.to_string());
+        }
+        self.enterprise_user = Some(enterprise_user);
+    }
 
-    #[test]
-    fn user_deserialization_with_minimum_fields() {
-        let json_data = r#"{
-            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
-            "id": "2819c223-7f76-453a-919d-413861904646",
-            "userName": "bjensen@example.com",
-            "meta": {
-                "resourceType": "User",
-                "created": "2010-01-23T04:56:22Z",
-                "lastModified": "2011-05-13T04:42:34Z",
-                "version": "W/\"3694e05e9dff590\"",
-                "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
-            }
-        }"#;
+    /// Attaches the enterprise extension like `set_enterprise`, and returns the user.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::EnterpriseUser;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let enterprise_user = EnterpriseUser::builder().employee_number("701984").build().unwrap();
+    /// let user = User { user_name: "bjensen".to_string(), ..Default::default() }.with_enterprise(enterprise_user);
+    ///
+    /// assert!(user.schemas.contains(&"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User".to_string()));
+    /// ```
+    pub fn with_enterprise(mut self, enterprise_user: EnterpriseUser) -> Self {
+        self.set_enterprise(enterprise_user);
+        self
+    }
 
-        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+    /// Detaches and returns the enterprise extension, removing its schema URN from `schemas`.
+    ///
+    /// The URN is removed even if the user had no extension attached, so `schemas` is consistent afterwards.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::EnterpriseUser;
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+    /// user.set_enterprise(EnterpriseUser::default());
+    ///
+    /// assert_eq!(user.take_enterprise(), Some(EnterpriseUser::default()));
+    /// assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()]);
+    /// ```
+    pub fn take_enterprise(&mut self) -> Option<EnterpriseUser> {
+        self.schemas.retain(|schema| !schema.eq_ignore_ascii_case(schema_uri::ENTERPRISE_USER));
+        self.enterprise_user.take()
+    }
 
-        if let Err(e) = &user {
-            eprintln!("Deserialization failed: {:?}", e);
+    /// Replaces this user with `replacement`, as a SCIM `PUT` does (RFC 7644 §3.5.1), and returns the result.
+    ///
+    /// Every client-managed attribute comes from `replacement`. The server-managed ones are kept from this user:
+    /// `id`, and `meta.created`, `meta.resourceType` and `meta.location`. `meta.lastModified` is set to the current
+    /// time, and `meta.version` is cleared since the content changed; use `refresh_version` to recompute it.
+    /// `password` is write-only and never returned to clients, so it is only replaced when `replacement` sets one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::User;
+    ///
+    /// let current = User {
+    ///     id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+    ///     user_name: "bjensen".to_string(),
+    ///     ..Default::default()
+    /// };
+    /// let replacement = User {
+    ///     user_name: "bjensen".to_string(),
+    ///     display_name: Some("Babs Jensen".to_string()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let updated = current.apply_put(replacement);
+    /// assert_eq!(updated.id, current.id);
+    /// assert_eq!(updated.display_name.as_deref(), Some("Babs Jensen"));
+    /// assert!(updated.meta.unwrap().last_modified.is_some());
+    /// ```
+    pub fn apply_put(&self, replacement: User) -> User {
+        let current_meta = self.meta.clone().unwrap_or_default();
+        User {
+            id: self.id.clone(),
+            password: replacement.password.or_else(|| self.password.clone()),
+            meta: Some(Meta {
+                resource_type: current_meta.resource_type,
+                created: current_meta.created,
+                last_modified: Some(now()),
+                version: None,
+                location: current_meta.location,
+            }),
+            ..replacement
         }
-        assert!(user.is_ok());
-        let user = user.unwrap();
-        assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User"]);
-        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".to_string()));
-        assert_eq!(user.user_name, "bjensen@example.com");
-        let meta = user.meta.unwrap();
-        assert_eq!(meta.resource_type, Some("User".to_string()));
-        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".to_string()));
-        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".to_string()));
-        assert_eq!(meta.version, Some("W/\"3694e05e9dff590\"".to_string()));
-        assert_eq!(meta.location, Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646".to_string()));
     }
 
-    #[test]
+    /// Merges a partial user onto this one, e.g. to stage several PATCH operations before persisting them.
+    ///
+    /// Every attribute set in `delta` replaces this user's: optional attributes when they are `Some`, and
+    /// `userName` when it is not empty. Complex attributes (`name`, the enterprise extension and its `manager`, and
+    /// custom extension objects) are merged sub-attribute by sub-attribute, so a delta that only sets
+    /// `department` keeps the `employeeNumber` already there. Multi-valued attributes are replaced as a whole, not
+    /// appended to, since adding and removing individual values is left to PATCH. Schema URNs in `delta` that this
+    /// user does not list are added. `id` and `meta` are always kept.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User { id: Some("2819c223".to_string()), user_name: "bjensen".to_string(), ..Default::default() };
+    /// let delta = User { display_name: Some("Babs Jensen".to_string()), ..Default::default() };
+    ///
+    /// user.merge(&delta);
+    /// assert_eq!(user.user_name, "bjensen");
+    /// assert_eq!(user.display_name.as_deref(), Some("Babs Jensen"));
+    /// assert_eq!(user.id.as_deref(), Some("2819c223"));
+    /// ```
+    pub fn merge(&mut self, delta: &User) {
+        fn overlay<T: Clone>(base: &mut Option<T>, delta: &Option<T>) {
+            if delta.is_some() {
+                base.clone_from(delta);
+            }
+        }
+
+        fn merge_name(base: &mut Name, delta: &Name) {
+            overlay(&mut base.formatted, &delta.formatted);
+            overlay(&mut base.family_name, &delta.family_name);
+            overlay(&mut base.given_name, &delta.given_name);
+            overlay(&mut base.middle_name, &delta.middle_name);
+            overlay(&mut base.honorific_prefix, &delta.honorific_prefix);
+            overlay(&mut base.honorific_suffix, &delta.honorific_suffix);
+        }
+
+        fn merge_enterprise(base: &mut EnterpriseUser, delta: &EnterpriseUser) {
+            overlay(&mut base.employee_number, &delta.employee_number);
+            overlay(&mut base.cost_center, &delta.cost_center);
+            overlay(&mut base.organization, &delta.organization);
+            overlay(&mut base.division, &delta.division);
+            overlay(&mut base.department, &delta.department);
+            match (&mut base.manager, &delta.manager) {
+                (Some(manager), Some(delta)) => {
+                    overlay(&mut manager.value, &delta.value);
+                    overlay(&mut manager.ref_, &delta.ref_);
+                    overlay(&mut manager.display_name, &delta.display_name);
+                }
+                (manager, delta) => overlay(manager, delta),
+            }
+        }
+
+        for schema in &delta.schemas {
+            if !self.schemas.iter().any(|listed| listed.eq_ignore_ascii_case(schema)) {
+                self.schemas.push(schema.clone());
+            }
+        }
+        if !delta.user_name.is_empty() {
+            self.user_name.clone_from(&delta.user_name);
+        }
+        if let Some(name) = &delta.name {
+            merge_name(self.name.get_or_insert_with(Name::default), name);
+        }
+        overlay(&mut self.display_name, &delta.display_name);
+        overlay(&mut self.nick_name, &delta.nick_name);
+        overlay(&mut self.profile_url, &delta.profile_url);
+        overlay(&mut self.title, &delta.title);
+        overlay(&mut self.user_type, &delta.user_type);
+        overlay(&mut self.preferred_language, &delta.preferred_language);
+        overlay(&mut self.locale, &delta.locale);
+        overlay(&mut self.timezone, &delta.timezone);
+        overlay(&mut self.active, &delta.active);
+        overlay(&mut self.password, &delta.password);
+        overlay(&mut self.emails, &delta.emails);
+        overlay(&mut self.addresses, &delta.addresses);
+        overlay(&mut self.phone_numbers, &delta.phone_numbers);
+        overlay(&mut self.ims, &delta.ims);
+        overlay(&mut self.photos, &delta.photos);
+        overlay(&mut self.groups, &delta.groups);
+        overlay(&mut self.entitlements, &delta.entitlements);
+        overlay(&mut self.roles, &delta.roles);
+        overlay(&mut self.x509_certificates, &delta.x509_certificates);
+        if let Some(enterprise_user) = &delta.enterprise_user {
+            merge_enterprise(self.enterprise_user.get_or_insert_with(EnterpriseUser::default), enterprise_user);
+        }
+        for (urn, value) in &delta.extensions {
+            match (self.extensions.get_mut(urn), value) {
+                (Some(Value::Object(extension)), Value::Object(attributes)) => {
+                    extension.extend(attributes.iter().map(|(name, value)| (name.clone(), value.clone())));
+                }
+                _ => {
+                    self.extensions.insert(urn.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns the user's `id`, for keying users in a `HashMap` or `HashSet`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use std::collections::HashMap;
+    ///
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User { id: Some("2819c223-7f76-453a-919d-413861904646".to_string()), ..Default::default() };
+    /// let by_id: HashMap<&str, &User> = user.key().map(|key| (key, &user)).into_iter().collect();
+    /// assert!(by_id.contains_key("2819c223-7f76-453a-919d-413861904646"));
+    /// ```
+    pub fn key(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Returns whether the user is active. An absent `active` means active, per SCIM convention.
+    pub fn is_active(&self) -> bool {
+        self.active.unwrap_or(true)
+    }
+
+    /// Marks the user as inactive by setting `active` to `Some(false)`.
+    pub fn deactivate(&mut self) {
+        self.active = Some(false);
+    }
+
+    /// Derives the read-only `groups` attribute from the groups that list this user as a member.
+    ///
+    /// A group contains the user when one of its `members` has the user's `id` as its `value`. Each such group is
+    /// listed with its id as `value`, its `meta.location` as `$ref`, its `displayName` as `display` and a `type` of
+    /// `direct`; memberships through nested groups are not followed. `groups` is cleared if the user has no `id` or
+    /// belongs to none of `groups`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::group::{Group, Member};
+    /// use scim_v2::models::user::User;
+    ///
+    /// let guides = Group {
+    ///     id: "e9e30dba".to_string(),
+    ///     display_name: "Tour Guides".to_string(),
+    ///     members: Some(vec![Member { value: Some("2819c223".to_string()), ..Default::default() }]),
+    ///     ..Default::default()
+    /// };
+    /// let mut user = User { id: Some("2819c223".to_string()), user_name: "bjensen".to_string(), ..Default::default() };
+    /// user.set_groups_from(&[guides]);
+    ///
+    /// assert_eq!(user.groups.unwrap()[0].display.as_deref(), Some("Tour Guides"));
+    /// ```
+    pub fn set_groups_from(&mut self, groups: &[group::Group]) {
+        let memberships: Vec<Group> = match self.id.as_deref() {
+            Some(id) => groups
+                .iter()
+                .filter(|group| group.contains_member(id))
+                .map(|group| Group {
+                    value: Some(group.id.clone()),
+                    ref_: group.meta.as_ref().and_then(|meta| meta.location.clone()),
+                    display: Some(group.display_name.clone()),
+                    type_: Some("direct".to_string()),
+                })
+                .collect(),
+            None => vec![],
+        };
+        self.groups = if memberships.is_empty() { None } else { Some(memberships) };
+    }
+
+    /// Returns every other resource the user refers to: its groups, then its manager.
+    ///
+    /// Group memberships come from `groups` and the manager from the enterprise extension, each with its `$ref`
+    /// when one is given. Entries without a `value` (or with an empty one) cannot be fetched and are skipped.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::{EnterpriseUser, Manager};
+    /// use scim_v2::models::user::{Group, RefKind, User};
+    ///
+    /// let user = User {
+    ///     user_name: "bjensen".to_string(),
+    ///     groups: Some(vec![Group { value: Some("e9e30dba".to_string()), ..Default::default() }]),
+    ///     enterprise_user: Some(EnterpriseUser {
+    ///         manager: Some(Manager { value: Some("26118915".to_string()), ref_: None, display_name: None }),
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let kinds: Vec<RefKind> = user.references().iter().map(|reference| reference.kind).collect();
+    /// assert_eq!(kinds, vec![RefKind::Group, RefKind::Manager]);
+    /// ```
+    pub fn references(&self) -> Vec<Reference> {
+        let groups = self.groups.iter().flatten().map(|group| (RefKind::Group, &group.value, &group.ref_));
+        let manager = self
+            .enterprise_user
+            .as_ref()
+            .and_then(|enterprise_user| enterprise_user.manager.as_ref())
+            .map(|manager| (RefKind::Manager, &manager.value, &manager.ref_));
+        groups
+            .chain(manager)
+            .filter_map(|(kind, value, ref_url)| match value {
+                Some(value) if !value.is_empty() => Some(Reference { kind, value: value.clone(), ref_url: ref_url.clone() }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Follows the enterprise extension's `manager` references up the reporting chain.
+    ///
+    /// Returns the manager ids in order, starting with this user's direct manager. Each manager is looked up
+    /// with `lookup` to find the next one. The chain ends when a user has no manager, when `lookup` cannot find
+    /// a manager (whose id is still included), or when a manager id repeats, including a user who manages
+    /// themselves.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::enterprise_user::{EnterpriseUser, Manager};
+    /// use scim_v2::models::user::User;
+    ///
+    /// let reporting_to = |id: &str, manager: &str| User {
+    ///     id: Some(id.to_string()),
+    ///     enterprise_user: Some(EnterpriseUser {
+    ///         manager: Some(Manager { value: Some(manager.to_string()), ref_: None, display_name: None }),
+    ///         ..Default::default()
+    ///     }),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let user = reporting_to("bjensen", "jsmith");
+    /// let chain = user.resolve_manager_chain(|id| match id {
+    ///     "jsmith" => Some(reporting_to("jsmith", "mpepperidge")),
+    ///     _ => None,
+    /// });
+    /// assert_eq!(chain, vec!["jsmith", "mpepperidge"]);
+    /// ```
+    pub fn resolve_manager_chain(&self, lookup: impl Fn(&str) -> Option<User>) -> Vec<String> {
+        let mut visited: HashSet<String> = self.id.iter().cloned().collect();
+        let mut chain = Vec::new();
+        let mut manager_id = self.enterprise_user.as_ref().and_then(EnterpriseUser::manager_id).map(str::to_string);
+        while let Some(id) = manager_id.take() {
+            if !visited.insert(id.clone()) {
+                break;
+            }
+            if let Some(manager) = lookup(&id) {
+                manager_id = manager.enterprise_user.as_ref().and_then(EnterpriseUser::manager_id).map(str::to_string);
+            }
+            chain.push(id);
+        }
+        chain
+    }
+
+    /// Removes exact duplicates from the user's multi-valued attributes.
+    ///
+    /// Elements of `emails`, `phone_numbers`, `ims`, `photos`, `addresses`, `entitlements`, `roles` and `groups`
+    /// are compared on all of their fields, and only the first of each set of equal elements is kept, in its
+    /// original position. Identity providers often resend duplicated entries, so this is handy to run right
+    /// after deserialization.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let work = Email { value: Some("bjensen@example.com".to_string()), type_: Some("work".to_string()), ..Default::default() };
+    /// let mut user = User { emails: Some(vec![work.clone(), work]), ..Default::default() };
+    ///
+    /// user.dedup_multivalued();
+    /// assert_eq!(user.emails.unwrap().len(), 1);
+    /// ```
+    pub fn dedup_multivalued(&mut self) {
+        dedup_values(&mut self.emails);
+        dedup_values(&mut self.phone_numbers);
+        dedup_values(&mut self.ims);
+        dedup_values(&mut self.photos);
+        dedup_values(&mut self.addresses);
+        dedup_values(&mut self.entitlements);
+        dedup_values(&mut self.roles);
+        dedup_values(&mut self.groups);
+    }
+
+    /// Returns the email marked `primary`, if any.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let user = User {
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     emails: Some(vec![Email { value: Some("jdoe@example.com".to_string()), primary: Some(true), ..Default::default() }]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// assert_eq!(user.primary_email().unwrap().value.as_deref(), Some("jdoe@example.com"));
+    /// ```
+    pub fn primary_email(&self) -> Option<&Email> {
+        find_primary(self.emails.as_deref().unwrap_or_default())
+    }
+
+    /// Returns the emails grouped by their lowercased `type`, e.g. `"work"` and `"home"`.
+    ///
+    /// Emails without a `type` are grouped under `"(none)"`. Within each group, emails keep their original order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::{Email, User};
+    ///
+    /// let email = |value: &str, type_: &str| Email {
+    ///     value: Some(value.to_string()),
+    ///     type_: Some(type_.to_string()),
+    ///     ..Default::default()
+    /// };
+    /// let user = User {
+    ///     user_name: "bjensen".to_string(),
+    ///     emails: Some(vec![email("bjensen@example.com", "work"), email("babs@jensen.org", "Home")]),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let emails = user.emails_by_type();
+    /// assert_eq!(emails["home"][0].value.as_deref(), Some("babs@jensen.org"));
+    /// ```
+    pub fn emails_by_type(&self) -> HashMap<String, Vec<&Email>> {
+        group_by_type(&self.emails)
+    }
+
+    /// Returns the phone numbers grouped by their lowercased `type`, like `emails_by_type`.
+    pub fn phone_numbers_by_type(&self) -> HashMap<String, Vec<&PhoneNumber>> {
+        group_by_type(&self.phone_numbers)
+    }
+
+    /// Returns the instant messaging addresses grouped by their lowercased `type`, like `emails_by_type`.
+    pub fn ims_by_type(&self) -> HashMap<String, Vec<&Im>> {
+        group_by_type(&self.ims)
+    }
+
+    /// Returns the phone number marked `primary`, if any.
+    pub fn primary_phone(&self) -> Option<&PhoneNumber> {
+        find_primary(self.phone_numbers.as_deref().unwrap_or_default())
+    }
+
+    /// Marks the email whose `value` is `value` as primary, and every other email as not primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidValue` if no email has the given `value`. The emails are left unchanged.
+    pub fn set_primary_email(&mut self, value: &str) -> Result<(), SCIMError> {
+        let emails = self.emails.as_deref_mut().unwrap_or_default();
+        if !emails.iter().any(|email| email.value.as_deref() == Some(value)) {
+            return Err(SCIMError::InvalidValue(format!("emails: no value {}", value)));
+        }
+        for email in emails {
+            email.primary = Some(email.value.as_deref() == Some(value));
+        }
+        Ok(())
+    }
+
+    /// Marks the phone number whose `value` is `value` as primary, and every other phone number as not primary.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidValue` if no phone number has the given `value`. The phone numbers are left unchanged.
+    pub fn set_primary_phone(&mut self, value: &str) -> Result<(), SCIMError> {
+        let phone_numbers = self.phone_numbers.as_deref_mut().unwrap_or_default();
+        if !phone_numbers.iter().any(|phone_number| phone_number.value.as_deref() == Some(value)) {
+            return Err(SCIMError::InvalidValue(format!("phoneNumbers: no value {}", value)));
+        }
+        for phone_number in phone_numbers {
+            phone_number.primary = Some(phone_number.value.as_deref() == Some(value));
+        }
+        Ok(())
+    }
+
+    /// Serializes the `User` instance to a JSON string, using the custom SCIMError for error handling.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` contains
+    /// the JSON string representation of the `User` instance, and `Err(SCIMError)` contains
+    /// the custom error encountered during serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User {
+    ///     schemas: vec!["urn:ietf:params:scim:schemas:core:2.0:User".to_string()],
+    ///     user_name: "jdoe@example.com".to_string(),
+    ///     // Initialize other fields as necessary...
+    ///     ..Default::default()
+    /// };
+    ///
+    /// match user.serialize() {
+    ///     Ok(json) => println!("Serialized User: {}", json),
+    ///     Err(e) => println!("Serialization error: {}", e),
+    /// }
+    /// ```
+    pub fn serialize(&self) -> Result<String, SCIMError> {
+        serde_json::to_string(&self).map_err(SCIMError::SerializationError)
+    }
+
+    /// Deserializes a JSON string into a `User` instance, using the custom SCIMError for error handling.
+    ///
+    /// # Parameters
+    ///
+    /// * `json` - A string slice that holds the JSON representation of a `User`.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<User, SCIMError>`, where `Ok(User)` is the deserialized `User` instance,
+    /// and `Err(SCIMError)` is the custom error encountered during deserialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user_json = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jdoe@example.com"}"#;
+    /// match User::deserialize(user_json) {
+    ///     Ok(user) => println!("Deserialized User: {:?}", user),
+    ///     Err(e) => println!("Deserialization error: {}", e),
+    /// }
+    /// ```
+    pub fn deserialize(json: &str) -> Result<Self, SCIMError> {
+        from_json_str(json)
+    }
+
+    /// Deserializes a `User` from a reader, such as a request body, without buffering it into a string first.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::DeserializationError` if reading fails or the input is not a valid `User`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    ///
+    /// use scim_v2::models::user::User;
+    ///
+    /// let body = Cursor::new(r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}"#);
+    ///
+    /// match User::from_reader(body) {
+    ///     Ok(user) => println!("Deserialized User: {:?}", user),
+    ///     Err(e) => println!("Deserialization error: {}", e),
+    /// }
+    /// ```
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, SCIMError> {
+        from_json_reader(reader)
+    }
+
+    /// Serializes the `User` as JSON into a writer, such as a response body.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if serialization or writing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User { user_name: "bjensen".to_string(), ..Default::default() };
+    /// let mut body = Vec::new();
+    ///
+    /// match user.to_writer(&mut body) {
+    ///     Ok(_) => println!("Wrote {} bytes", body.len()),
+    ///     Err(e) => println!("Serialization error: {}", e),
+    /// }
+    /// ```
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), SCIMError> {
+        serde_json::to_writer(writer, self).map_err(SCIMError::SerializationError)
+    }
+
+    /// Serializes the `User` instance to canonical JSON: compact, with the keys of every object sorted.
+    ///
+    /// Unlike `serialize`, the output does not depend on the order of `extensions` or on serde_json's
+    /// `preserve_order` feature, so equal users always produce byte-identical strings. Use it when hashing or
+    /// signing a user.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the user cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User { user_name: "bjensen".to_string(), active: Some(true), ..Default::default() };
+    /// assert_eq!(
+    ///     user.to_canonical_json().unwrap(),
+    ///     r#"{"active":true,"schemas":["urn:ietf:params:scim:schemas:core:2.0:User"],"userName":"bjensen"}"#
+    /// );
+    /// ```
+    pub fn to_canonical_json(&self) -> Result<String, SCIMError> {
+        let value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        serde_json::to_string(&canonicalize(value)).map_err(SCIMError::SerializationError)
+    }
+
+    /// Computes the weak ETag for the user's current content, suitable for `meta.version`.
+    ///
+    /// The version is derived from the user's JSON representation (excluding `meta.version` itself), so equal
+    /// users always produce the same version and any change to an attribute produces a different one.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<String, SCIMError>`, where `Ok(String)` is a weak ETag such as
+    /// `W/"3694e05e9dff5926"`, and `Err(SCIMError)` is the custom error encountered during serialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+    /// let version = user.compute_version().unwrap();
+    /// assert!(version.starts_with("W/\""));
+    /// ```
+    pub fn compute_version(&self) -> Result<String, SCIMError> {
+        let value = serde_json::to_value(self).map_err(SCIMError::SerializationError)?;
+        Ok(weak_etag(&value))
+    }
+
+    /// Checks that a stored `meta.version` still matches the version computed from the user's content.
+    ///
+    /// A mismatch means the user was changed without `refresh_version` being called, e.g. by a manual edit or
+    /// corrupted storage. Users without `meta` or without a version are not checked.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidValue` - If `meta.version` is set and differs from `compute_version`.
+    /// * `SCIMError::SerializationError` - If the user cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+    /// user.refresh_version().unwrap();
+    /// assert!(user.verify_version().is_ok());
+    ///
+    /// user.display_name = Some("Babs Jensen".to_string());
+    /// assert!(user.verify_version().is_err());
+    /// ```
+    pub fn verify_version(&self) -> Result<(), SCIMError> {
+        let stored = match self.meta.as_ref().and_then(|meta| meta.version.as_ref()) {
+            Some(version) => version,
+            None => return Ok(()),
+        };
+        if *stored != self.compute_version()? {
+            return Err(SCIMError::InvalidValue("version mismatch".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Sets `meta.version` to the version computed from the user's current content, creating `meta` if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::SerializationError` if the user cannot be serialized.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+    /// user.refresh_version().unwrap();
+    /// assert_eq!(user.meta.as_ref().unwrap().version, Some(user.compute_version().unwrap()));
+    /// ```
+    pub fn refresh_version(&mut self) -> Result<(), SCIMError> {
+        let version = self.compute_version()?;
+        self.meta.get_or_insert_with(Meta::default).version = Some(version);
+        Ok(())
+    }
+
+    /// Evaluates a request's `If-Match` and `If-None-Match` headers against the user's `meta.version`, for
+    /// optimistic concurrency control (RFC 7644 §3.14, RFC 7232 §3.1–3.2).
+    ///
+    /// `If-Match` holds when it is `*` or lists the current version; `If-None-Match` holds when it is not `*` and
+    /// does not list the current version. Since SCIM versions are weak ETags, both compare weakly: `W/"abc"`
+    /// matches `"abc"`. A user without `meta.version` matches only `*`.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::PreconditionFailed` - If either header does not hold; the request should be answered with 412.
+    /// * `SCIMError::RequestError` - If a header is neither `*` nor a list of quoted entity tags.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    /// use scim_v2::utils::error::SCIMError;
+    ///
+    /// let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+    /// user.refresh_version().unwrap();
+    /// let version = user.meta.as_ref().unwrap().version.clone().unwrap();
+    ///
+    /// assert!(user.check_precondition(Some(&version), None).is_ok());
+    /// assert!(matches!(user.check_precondition(Some(r#"W/"stale""#), None), Err(SCIMError::PreconditionFailed(_))));
+    /// assert!(matches!(user.check_precondition(None, Some("*")), Err(SCIMError::PreconditionFailed(_))));
+    /// ```
+    pub fn check_precondition(&self, if_match: Option<&str>, if_none_match: Option<&str>) -> Result<(), SCIMError> {
+        let version = self.meta.as_ref().and_then(|meta| meta.version.as_deref());
+        let listed = |header: &str| -> Result<bool, SCIMError> {
+            match version {
+                Some(version) => etag_list_contains(header, version),
+                // Nothing matches a missing version, but a malformed header is still reported.
+                None => etag_list_contains(header, "").map(|_| false),
+            }
+        };
+
+        if let Some(header) = if_match {
+            if header.trim() != "*" && !listed(header)? {
+                return Err(SCIMError::PreconditionFailed(format!("If-Match {} does not match the current version", header)));
+            }
+        }
+        if let Some(header) = if_none_match {
+            if header.trim() == "*" || listed(header)? {
+                return Err(SCIMError::PreconditionFailed(format!("If-None-Match {} matches the current version", header)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Deserializes a JSON string into a `User` instance, matching attribute names case-insensitively.
+    ///
+    /// SCIM attribute names are case-insensitive (RFC 7643 §2.1), but `deserialize` only recognises the canonical
+    /// camelCase names and silently ignores anything else. This method first rewrites keys such as `USERNAME`
+    /// or `Emails` to their canonical form, at every level of nesting. Schema extension URN keys are kept verbatim.
+    ///
+    /// # Returns
+    ///
+    /// This method returns a `Result<User, SCIMError>`, where `Ok(User)` is the deserialized `User` instance,
+    /// and `Err(SCIMError)` is the custom error encountered during deserialization.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user_json = r#"{"SCHEMAS": ["urn:ietf:params:scim:schemas:core:2.0:User"], "UserName": "jdoe@example.com"}"#;
+    /// let user = User::deserialize_lenient(user_json).unwrap();
+    /// assert_eq!(user.user_name, "jdoe@example.com");
+    /// ```
+    pub fn deserialize_lenient(json: &str) -> Result<Self, SCIMError> {
+        let value: Value = serde_json::from_str(json).map_err(SCIMError::DeserializationError)?;
+        from_json_value(normalize_keys(value))
+    }
+
+    /// Deserializes a JSON string into a `User` instance, rejecting top-level attributes the model would drop.
+    ///
+    /// `deserialize` silently ignores attributes it does not know. This method instead compares the input's
+    /// top-level keys with those of the deserialized user and fails if any were lost, which helps to spot
+    /// attributes an identity provider sends that the model does not capture. Schema extensions are kept
+    /// (see `extensions`), and `null` or empty-array attributes are treated as absent rather than dropped.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::DeserializationError` - If the JSON string is not a valid `User`.
+    /// * `SCIMError::InvalidValue` - Listing the top-level attributes that would be dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scim_v2::models::user::User;
+    ///
+    /// let user_json = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jdoe", "favoriteColor": "green"}"#;
+    ///
+    /// match User::deserialize_strict(user_json) {
+    ///     Ok(user) => println!("Deserialized User: {:?}", user),
+    ///     Err(e) => println!("Deserialization error: {}", e),
+    /// }
+    /// ```
+    pub fn deserialize_strict(json: &str) -> Result<Self, SCIMError> {
+        let original: Value = serde_json::from_str(json).map_err(SCIMError::DeserializationError)?;
+        let user = User::try_from(original.clone())?;
+        let kept = Value::from(&user);
+
+        let dropped: Vec<&str> = original
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(_, value)| !value.is_null() && value.as_array().map_or(true, |items| !items.is_empty()))
+            .filter(|(key, _)| kept.get(key.as_str()).is_none())
+            .map(|(key, _)| key.as_str())
+            .collect();
+        if !dropped.is_empty() {
+            return Err(SCIMError::InvalidValue(format!("unknown attributes: {}", dropped.join(", "))));
+        }
+        Ok(user)
+    }
+
+    /// Assembles a `User` from the core attributes and the schema extension objects, keyed by their URN.
+    ///
+    /// Each extension object is placed under its URN in `core`, replacing any object already there, and the whole
+    /// is deserialized as a `User`: the enterprise extension ends up in `enterprise_user` and the rest in
+    /// `extensions`. Every extension URN is then added to `schemas` if it is not listed already.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::DeserializationError` if `core` is not a JSON object, or if the merged object is not a
+    /// valid `User`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::collections::HashMap;
+    ///
+    /// use scim_v2::models::user::User;
+    ///
+    /// let core = serde_json::json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"});
+    /// let extensions = HashMap::from([(
+    ///     "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User".to_string(),
+    ///     serde_json::json!({"employeeNumber": "701984"}),
+    /// )]);
+    ///
+    /// match User::from_core_and_extensions(core, extensions) {
+    ///     Ok(user) => println!("Assembled User: {:?}", user),
+    ///     Err(e) => println!("Deserialization error: {}", e),
+    /// }
+    /// ```
+    pub fn from_core_and_extensions(core: Value, extensions: HashMap<String, Value>) -> Result<Self, SCIMError> {
+        let mut document = match core {
+            Value::Object(fields) => fields,
+            _ => {
+                let error = <serde_json::Error as serde::de::Error>::custom("core attributes must be a JSON object");
+                return Err(SCIMError::DeserializationError(error));
+            }
+        };
+        let urns: Vec<String> = extensions.keys().cloned().collect();
+        document.extend(extensions);
+
+        let mut user = User::try_from(Value::Object(document))?;
+        for urn in urns {
+            if !user.schemas.iter().any(|schema| schema.eq_ignore_ascii_case(&urn)) {
+                user.schemas.push(urn);
+            }
+        }
+        Ok(user)
+    }
+}
+
+/// A builder for `User`.
+///
+/// `schemas` defaults to the core User schema URN, and the enterprise extension URN is added when an
+/// enterprise extension is attached. `build` validates the resulting user.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::{Email, User};
+///
+/// let user = User::builder()
+///     .user_name("bjensen@example.com")
+///     .display_name("Babs Jensen")
+///     .add_email(Email {
+///         value: Some("bjensen@example.com".to_string()),
+///         type_: Some("work".to_string()),
+///         primary: Some(true),
+///         ..Default::default()
+///     })
+///     .build();
+///
+/// match user {
+///     Ok(user) => println!("Built User: {:?}", user),
+///     Err(e) => println!("User is invalid: {}", e),
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct UserBuilder {
+    user: User,
+}
+
+impl UserBuilder {
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.user.id = Some(id.into());
+        self
+    }
+
+    pub fn user_name(mut self, user_name: impl Into<String>) -> Self {
+        self.user.user_name = user_name.into();
+        self
+    }
+
+    pub fn name(mut self, name: Name) -> Self {
+        self.user.name = Some(name);
+        self
+    }
+
+    pub fn display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.user.display_name = Some(display_name.into());
+        self
+    }
+
+    pub fn nick_name(mut self, nick_name: impl Into<String>) -> Self {
+        self.user.nick_name = Some(nick_name.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.user.title = Some(title.into());
+        self
+    }
+
+    pub fn active(mut self, active: bool) -> Self {
+        self.user.active = Some(active);
+        self
+    }
+
+    pub fn add_email(mut self, email: Email) -> Self {
+        self.user.emails.get_or_insert_with(Vec::new).push(email);
+        self
+    }
+
+    pub fn add_phone_number(mut self, phone_number: PhoneNumber) -> Self {
+        self.user.phone_numbers.get_or_insert_with(Vec::new).push(phone_number);
+        self
+    }
+
+    pub fn add_address(mut self, address: Address) -> Self {
+        self.user.addresses.get_or_insert_with(Vec::new).push(address);
+        self
+    }
+
+    /// Attaches the enterprise extension and lists its schema URN in `schemas`.
+    pub fn enterprise(mut self, enterprise_user: EnterpriseUser) -> Self {
+        self.user.set_enterprise(enterprise_user);
+        self
+    }
+
+    /// Validates and returns the built `User`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error from `User::validate` if the user is invalid, e.g. `SCIMError::MissingRequiredField`
+    /// when no `user_name` was set.
+    pub fn build(self) -> Result<User, SCIMError> {
+        self.user.validate()?;
+        Ok(self.user)
+    }
+}
+
+impl User {
+    /// Returns a `UserBuilder` for constructing a `User`.
+    pub fn builder() -> UserBuilder {
+        UserBuilder::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Import everything from the outer module
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::enterprise_user::Manager;
+
+    #[test]
+    fn user_deserialization_with_minimum_fields() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen@example.com",
+            "meta": {
+                "resourceType": "User",
+                "created": "2010-01-23T04:56:22Z",
+                "lastModified": "2011-05-13T04:42:34Z",
+                "version": "W/\"3694e05e9dff590\"",
+                "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
+            }
+        }"#;
+
+        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+
+        if let Err(e) = &user {
+            eprintln!("Deserialization failed: {:?}", e);
+        }
+        assert!(user.is_ok());
+        let user = user.unwrap();
+        assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User"]);
+        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".to_string()));
+        assert_eq!(user.user_name, "bjensen@example.com");
+        let meta = user.meta.unwrap();
+        assert_eq!(meta.resource_type, Some("User".to_string()));
+        #[cfg(not(feature = "chrono"))]
+        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".to_string()));
+        #[cfg(not(feature = "chrono"))]
+        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".to_string()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
+        assert_eq!(meta.version, Some("W/\"3694e05e9dff590\"".to_string()));
+        assert_eq!(meta.location, Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646".to_string()));
+    }
+
+    #[test]
     fn user_deserialization_with_all_fields() {
         let json_data = r#"{
             "schemas": [
@@ -434,7 +2465,169 @@ mod tests {
             "displayName": "Babs Jensen",
             "nickName": "Babs",
             "profileUrl": "https://login.example.com/bjensen",
-            "emails": [
+            "emails": [
+                {
+                    "value": "bjensen@example.com",
+                    "type": "work",
+                    "primary": true
+                },
+                {
+                    "value": "babs@jensen.org",
+                    "type": "home"
+                }
+            ],
+            "addresses": [
+                {
+                    "type": "work",
+                    "streetAddress": "100 Universal City Plaza",
+                    "locality": "Hollywood",
+                    "region": "CA",
+                    "postalCode": "91608",
+                    "country": "USA",
+                    "formatted": "100 Universal City Plaza\nHollywood, CA 91608 USA",
+                    "primary": true
+                },
+                {
+                    "type": "home",
+                    "streetAddress": "456 Hollywood Blvd",
+                    "locality": "Hollywood",
+                    "region": "CA",
+                    "postalCode": "91608",
+                    "country": "USA",
+                    "formatted": "456 Hollywood Blvd\nHollywood, CA 91608 USA"
+                }
+            ],
+            "phoneNumbers": [
+                {
+                    "value": "555-555-5555",
+                    "type": "work"
+                },
+                {
+                    "value": "555-555-4444",
+                    "type": "mobile"
+                }
+            ],
+            "ims": [
+                {
+                    "value": "someaimhandle",
+                    "type": "aim"
+                }
+            ],
+            "photos": [
+                {
+                    "value": "https://photos.example.com/profilephoto/72930000000Ccne/F",
+                    "type": "photo"
+                },
+                {
+                    "value": "https://photos.example.com/profilephoto/72930000000Ccne/T",
+                    "type": "thumbnail"
+                }
+            ],
+            "userType": "Employee",
+            "title": "Tour Guide",
+            "preferredLanguage": "en-US",
+            "locale": "en-US",
+            "timezone": "America/Los_Angeles",
+            "active": true,
+            "password": "t1meMa$heen",
+            "groups": [
+                {
+                    "value": "e9e30dba-f08f-4109-8486-d5c6a331660a",
+                    "$ref": "https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a",
+                    "display": "Tour Guides"
+                },
+                {
+                    "value": "fc348aa8-3835-40eb-a20b-c726e15c55b5",
+                    "$ref": "https://example.com/v2/Groups/fc348aa8-3835-40eb-a20b-c726e15c55b5",
+                    "display": "Employees"
+                },
+                {
+                    "value": "71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
+                    "$ref": "https://example.com/v2/Groups/71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
+                    "display": "US Employees"
+                }
+            ],
+            "x509Certificates": [
+                {
+                    "value": "MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo="
+                }
+            ],
+            "meta": {
+                "resourceType": "User",
+                "created": "2010-01-23T04:56:22Z",
+                "lastModified": "2011-05-13T04:42:34Z",
+                "version": "W/\"a330bc54f0671c9\"",
+                "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
+            }
+        }"#;
+
+        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+
+        if let Err(e) = &user {
+            eprintln!("Deserialization failed: {:?}", e);
+        }
+
+        assert!(user.is_ok());
+        let user = user.unwrap();
+        assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User"]);
+        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".to_string()));
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert_eq!(user.name.as_ref().unwrap().formatted, Some("Ms. Barbara J Jensen, III".to_string()));
+        assert_eq!(user.display_name, Some("Babs Jensen".to_string()));
+        assert_eq!(user.nick_name, Some("Babs".to_string()));
+        assert_eq!(user.profile_url, Some("https://login.example.com/bjensen".to_string()));
+        assert_eq!(user.emails.as_ref().unwrap().len(), 2);
+        assert_eq!(user.emails.as_ref().unwrap()[0].value, Some("bjensen@example.com".to_string()));
+        assert_eq!(user.emails.as_ref().unwrap()[0].type_, Some("work".to_string()));
+        assert_eq!(user.addresses.as_ref().unwrap().len(), 2);
+        assert_eq!(user.addresses.as_ref().unwrap()[0].type_.as_ref().unwrap(), "work");
+        assert_eq!(user.phone_numbers.as_ref().unwrap().len(), 2);
+        assert_eq!(user.phone_numbers.as_ref().unwrap()[0].value, Some("555-555-5555".to_string()));
+        assert_eq!(user.ims.as_ref().unwrap().len(), 1);
+        assert_eq!(user.ims.as_ref().unwrap()[0].value, Some("someaimhandle".to_string()));
+        assert_eq!(user.groups.as_ref().unwrap().len(), 3);
+        assert_eq!(user.groups.as_ref().unwrap()[0].value, Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()));
+        assert_eq!(user.x509_certificates.as_ref().unwrap().len(), 1);
+        assert_eq!(user.x509_certificates.as_ref().unwrap()[0].value, Some("MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo=".to_string()), "x509_certificates[0].value did not match expected value");
+        let meta = user.meta.unwrap();
+        assert_eq!(meta.resource_type, Some("User".to_string()));
+        #[cfg(not(feature = "chrono"))]
+        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".to_string()));
+        #[cfg(not(feature = "chrono"))]
+        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".to_string()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
+        #[cfg(feature = "chrono")]
+        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".parse::<chrono::DateTime<chrono::Utc>>().unwrap()));
+        assert_eq!(meta.version, Some("W/\"a330bc54f0671c9\"".to_string()));
+        assert_eq!(meta.location, Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646".to_string()));
+    }
+
+    #[test]
+    fn user_deserialization_with_enterprise_user_extension() {
+        let json_data = r#"{
+            "schemas":
+            [
+                "urn:ietf:params:scim:schemas:core:2.0:User",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
+            ],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "externalId": "701984",
+            "userName": "bjensen@example.com",
+            "name":
+            {
+                "formatted": "Ms. Barbara J Jensen, III",
+                "familyName": "Jensen",
+                "givenName": "Barbara",
+                "middleName": "Jane",
+                "honorificPrefix": "Ms.",
+                "honorificSuffix": "III"
+            },
+            "displayName": "Babs Jensen",
+            "nickName": "Babs",
+            "profileUrl": "https://login.example.com/bjensen",
+            "emails":
+            [
                 {
                     "value": "bjensen@example.com",
                     "type": "work",
@@ -445,28 +2638,30 @@ mod tests {
                     "type": "home"
                 }
             ],
-            "addresses": [
+            "addresses":
+            [
                 {
-                    "type": "work",
                     "streetAddress": "100 Universal City Plaza",
                     "locality": "Hollywood",
                     "region": "CA",
                     "postalCode": "91608",
                     "country": "USA",
                     "formatted": "100 Universal City Plaza\nHollywood, CA 91608 USA",
+                    "type": "work",
                     "primary": true
                 },
                 {
-                    "type": "home",
                     "streetAddress": "456 Hollywood Blvd",
                     "locality": "Hollywood",
                     "region": "CA",
                     "postalCode": "91608",
                     "country": "USA",
-                    "formatted": "456 Hollywood Blvd\nHollywood, CA 91608 USA"
+                    "formatted": "456 Hollywood Blvd\nHollywood, CA 91608 USA",
+                    "type": "home"
                 }
             ],
-            "phoneNumbers": [
+            "phoneNumbers":
+            [
                 {
                     "value": "555-555-5555",
                     "type": "work"
@@ -476,13 +2671,15 @@ mod tests {
                     "type": "mobile"
                 }
             ],
-            "ims": [
+            "ims":
+            [
                 {
                     "value": "someaimhandle",
                     "type": "aim"
                 }
             ],
-            "photos": [
+            "photos":
+            [
                 {
                     "value": "https://photos.example.com/profilephoto/72930000000Ccne/F",
                     "type": "photo"
@@ -499,248 +2696,1684 @@ mod tests {
             "timezone": "America/Los_Angeles",
             "active": true,
             "password": "t1meMa$heen",
-            "groups": [
+            "groups":
+            [
                 {
                     "value": "e9e30dba-f08f-4109-8486-d5c6a331660a",
-                    "$ref": "https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a",
+                    "$ref": "../Groups/e9e30dba-f08f-4109-8486-d5c6a331660a",
                     "display": "Tour Guides"
                 },
                 {
                     "value": "fc348aa8-3835-40eb-a20b-c726e15c55b5",
-                    "$ref": "https://example.com/v2/Groups/fc348aa8-3835-40eb-a20b-c726e15c55b5",
+                    "$ref": "../Groups/fc348aa8-3835-40eb-a20b-c726e15c55b5",
                     "display": "Employees"
                 },
                 {
                     "value": "71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
-                    "$ref": "https://example.com/v2/Groups/71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
+                    "$ref": "../Groups/71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
                     "display": "US Employees"
                 }
             ],
-            "x509Certificates": [
+            "x509Certificates":
+            [
                 {
-                    "value": "MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo="
+                  "value": "MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo="
+
+                }
+            ],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User":
+            {
+                "employeeNumber": "701984",
+                "costCenter": "4130",
+                "organization": "Universal Studios",
+                "division": "Theme Park",
+                "department": "Tour Operations",
+                "manager":
+                {
+                    "value": "26118915-6090-4610-87e4-49d8ca9f808d",
+                    "$ref": "../Users/26118915-6090-4610-87e4-49d8ca9f808d",
+                    "displayName": "John Smith"
+                }
+            },
+            "meta":
+            {
+                "resourceType": "User",
+                "created": "2010-01-23T04:56:22Z",
+                "lastModified": "2011-05-13T04:42:34Z",
+                "version": "W/\"3694e05e9dff591\"",
+                "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
+            }
+        }"#;
+
+        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+
+        if let Err(e) = &user {
+            eprintln!("Deserialization failed: {:?}", e);
+        }
+        assert!(user.is_ok());
+        let user = user.unwrap();
+        let enterprise_user = user.enterprise_user.unwrap();
+        std::assert_eq!(enterprise_user.employee_number, Some("701984".to_string()));
+        std::assert_eq!(enterprise_user.cost_center, Some("4130".to_string()));
+        std::assert_eq!(enterprise_user.organization, Some("Universal Studios".to_string()));
+        std::assert_eq!(enterprise_user.division, Some("Theme Park".to_string()));
+        std::assert_eq!(enterprise_user.department, Some("Tour Operations".to_string()));
+        let manager = enterprise_user.manager.unwrap();
+        std::assert_eq!(manager.value, Some("26118915-6090-4610-87e4-49d8ca9f808d".to_string()));
+        std::assert_eq!(manager.display_name, Some("John Smith".to_string()));
+    }
+
+    fn user_with_email(value: &str) -> User {
+        User {
+            user_name: "bjensen@example.com".to_string(),
+            emails: Some(vec![Email {
+                value: Some(value.to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_strict_accepts_valid_email() {
+        assert!(user_with_email("bjensen@example.com").validate_strict().is_ok());
+    }
+
+    #[test]
+    fn validate_strict_rejects_empty_email() {
+        let result = user_with_email("").validate_strict();
+        assert!(matches!(result, Err(SCIMError::InvalidFieldValue(_))));
+    }
+
+    #[test]
+    fn validate_strict_rejects_malformed_emails() {
+        for value in ["bjensen.example.com", "@example.com", "bjensen@", "b@jensen@example.com"] {
+            match user_with_email(value).validate_strict() {
+                Err(SCIMError::InvalidFieldValue(msg)) => assert_eq!(msg, format!("emails: {}", value)),
+                other => panic!("expected InvalidFieldValue for {}, got {:?}", value, other),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_does_not_check_email_format() {
+        assert!(user_with_email("not-an-email").validate().is_ok());
+    }
+
+    fn assert_multiple_primary_error(user: User, attribute: &str) {
+        match user.validate_primary_uniqueness() {
+            Err(SCIMError::InvalidFieldValue(msg)) => assert_eq!(msg, format!("{}: multiple primary values", attribute)),
+            other => panic!("expected InvalidFieldValue for {}, got {:?}", attribute, other),
+        }
+        assert!(user.validate_strict().is_err());
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_allows_single_primary() {
+        let user = User {
+            user_name: "bjensen@example.com".to_string(),
+            emails: Some(vec![
+                Email { value: Some("bjensen@example.com".to_string()), primary: Some(true), ..Default::default() },
+                Email { value: Some("babs@jensen.org".to_string()), primary: Some(false), ..Default::default() },
+                Email { value: Some("barbara@example.org".to_string()), ..Default::default() },
+            ]),
+            ..Default::default()
+        };
+        assert!(user.validate_primary_uniqueness().is_ok());
+        assert!(user.validate_strict().is_ok());
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_rejects_multiple_primary_emails() {
+        let email = || Email { value: Some("bjensen@example.com".to_string()), primary: Some(true), ..Default::default() };
+        let user = User { user_name: "bjensen".to_string(), emails: Some(vec![email(), email()]), ..Default::default() };
+        assert_multiple_primary_error(user, "emails");
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_rejects_multiple_primary_phone_numbers() {
+        let phone = || PhoneNumber { value: Some("555-555-5555".to_string()), primary: Some(true), ..Default::default() };
+        let user = User { user_name: "bjensen".to_string(), phone_numbers: Some(vec![phone(), phone()]), ..Default::default() };
+        assert_multiple_primary_error(user, "phoneNumbers");
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_rejects_multiple_primary_ims() {
+        let im = || Im { value: Some("someaimhandle".to_string()), primary: Some(true), ..Default::default() };
+        let user = User { user_name: "bjensen".to_string(), ims: Some(vec![im(), im()]), ..Default::default() };
+        assert_multiple_primary_error(user, "ims");
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_rejects_multiple_primary_photos() {
+        let photo = || Photo { value: Some("https://photos.example.com/F".to_string()), primary: Some(true), ..Default::default() };
+        let user = User { user_name: "bjensen".to_string(), photos: Some(vec![photo(), photo()]), ..Default::default() };
+        assert_multiple_primary_error(user, "photos");
+    }
+
+    #[test]
+    fn validate_primary_uniqueness_rejects_multiple_primary_addresses() {
+        let address = || Address { locality: Some("Hollywood".to_string()), primary: Some(true), ..Default::default() };
+        let user = User { user_name: "bjensen".to_string(), addresses: Some(vec![address(), address()]), ..Default::default() };
+        assert_multiple_primary_error(user, "addresses");
+    }
+
+    #[test]
+    fn user_builder_builds_minimal_user() {
+        let user = User::builder().user_name("bjensen@example.com").build().unwrap();
+        assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User"]);
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert!(user.emails.is_none());
+        assert!(user.enterprise_user.is_none());
+    }
+
+    #[test]
+    fn user_builder_builds_fully_populated_user() {
+        let user = User::builder()
+            .id("2819c223-7f76-453a-919d-413861904646")
+            .user_name("bjensen@example.com")
+            .name(Name {
+                given_name: Some("Barbara".to_string()),
+                family_name: Some("Jensen".to_string()),
+                ..Default::default()
+            })
+            .display_name("Babs Jensen")
+            .nick_name("Babs")
+            .title("Tour Guide")
+            .active(true)
+            .add_email(Email { value: Some("bjensen@example.com".to_string()), primary: Some(true), ..Default::default() })
+            .add_email(Email { value: Some("babs@jensen.org".to_string()), ..Default::default() })
+            .add_phone_number(PhoneNumber { value: Some("555-555-5555".to_string()), ..Default::default() })
+            .add_address(Address { locality: Some("Hollywood".to_string()), ..Default::default() })
+            .enterprise(EnterpriseUser { department: Some("Tour Operations".to_string()), ..Default::default() })
+            .build()
+            .unwrap();
+
+        assert_eq!(user.schemas, vec![
+            "urn:ietf:params:scim:schemas:core:2.0:User",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User",
+        ]);
+        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".to_string()));
+        assert_eq!(user.name.unwrap().given_name, Some("Barbara".to_string()));
+        assert_eq!(user.display_name, Some("Babs Jensen".to_string()));
+        assert_eq!(user.nick_name, Some("Babs".to_string()));
+        assert_eq!(user.title, Some("Tour Guide".to_string()));
+        assert_eq!(user.active, Some(true));
+        assert_eq!(user.emails.unwrap().len(), 2);
+        assert_eq!(user.phone_numbers.unwrap().len(), 1);
+        assert_eq!(user.addresses.unwrap().len(), 1);
+        assert_eq!(user.enterprise_user.unwrap().department, Some("Tour Operations".to_string()));
+    }
+
+    #[test]
+    fn user_builder_rejects_missing_user_name() {
+        let result = User::builder().display_name("Babs Jensen").build();
+        assert!(matches!(result, Err(SCIMError::MissingRequiredField(_))));
+    }
+
+    #[test]
+    fn user_deserialization_without_enterprise_user_extension() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen@example.com"
+        }"#;
+
+        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+
+        if let Err(e) = &user {
+            eprintln!("Deserialization failed: {:?}", e);
+        }
+        assert!(user.is_ok());
+        let user = user.unwrap();
+        assert!(user.enterprise_user.is_none());
+    }
+
+    #[test]
+    fn deserialize_lenient_matches_attribute_names_case_insensitively() {
+        let json_data = r#"{
+            "Schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            "UserName": "bjensen@example.com",
+            "NAME": {"GIVENNAME": "Barbara"},
+            "EMAILS": [{"Value": "bjensen@example.com", "TYPE": "work", "Primary": true}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"EmployeeNumber": "701984"}
+        }"#;
+
+        let user = User::deserialize_lenient(json_data).unwrap();
+
+        assert_eq!(user.user_name, "bjensen@example.com");
+        assert_eq!(user.name.unwrap().given_name, Some("Barbara".to_string()));
+        let emails = user.emails.unwrap();
+        assert_eq!(emails[0].value, Some("bjensen@example.com".to_string()));
+        assert_eq!(emails[0].type_, Some("work".to_string()));
+        assert_eq!(emails[0].primary, Some(true));
+        assert_eq!(user.enterprise_user.unwrap().employee_number, Some("701984".to_string()));
+    }
+
+    #[test]
+    fn deserialize_lenient_keeps_extension_urns_verbatim() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen@example.com",
+            "URN:IETF:PARAMS:SCIM:SCHEMAS:EXTENSION:ENTERPRISE:2.0:USER": {"employeeNumber": "701984"}
+        }"#;
+
+        let user = User::deserialize_lenient(json_data).unwrap();
+
+        assert!(user.enterprise_user.is_none());
+    }
+
+    fn contains_null(value: &serde_json::Value) -> bool {
+        match value {
+            serde_json::Value::Null => true,
+            serde_json::Value::Array(items) => items.iter().any(contains_null),
+            serde_json::Value::Object(fields) => fields.values().any(contains_null),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn user_serialization_omits_null_fields() {
+        let user = User {
+            user_name: "bjensen@example.com".to_string(),
+            name: Some(Name { given_name: Some("Barbara".to_string()), ..Default::default() }),
+            emails: Some(vec![Email { value: Some("bjensen@example.com".to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+
+        for user in [User::default(), user] {
+            let json: serde_json::Value = serde_json::from_str(&user.serialize().unwrap()).unwrap();
+            assert!(!contains_null(&json), "{}", json);
+            assert!(json.get("schemas").is_some());
+        }
+    }
+
+    #[test]
+    fn user_serialization_omits_empty_multi_valued_attributes() {
+        let user = User {
+            schemas: vec![],
+            user_name: "bjensen@example.com".to_string(),
+            emails: Some(vec![]),
+            roles: Some(vec![]),
+            ..Default::default()
+        };
+
+        let json: serde_json::Value = serde_json::from_str(&user.serialize().unwrap()).unwrap();
+
+        assert_eq!(json, serde_json::json!({"schemas": [], "userName": "bjensen@example.com"}));
+    }
+
+    #[test]
+    fn compute_version_is_stable_for_equal_users() {
+        let build = || User {
+            user_name: "bjensen@example.com".to_string(),
+            display_name: Some("Babs Jensen".to_string()),
+            ..Default::default()
+        };
+
+        let version = build().compute_version().unwrap();
+
+        assert_eq!(version, build().compute_version().unwrap());
+        assert!(version.starts_with("W/\"") && version.ends_with('"'));
+    }
+
+    #[test]
+    fn compute_version_changes_when_content_changes() {
+        let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+        let before = user.compute_version().unwrap();
+
+        user.display_name = Some("Babs Jensen".to_string());
+
+        assert_ne!(before, user.compute_version().unwrap());
+    }
+
+    #[test]
+    fn refresh_version_ignores_existing_version() {
+        let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+        user.refresh_version().unwrap();
+        let version = user.meta.as_ref().unwrap().version.clone();
+
+        user.refresh_version().unwrap();
+
+        assert_eq!(user.meta.unwrap().version, version);
+    }
+
+    #[test]
+    fn validate_schema_consistency_succeeds_when_consistent() {
+        let without_extension = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+        let with_extension = User {
+            schemas: vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string()],
+            enterprise_user: Some(EnterpriseUser::default()),
+            ..Default::default()
+        };
+
+        assert!(without_extension.validate_schema_consistency().is_ok());
+        assert!(with_extension.validate_schema_consistency().is_ok());
+    }
+
+    #[test]
+    fn validate_schema_consistency_fails_when_urn_missing() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen@example.com",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"employeeNumber": "701984"}
+        }"#;
+        let user = User::deserialize(json_data).unwrap();
+
+        assert!(matches!(user.validate_schema_consistency(), Err(SCIMError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn validate_schema_consistency_fails_when_extension_missing() {
+        let user = User {
+            schemas: vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string()],
+            user_name: "bjensen@example.com".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(user.validate_schema_consistency(), Err(SCIMError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn type_enum_recognizes_canonical_values() {
+        let email: Email = serde_json::from_str(r#"{"value": "bjensen@example.com", "type": "Work"}"#).unwrap();
+        let phone: PhoneNumber = serde_json::from_str(r#"{"value": "555-555-8377", "type": "mobile"}"#).unwrap();
+        let im: Im = serde_json::from_str(r#"{"value": "someaimhandle", "type": "aim"}"#).unwrap();
+
+        assert_eq!(email.type_enum(), Some(EmailType::Work));
+        assert_eq!(phone.type_enum(), Some(PhoneType::Mobile));
+        assert_eq!(im.type_enum(), Some(ImType::Aim));
+        assert_eq!(Email::default().type_enum(), None);
+    }
+
+    #[test]
+    fn type_enum_keeps_custom_values() {
+        let email: Email = serde_json::from_str(r#"{"value": "bjensen@example.com", "type": "school"}"#).unwrap();
+
+        assert_eq!(email.type_enum(), Some(EmailType::Other("school".to_string())));
+        assert_eq!(PhoneType::from("other".to_string()), PhoneType::Other("other".to_string()));
+    }
+
+    #[test]
+    fn type_enums_serialize_to_canonical_strings() {
+        assert_eq!(serde_json::to_string(&EmailType::Home).unwrap(), r#""home""#);
+        assert_eq!(serde_json::to_string(&PhoneType::Other("satellite".to_string())).unwrap(), r#""satellite""#);
+        assert_eq!(serde_json::from_str::<ImType>(r#""XMPP""#).unwrap(), ImType::Xmpp);
+        assert_eq!(serde_json::from_str::<ImType>(r#""matrix""#).unwrap(), ImType::Other("matrix".to_string()));
+    }
+
+    #[test]
+    fn user_round_trips_through_json_value() {
+        let user = User::builder()
+            .id("2819c223-7f76-453a-919d-413861904646")
+            .user_name("bjensen@example.com")
+            .display_name("Babs Jensen")
+            .add_email(Email { value: Some("bjensen@example.com".to_string()), type_: Some("work".to_string()), primary: Some(true), ..Default::default() })
+            .enterprise(EnterpriseUser { employee_number: Some("701984".to_string()), ..Default::default() })
+            .build()
+            .unwrap();
+
+        let value = Value::from(&user);
+        assert_eq!(value["emails"][0]["type"], "work");
+
+        let round_tripped = User::try_from(value.clone()).unwrap();
+        assert_eq!(round_tripped.user_name, "bjensen@example.com");
+        assert_eq!(round_tripped.enterprise_user.unwrap().employee_number, Some("701984".to_string()));
+        assert_eq!(Value::from(&User::try_from(value.clone()).unwrap()), value);
+    }
+
+    #[test]
+    fn user_try_from_value_fails_for_invalid_value() {
+        let result = User::try_from(serde_json::json!({"userName": 42}));
+        assert!(matches!(result, Err(SCIMError::DeserializationError(_))));
+    }
+
+    #[test]
+    fn phone_number_normalize_strips_formatting() {
+        let cases = [
+            ("555-555-5555", "5555555555"),
+            ("(555) 555 5555", "5555555555"),
+            ("+1 555 555 5555", "+15555555555"),
+            ("tel:+1-201-555-0123", "+12015550123"),
+            ("0044 20 7946 0958", "+442079460958"),
+        ];
+        for (value, expected) in cases {
+            let mut phone = PhoneNumber { value: Some(value.to_string()), ..Default::default() };
+            phone.normalize();
+            assert_eq!(phone.value.as_deref(), Some(expected), "{}", value);
+            assert!(phone.validate().is_ok(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn phone_number_validate_rejects_letters() {
+        let mut phone = PhoneNumber { value: Some("555-CALL-NOW".to_string()), ..Default::default() };
+        phone.normalize();
+
+        assert_eq!(phone.value.as_deref(), Some("555-CALL-NOW"));
+        match phone.validate() {
+            Err(SCIMError::InvalidFieldValue(msg)) => assert_eq!(msg, "phoneNumbers: 555-CALL-NOW"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+        let user = User { user_name: "bjensen".to_string(), phone_numbers: Some(vec![phone]), ..Default::default() };
+        assert!(user.validate_strict().is_err());
+    }
+
+    #[test]
+    fn phone_number_validate_accepts_tel_uri_parameters() {
+        for value in ["tel:+1-201-555-0123;ext=1234", "tel:7042;phone-context=example.com"] {
+            let phone = PhoneNumber { value: Some(value.to_string()), ..Default::default() };
+            assert!(phone.validate().is_ok(), "{}", value);
+            let user = User { user_name: "bjensen".to_string(), phone_numbers: Some(vec![phone]), ..Default::default() };
+            assert!(user.validate_strict().is_ok(), "{}", value);
+        }
+        for value in ["+1-201-555-0123;ext=1234", "tel:+1-201-555-0123;", "tel:+1-201-555-0123;=1234"] {
+            let phone = PhoneNumber { value: Some(value.to_string()), ..Default::default() };
+            assert!(phone.validate().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn user_group_reference_deserializes_type() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen",
+            "groups": [
+                {
+                    "value": "e9e30dba-f08f-4109-8486-d5c6a331660a",
+                    "$ref": "https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a",
+                    "display": "Tour Guides",
+                    "type": "direct"
                 }
+            ]
+        }"#;
+
+        let user = User::deserialize(json_data).unwrap();
+
+        let groups = user.groups.as_ref().unwrap();
+        assert_eq!(groups[0].type_, Some("direct".to_string()));
+        assert_eq!(groups[0].ref_, Some("https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()));
+        assert!(user.serialize().unwrap().contains(r#""type":"direct""#));
+    }
+
+    fn user_with_emails() -> User {
+        let email = |value: &str, primary: Option<bool>| Email { value: Some(value.to_string()), primary, ..Default::default() };
+        User {
+            user_name: "bjensen".to_string(),
+            emails: Some(vec![
+                email("bjensen@example.com", Some(false)),
+                email("babs@jensen.org", Some(true)),
+                email("barbara@example.org", None),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn primary_email_returns_primary_entry() {
+        let user = user_with_emails();
+
+        assert_eq!(user.primary_email().unwrap().value.as_deref(), Some("babs@jensen.org"));
+        assert!(User::default().primary_email().is_none());
+        assert!(user.primary_phone().is_none());
+    }
+
+    #[test]
+    fn set_primary_email_redesignates_primary() {
+        let mut user = user_with_emails();
+
+        user.set_primary_email("barbara@example.org").unwrap();
+
+        assert_eq!(user.primary_email().unwrap().value.as_deref(), Some("barbara@example.org"));
+        let primaries: Vec<Option<bool>> = user.emails.unwrap().iter().map(|email| email.primary).collect();
+        assert_eq!(primaries, vec![Some(false), Some(false), Some(true)]);
+    }
+
+    #[test]
+    fn set_primary_email_fails_for_unknown_value() {
+        let mut user = user_with_emails();
+
+        assert!(matches!(user.set_primary_email("nobody@example.com"), Err(SCIMError::InvalidValue(_))));
+        assert_eq!(user.primary_email().unwrap().value.as_deref(), Some("babs@jensen.org"));
+        assert!(matches!(User::default().set_primary_phone("555-555-5555"), Err(SCIMError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn custom_extension_round_trips() {
+        let json_data = serde_json::json!({
+            "schemas": [
+                "urn:ietf:params:scim:schemas:core:2.0:User",
+                "urn:acme:params:scim:schemas:extension:2.0:User"
             ],
+            "userName": "bjensen",
+            "urn:acme:params:scim:schemas:extension:2.0:User": {
+                "badgeNumber": "42",
+                "clearances": ["lobby", "lab"]
+            }
+        });
+        let mut with_unknown_member = json_data.clone();
+        with_unknown_member["favouriteColour"] = serde_json::json!("green");
+
+        let user = User::try_from(with_unknown_member).unwrap();
+
+        assert_eq!(user.extensions.len(), 1);
+        let acme = user.extension("urn:acme:params:scim:schemas:extension:2.0:User").unwrap();
+        assert_eq!(acme["clearances"][1], "lab");
+        assert!(user.extension("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User").is_none());
+        assert_eq!(Value::from(&user), json_data);
+    }
+
+    #[test]
+    fn ensure_meta_fills_resource_type_and_location() {
+        let mut user = User {
+            id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+            user_name: "bjensen@example.com".to_string(),
+            ..Default::default()
+        };
+
+        user.ensure_meta("https://example.com/v2/");
+
+        let meta = user.meta.as_ref().unwrap();
+        assert_eq!(meta.resource_type.as_deref(), Some("User"));
+        assert_eq!(meta.location.as_deref(), Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"));
+        assert!(meta.created.is_none());
+    }
+
+    #[test]
+    fn ensure_meta_keeps_existing_resource_type() {
+        let mut user = User {
+            id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+            meta: Some(Meta { resource_type: Some("User".to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+
+        user.ensure_meta("https://example.com/v2");
+
+        assert!(user.meta.unwrap().location.is_none());
+    }
+
+    #[test]
+    fn validate_strict_accepts_language_tags() {
+        for tag in ["en-US", "fr"] {
+            let user = User {
+                user_name: "bjensen".to_string(),
+                preferred_language: Some(tag.to_string()),
+                locale: Some(tag.to_string()),
+                ..Default::default()
+            };
+            assert!(user.validate_strict().is_ok(), "{} should be accepted", tag);
+        }
+    }
+
+    #[test]
+    fn validate_strict_rejects_malformed_language_tags() {
+        for tag in ["EN_us", "english"] {
+            let preferred_language = User {
+                user_name: "bjensen".to_string(),
+                preferred_language: Some(tag.to_string()),
+                ..Default::default()
+            };
+            match preferred_language.validate_strict() {
+                Err(SCIMError::InvalidFieldValue(message)) => assert_eq!(message, format!("preferredLanguage: {}", tag)),
+                other => panic!("expected InvalidFieldValue for {}, got {:?}", tag, other),
+            }
+
+            let locale = User { user_name: "bjensen".to_string(), locale: Some(tag.to_string()), ..Default::default() };
+            assert!(matches!(locale.validate_strict(), Err(SCIMError::InvalidFieldValue(_))));
+        }
+    }
+
+    #[cfg(feature = "tz-validate")]
+    #[test]
+    fn validate_timezone_checks_iana_zone_names() {
+        let user = |timezone: &str| User {
+            user_name: "bjensen".to_string(),
+            timezone: Some(timezone.to_string()),
+            ..Default::default()
+        };
+
+        assert!(user("America/Los_Angeles").validate_timezone().is_ok());
+        match user("Mars/Phobos").validate_timezone() {
+            Err(SCIMError::InvalidFieldValue(message)) => assert_eq!(message, "timezone: Mars/Phobos"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn is_active_treats_absent_as_active() {
+        let user = |active: Option<bool>| User { user_name: "bjensen".to_string(), active, ..Default::default() };
+
+        assert!(user(Some(true)).is_active());
+        assert!(!user(Some(false)).is_active());
+        assert!(user(None).is_active());
+    }
+
+    #[test]
+    fn deactivate_sets_active_to_false() {
+        let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+
+        user.deactivate();
+
+        assert_eq!(user.active, Some(false));
+        assert!(!user.is_active());
+    }
+
+    fn existing_users() -> Vec<User> {
+        ["bjensen", "jsmith"]
+            .iter()
+            .enumerate()
+            .map(|(i, user_name)| User {
+                id: Some(format!("id-{}", i)),
+                user_name: user_name.to_string(),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validate_unique_username_accepts_fresh_name() {
+        let candidate = User { user_name: "mpepperidge".to_string(), ..Default::default() };
+
+        assert!(candidate.validate_unique_username(&existing_users()).is_ok());
+    }
+
+    #[test]
+    fn validate_unique_username_rejects_case_variant() {
+        let candidate = User { user_name: "BJensen".to_string(), ..Default::default() };
+
+        match candidate.validate_unique_username(&existing_users()) {
+            Err(SCIMError::ConflictError(message)) => assert_eq!(message, "userName: BJensen is already in use"),
+            other => panic!("expected ConflictError, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_unique_username_ignores_self_on_update() {
+        let mut candidate = existing_users().remove(0);
+        candidate.user_name = "BJENSEN".to_string();
+
+        assert!(candidate.validate_unique_username(&existing_users()).is_ok());
+    }
+
+    #[test]
+    fn user_reads_from_and_writes_to_io() {
+        let json = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen", "active": true}"#;
+
+        let user = User::from_reader(std::io::Cursor::new(json)).unwrap();
+        assert_eq!(user.user_name, "bjensen");
+
+        let mut body = std::io::Cursor::new(Vec::new());
+        user.to_writer(&mut body).unwrap();
+        let written: Value = serde_json::from_slice(body.get_ref()).unwrap();
+        assert_eq!(written, serde_json::from_str::<Value>(json).unwrap());
+
+        assert!(matches!(User::from_reader(std::io::Cursor::new("{")), Err(SCIMError::DeserializationError(_))));
+    }
+
+    #[test]
+    fn from_reader_error_names_failing_attribute() {
+        let json = r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen", "emails": "bjensen@example.com"}"#;
+
+        match User::from_reader(std::io::Cursor::new(json)) {
+            Err(SCIMError::DeserializationError(err)) => {
+                let message = err.to_string();
+                assert!(message.starts_with("emails: invalid type: string"), "{}", message);
+                assert!(message.contains("line 1"), "{}", message);
+            }
+            other => panic!("expected DeserializationError, got {:?}", other),
+        }
+    }
+
+    fn reporting_to(id: &str, manager: &str) -> User {
+        User {
+            id: Some(id.to_string()),
+            user_name: id.to_string(),
+            enterprise_user: Some(EnterpriseUser {
+                manager: Some(Manager { value: Some(manager.to_string()), ref_: None, display_name: None }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn resolve_manager_chain_follows_two_levels() {
+        let user = reporting_to("bjensen", "jsmith");
+        assert_eq!(user.enterprise_user.as_ref().unwrap().manager_id(), Some("jsmith"));
+
+        let chain = user.resolve_manager_chain(|id| match id {
+            "jsmith" => Some(reporting_to("jsmith", "mpepperidge")),
+            "mpepperidge" => Some(User { id: Some("mpepperidge".to_string()), ..Default::default() }),
+            _ => None,
+        });
+
+        assert_eq!(chain, vec!["jsmith", "mpepperidge"]);
+    }
+
+    #[test]
+    fn resolve_manager_chain_stops_on_self_reference() {
+        let user = reporting_to("bjensen", "bjensen");
+        assert!(user.resolve_manager_chain(|id| Some(reporting_to(id, id))).is_empty());
+
+        let user = reporting_to("bjensen", "jsmith");
+        let chain = user.resolve_manager_chain(|id| Some(reporting_to(id, id)));
+        assert_eq!(chain, vec!["jsmith"]);
+    }
+
+    #[test]
+    fn deserialize_strict_rejects_unknown_attribute() {
+        let json = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:acme:params:scim:schemas:extension:2.0:User"],
+            "userName": "bjensen",
+            "displayName": null,
+            "emails": [],
+            "favoriteColor": "green",
+            "urn:acme:params:scim:schemas:extension:2.0:User": {"badgeNumber": "42"}
+        }"#;
+
+        match User::deserialize_strict(json) {
+            Err(SCIMError::InvalidValue(message)) => assert_eq!(message, "unknown attributes: favoriteColor"),
+            other => panic!("expected InvalidValue, got {:?}", other),
+        }
+
+        let known_only = json.replace("\"favoriteColor\": \"green\",", "");
+        let user = User::deserialize_strict(&known_only).unwrap();
+        assert!(user.extension("urn:acme:params:scim:schemas:extension:2.0:User").is_some());
+    }
+
+    #[test]
+    fn dedup_multivalued_collapses_identical_entries() {
+        let email = |value: &str, type_: &str| Email {
+            value: Some(value.to_string()),
+            type_: Some(type_.to_string()),
+            ..Default::default()
+        };
+        let mut user = User {
+            user_name: "bjensen".to_string(),
+            emails: Some(vec![
+                email("bjensen@example.com", "work"),
+                email("babs@jensen.org", "home"),
+                email("bjensen@example.com", "work"),
+                email("bjensen@example.com", "other"),
+            ]),
+            ..Default::default()
+        };
+
+        user.dedup_multivalued();
+
+        let emails: Vec<(&str, &str)> = user
+            .emails
+            .iter()
+            .flatten()
+            .map(|email| (email.value.as_deref().unwrap(), email.type_.as_deref().unwrap()))
+            .collect();
+        assert_eq!(emails, vec![("bjensen@example.com", "work"), ("babs@jensen.org", "home"), ("bjensen@example.com", "other")]);
+        assert!(user.phone_numbers.is_none());
+    }
+
+    #[test]
+    fn key_and_hash_index_users_by_id() {
+        let user = |id: &str, email: &str| User {
+            id: Some(id.to_string()),
+            user_name: id.to_string(),
+            emails: Some(vec![Email { value: Some(email.to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+        let users = vec![user("2819c223", "bjensen@example.com"), user("c75ad752", "jsmith@example.com")];
+
+        let ids: HashSet<&str> = users.iter().filter_map(User::key).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains("2819c223"));
+        assert_eq!(User::default().key(), None);
+
+        let emails: HashSet<&Email> = users.iter().chain(&users).flat_map(|user| user.emails.iter().flatten()).collect();
+        assert_eq!(emails.len(), 2);
+    }
+
+    #[test]
+    fn apply_put_preserves_server_managed_attributes() {
+        let current = User::try_from(r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen",
+            "password": "t1meMa$heen",
+            "title": "Tour Guide",
             "meta": {
                 "resourceType": "User",
                 "created": "2010-01-23T04:56:22Z",
                 "lastModified": "2011-05-13T04:42:34Z",
-                "version": "W/\"a330bc54f0671c9\"",
+                "version": "W/\"3694e05e9dff590\"",
                 "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
             }
+        }"#)
+        .unwrap();
+        let replacement = User::try_from(r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "id": "spoofed",
+            "userName": "bjensen",
+            "displayName": "Babs Jensen",
+            "meta": {"created": "2030-01-01T00:00:00Z"}
+        }"#)
+        .unwrap();
+
+        let updated = current.apply_put(replacement);
+
+        assert_eq!(updated.id.as_deref(), Some("2819c223-7f76-453a-919d-413861904646"));
+        assert_eq!(updated.display_name.as_deref(), Some("Babs Jensen"));
+        assert_eq!(updated.title, None);
+        assert_eq!(updated.password.as_deref(), Some("t1meMa$heen"));
+        let (meta, current_meta) = (updated.meta.unwrap(), current.meta.unwrap());
+        assert_eq!(meta.created, current_meta.created);
+        assert_eq!(meta.resource_type.as_deref(), Some("User"));
+        assert_eq!(meta.location, current_meta.location);
+        assert_eq!(meta.version, None);
+        assert!(meta.last_modified.is_some());
+        assert_ne!(meta.last_modified, current_meta.last_modified);
+    }
+
+    #[test]
+    fn apply_put_replaces_password_when_given() {
+        let current = User { id: Some("2819c223".to_string()), password: Some("old".to_string()), ..Default::default() };
+        let replacement = User { password: Some("new".to_string()), ..Default::default() };
+
+        assert_eq!(current.apply_put(replacement).password.as_deref(), Some("new"));
+    }
+
+    #[test]
+    fn to_canonical_json_is_stable_with_extensions() {
+        let json = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:example:params:scim:schemas:extension:b", "urn:example:params:scim:schemas:extension:a"],
+            "userName": "bjensen",
+            "urn:example:params:scim:schemas:extension:b": {"zeta": 1, "alpha": {"y": true, "x": false}},
+            "urn:example:params:scim:schemas:extension:a": {"badge": "1234"}
         }"#;
+        let user = User::deserialize(json).unwrap();
+        let reordered = User::deserialize(json).unwrap();
+
+        let canonical = user.to_canonical_json().unwrap();
+        assert_eq!(canonical, user.to_canonical_json().unwrap());
+        assert_eq!(canonical, reordered.to_canonical_json().unwrap());
+        assert!(canonical.find("extension:a").unwrap() < canonical.find("extension:b\":").unwrap());
+        assert!(canonical.contains(r#"{"alpha":{"x":false,"y":true},"zeta":1}"#));
+    }
+
+    #[test]
+    fn validate_strict_checks_two_letter_country_codes() {
+        let user_in = |country: &str| User {
+            user_name: "bjensen".to_string(),
+            addresses: Some(vec![Address { country: Some(country.to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+
+        assert!(user_in("US").validate_strict().is_ok());
+        assert!(user_in("United States").validate_strict().is_ok());
+        match user_in("XX").validate_strict() {
+            Err(SCIMError::InvalidFieldValue(message)) => assert_eq!(message, "addresses.country: XX"),
+            other => panic!("expected InvalidFieldValue, got {:?}", other),
+        }
+        assert!(user_in("us").validate_strict().is_err());
+    }
+
+    #[test]
+    fn set_enterprise_adds_schema_urn_once() {
+        let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+
+        user.set_enterprise(EnterpriseUser { department: Some("Tour Operations".to_string()), ..Default::default() });
+        user.set_enterprise(EnterpriseUser { department: Some("Theme Park".to_string()), ..Default::default() });
+
+        assert_eq!(user.schemas, vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string()]);
+        assert_eq!(user.enterprise_user.as_ref().unwrap().department.as_deref(), Some("Theme Park"));
+        assert!(user.validate_schema_consistency().is_ok());
+    }
+
+    #[test]
+    fn take_enterprise_removes_schema_urn() {
+        let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+        user.set_enterprise(EnterpriseUser { department: Some("Tour Operations".to_string()), ..Default::default() });
+
+        let enterprise_user = user.take_enterprise().unwrap();
+
+        assert_eq!(enterprise_user.department.as_deref(), Some("Tour Operations"));
+        assert_eq!(user.schemas, vec![schema_uri::USER.to_string()]);
+        assert!(user.enterprise_user.is_none());
+        assert!(user.validate_schema_consistency().is_ok());
+        assert_eq!(user.take_enterprise(), None);
+    }
+
+    #[test]
+    fn display_is_concise_and_omits_password() {
+        let mut user = User {
+            id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+            user_name: "bjensen@example.com".to_string(),
+            password: Some("t1meMa$heen".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(user.to_string(), "User(id=2819c223-7f76-453a-919d-413861904646, userName=bjensen@example.com)");
+        assert!(!user.to_string().contains("t1meMa$heen"));
+        user.id = None;
+        assert_eq!(user.to_string(), "User(userName=bjensen@example.com)");
+    }
+
+    #[test]
+    fn debug_redacts_password() {
+        let user = User {
+            user_name: "bjensen@example.com".to_string(),
+            password: Some("t1meMa$heen".to_string()),
+            ..Default::default()
+        };
+
+        let debug = format!("{:?}", user);
+        assert!(!debug.contains("t1meMa$heen"));
+        assert!(debug.contains(r#"password: Some("***REDACTED***")"#));
+        assert!(debug.contains(r#"user_name: "bjensen@example.com""#));
+        assert!(format!("{:#?}", user).contains("***REDACTED***"));
+        assert!(format!("{:?}", User::default()).contains("password: None"));
+
+        let parsed = User::deserialize(r#"{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen", "password": "t1meMa$heen"}"#).unwrap();
+        assert_eq!(parsed.password.as_deref(), Some("t1meMa$heen"));
+    }
+
+    #[test]
+    fn from_core_and_extensions_merges_enterprise_extension() {
+        let core = serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "bjensen",
+            "displayName": "Babs Jensen"
+        });
+        let extensions = HashMap::from([(
+            schema_uri::ENTERPRISE_USER.to_string(),
+            serde_json::json!({"employeeNumber": "701984", "manager": {"value": "26118915-6090-4610-87e4-49d8ca9f808d"}}),
+        )]);
+
+        let user = User::from_core_and_extensions(core, extensions).unwrap();
+
+        assert_eq!(user.display_name.as_deref(), Some("Babs Jensen"));
+        let enterprise_user = user.enterprise_user.as_ref().unwrap();
+        assert_eq!(enterprise_user.employee_number.as_deref(), Some("701984"));
+        assert_eq!(enterprise_user.manager_id(), Some("26118915-6090-4610-87e4-49d8ca9f808d"));
+        assert_eq!(user.schemas, vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string()]);
+        assert!(user.validate_schema_consistency().is_ok());
+    }
+
+    #[test]
+    fn from_core_and_extensions_rejects_malformed_core() {
+        let result = User::from_core_and_extensions(serde_json::json!(["bjensen"]), HashMap::new());
+        assert!(matches!(result, Err(SCIMError::DeserializationError(_))));
+
+        let result = User::from_core_and_extensions(serde_json::json!({"userName": 42}), HashMap::new());
+        assert!(matches!(result, Err(SCIMError::DeserializationError(_))));
+    }
+
+    #[test]
+    fn verify_version_detects_stale_versions() {
+        let mut user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() };
+        assert!(user.verify_version().is_ok());
+
+        user.refresh_version().unwrap();
+        assert!(user.verify_version().is_ok());
+
+        user.active = Some(false);
+        match user.verify_version() {
+            Err(SCIMError::InvalidValue(message)) => assert_eq!(message, "version mismatch"),
+            other => panic!("expected InvalidValue, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn emails_by_type_groups_by_lowercased_type() {
+        let email = |value: &str, type_: Option<&str>| Email {
+            value: Some(value.to_string()),
+            type_: type_.map(str::to_string),
+            ..Default::default()
+        };
+        let user = User {
+            user_name: "bjensen".to_string(),
+            emails: Some(vec![
+                email("bjensen@example.com", Some("work")),
+                email("babs@jensen.org", Some("home")),
+                email("b.jensen@example.com", Some("Work")),
+                email("bjensen@mail.example", None),
+            ]),
+            ..Default::default()
+        };
+
+        let emails = user.emails_by_type();
+
+        let mut keys: Vec<&str> = emails.keys().map(String::as_str).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, vec!["(none)", "home", "work"]);
+        let work: Vec<Option<&str>> = emails["work"].iter().map(|email| email.value.as_deref()).collect();
+        assert_eq!(work, vec![Some("bjensen@example.com"), Some("b.jensen@example.com")]);
+        assert_eq!(emails["home"].len(), 1);
+        assert_eq!(emails["(none)"].len(), 1);
+        assert!(User::default().emails_by_type().is_empty());
+    }
+
+    #[test]
+    fn validate_unique_username_ignores_case() {
+        let existing = vec![User { user_name: "BJensen@Example.com".to_string(), ..Default::default() }];
+        let candidate = User { user_name: "bjensen@EXAMPLE.com".to_string(), ..Default::default() };
+
+        assert_eq!(normalize_username("BJensen@Example.com"), "bjensen@example.com");
+        assert!(matches!(candidate.validate_unique_username(&existing), Err(SCIMError::ConflictError(_))));
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalize_username_unifies_composed_and_decomposed_forms() {
+        let composed = "Ren\u{e9}e";
+        let decomposed = "rene\u{301}e";
+        assert_ne!(composed.to_lowercase(), decomposed.to_lowercase());
+        assert_eq!(normalize_username(composed), normalize_username(decomposed));
+
+        let existing = vec![User { user_name: composed.to_string(), ..Default::default() }];
+        let candidate = User { user_name: decomposed.to_string(), ..Default::default() };
+        assert!(matches!(candidate.validate_unique_username(&existing), Err(SCIMError::ConflictError(_))));
+    }
+
+    #[test]
+    fn references_lists_groups_and_manager() {
+        let user = User::deserialize(r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            "userName": "bjensen",
+            "groups": [
+                {"value": "e9e30dba-f08f-4109-8486-d5c6a331660a", "$ref": "../Groups/e9e30dba-f08f-4109-8486-d5c6a331660a", "display": "Tour Guides"},
+                {"value": "fc348aa8-3835-40eb-a20b-c726e15c55b5"},
+                {"display": "No Id"}
+            ],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {
+                "manager": {"value": "26118915-6090-4610-87e4-49d8ca9f808d", "$ref": "../Users/26118915-6090-4610-87e4-49d8ca9f808d"}
+            }
+        }"#).unwrap();
+
+        assert_eq!(user.references(), vec![
+            Reference {
+                kind: RefKind::Group,
+                value: "e9e30dba-f08f-4109-8486-d5c6a331660a".to_string(),
+                ref_url: Some("../Groups/e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+            },
+            Reference { kind: RefKind::Group, value: "fc348aa8-3835-40eb-a20b-c726e15c55b5".to_string(), ref_url: None },
+            Reference {
+                kind: RefKind::Manager,
+                value: "26118915-6090-4610-87e4-49d8ca9f808d".to_string(),
+                ref_url: Some("../Users/26118915-6090-4610-87e4-49d8ca9f808d".to_string()),
+            },
+        ]);
+        assert!(User::default().references().is_empty());
+    }
+
+    #[test]
+    fn validate_with_runs_only_enabled_checks() {
+        let user = User {
+            user_name: "bjensen".to_string(),
+            emails: Some(vec![
+                Email { value: Some("not-an-email".to_string()), primary: Some(true), ..Default::default() },
+                Email { value: Some("bjensen@example.com".to_string()), primary: Some(true), ..Default::default() },
+            ]),
+            phone_numbers: Some(vec![PhoneNumber { value: Some("call me".to_string()), ..Default::default() }]),
+            addresses: Some(vec![Address { country: Some("XX".to_string()), ..Default::default() }]),
+            locale: Some("en_US".to_string()),
+            ..Default::default()
+        };
+        let none = ValidationPolicy { require_schemas: false, ..ValidationPolicy::lenient() };
+        let only = |policy: ValidationPolicy| user.validate_with(&policy).unwrap_err().to_string();
+
+        assert!(user.validate_with(&ValidationPolicy::lenient()).is_ok());
+        assert!(only(ValidationPolicy { check_primary_uniqueness: true, ..none }).contains("emails"));
+        assert!(only(ValidationPolicy { check_email_format: true, ..none }).contains("not-an-email"));
+        assert!(only(ValidationPolicy { check_phone_numbers: true, ..none }).contains("call me"));
+        assert!(only(ValidationPolicy { check_country_codes: true, ..none }).contains("XX"));
+        assert!(only(ValidationPolicy { check_language_tags: true, ..none }).contains("en_US"));
+        let with_bad_ref = User { groups: Some(vec![Group { ref_: Some("htt p://".to_string()), ..Default::default() }]), ..user.clone() };
+        assert!(with_bad_ref.validate_with(&ValidationPolicy { check_references: true, ..none }).unwrap_err().to_string().contains("htt p://"));
+        assert!(user.validate_with(&ValidationPolicy::strict()).is_err());
+
+        let without_schemas = User { schemas: vec![], user_name: "bjensen".to_string(), ..Default::default() };
+        assert!(matches!(without_schemas.validate_with(&ValidationPolicy::lenient()), Err(SCIMError::MissingRequiredField(_))));
+        assert!(without_schemas.validate_with(&none).is_ok());
+    }
+
+    #[test]
+    fn validate_all_reports_every_violation() {
+        let primary_email = |value: &str| Email { value: Some(value.to_string()), primary: Some(true), ..Default::default() };
+        let user = User {
+            schemas: vec![],
+            emails: Some(vec![primary_email("bjensen@example.com"), primary_email("bjensen")]),
+            phone_numbers: Some(vec![
+                PhoneNumber { value: Some("555-0100".to_string()), primary: Some(true), ..Default::default() },
+                PhoneNumber { value: Some("555-0101".to_string()), primary: Some(true), ..Default::default() },
+            ]),
+            ..Default::default()
+        };
+
+        let errors = user.validate_all().unwrap_err();
+
+        let messages: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        assert_eq!(messages.len(), 5, "{:?}", messages);
+        assert!(matches!(&errors[0], SCIMError::MissingRequiredField(field) if field == "schemas"));
+        assert!(matches!(&errors[1], SCIMError::MissingRequiredField(field) if field == "user_name"));
+        assert!(matches!(&errors[2], SCIMError::InvalidFieldValue(value) if value == "emails: multiple primary values"));
+        assert!(matches!(&errors[3], SCIMError::InvalidFieldValue(value) if value == "phoneNumbers: multiple primary values"));
+        assert!(matches!(&errors[4], SCIMError::InvalidFieldValue(value) if value == "emails: bjensen"));
+        // The single-error validators still stop at the first violation.
+        assert!(matches!(user.validate_strict(), Err(SCIMError::MissingRequiredField(field)) if field == "schemas"));
+    }
+
+    #[test]
+    fn validate_all_accepts_valid_user() {
+        let user = User { user_name: "bjensen".to_string(), ..Default::default() };
+        assert!(user.validate_all().is_ok());
+    }
+
+    #[test]
+    fn with_enterprise_serializes_extension_key() {
+        let enterprise_user = EnterpriseUser::builder()
+            .employee_number("701984")
+            .department("Tour Operations")
+            .manager("26118915-6090-4610-87e4-49d8ca9f808d", "John Smith")
+            .build()
+            .unwrap();
+        let user = User { user_name: "bjensen@example.com".to_string(), ..Default::default() }
+            .with_enterprise(enterprise_user.clone())
+            .with_enterprise(enterprise_user);
+
+        let json = serde_json::to_value(&user).unwrap();
+
+        let enterprise_schemas = user.schemas.iter().filter(|schema| *schema == schema_uri::ENTERPRISE_USER).count();
+        assert_eq!(enterprise_schemas, 1);
+        assert_eq!(json[schema_uri::ENTERPRISE_USER]["employeeNumber"], "701984");
+        assert_eq!(json[schema_uri::ENTERPRISE_USER]["manager"]["displayName"], "John Smith");
+        assert!(user.validate_schema_consistency().is_ok());
+    }
+
+    #[test]
+    fn set_groups_from_lists_only_groups_containing_the_user() {
+        let member = |value: &str| crate::models::group::Member { value: Some(value.to_string()), ..Default::default() };
+        let guides = group::Group {
+            id: "e9e30dba-f08f-4109-8486-d5c6a331660a".to_string(),
+            display_name: "Tour Guides".to_string(),
+            members: Some(vec![member("902c246b-6245-4190-8e05-00816be7344a"), member("2819c223-7f76-453a-919d-413861904646")]),
+            meta: Some(Meta {
+                location: Some("https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let trainees = group::Group {
+            id: "fc348aa8-3835-40eb-a20b-c726e15c55b5".to_string(),
+            display_name: "Trainees".to_string(),
+            members: Some(vec![member("902c246b-6245-4190-8e05-00816be7344a")]),
+            ..Default::default()
+        };
+        let mut user = User {
+            id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+            user_name: "bjensen".to_string(),
+            ..Default::default()
+        };
+
+        user.set_groups_from(&[guides, trainees.clone()]);
+
+        assert_eq!(
+            user.groups,
+            Some(vec![Group {
+                value: Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+                ref_: Some("https://example.com/v2/Groups/e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()),
+                display: Some("Tour Guides".to_string()),
+                type_: Some("direct".to_string()),
+            }])
+        );
+
+        user.set_groups_from(&[trainees]);
+        assert_eq!(user.groups, None);
+    }
+
+    #[test]
+    fn validate_strict_checks_references_are_uris() {
+        let with_refs = |group_ref: &str, manager_ref: &str| User {
+            user_name: "bjensen".to_string(),
+            groups: Some(vec![Group { value: Some("e9e30dba".to_string()), ref_: Some(group_ref.to_string()), ..Default::default() }]),
+            enterprise_user: Some(EnterpriseUser {
+                manager: Some(Manager { value: Some("26118915".to_string()), ref_: Some(manager_ref.to_string()), display_name: None }),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        assert!(with_refs("https://example.com/v2/Groups/e9e30dba", "https://example.com/v2/Users/26118915").validate_strict().is_ok());
+        assert!(with_refs("../Groups/e9e30dba", "/v2/Users/26118915%20x").validate_strict().is_ok());
+
+        for (group_ref, manager_ref, invalid) in [
+            ("htt p://", "../Users/26118915", "groups.$ref: htt p://"),
+            ("../Groups/e9e30dba", "1http://example.com", "manager.$ref: 1http://example.com"),
+            ("../Groups/%zz", "../Users/26118915", "groups.$ref: ../Groups/%zz"),
+            ("", "../Users/26118915", "groups.$ref: "),
+        ] {
+            let user = with_refs(group_ref, manager_ref);
+            assert!(matches!(user.validate_strict(), Err(SCIMError::InvalidFieldValue(message)) if message == invalid), "{}", invalid);
+            assert!(user.validate().is_ok());
+        }
+    }
+
+    #[test]
+    fn check_precondition_compares_etags_weakly() {
+        let user = User {
+            user_name: "bjensen".to_string(),
+            meta: Some(Meta { version: Some(r#"W/"e180ee84f0671b1""#.to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+
+        assert!(user.check_precondition(None, None).is_ok());
+        assert!(user.check_precondition(Some(r#"W/"e180ee84f0671b1""#), None).is_ok());
+        assert!(user.check_precondition(Some(r#""e180ee84f0671b1""#), None).is_ok());
+        assert!(user.check_precondition(Some(r#""a,b", W/"e180ee84f0671b1""#), None).is_ok());
+        assert!(user.check_precondition(None, Some(r#"W/"0000000000000000""#)).is_ok());
+
+        for (if_match, if_none_match) in [
+            (Some(r#"W/"0000000000000000""#), None),
+            (Some(r#""a,b", "c""#), None),
+            (None, Some(r#"W/"e180ee84f0671b1""#)),
+            (None, Some(r#""other", "e180ee84f0671b1""#)),
+        ] {
+            let result = user.check_precondition(if_match, if_none_match);
+            assert!(matches!(result, Err(SCIMError::PreconditionFailed(_))), "{:?} {:?}", if_match, if_none_match);
+        }
+        assert!(matches!(user.check_precondition(Some("e180ee84f0671b1"), None), Err(SCIMError::RequestError(_))));
+        assert!(matches!(user.check_precondition(Some(r#""unterminated"#), None), Err(SCIMError::RequestError(_))));
+    }
+
+    #[test]
+    fn check_precondition_handles_wildcard() {
+        let versioned = User {
+            user_name: "bjensen".to_string(),
+            meta: Some(Meta { version: Some(r#"W/"e180ee84f0671b1""#.to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+        let unversioned = User { user_name: "bjensen".to_string(), ..Default::default() };
+
+        for user in [&versioned, &unversioned] {
+            assert!(user.check_precondition(Some("*"), None).is_ok());
+            assert!(matches!(user.check_precondition(None, Some(" * ")), Err(SCIMError::PreconditionFailed(_))));
+        }
+        assert!(matches!(unversioned.check_precondition(Some(r#"W/"e180ee84f0671b1""#), None), Err(SCIMError::PreconditionFailed(_))));
+        assert!(unversioned.check_precondition(None, Some(r#"W/"e180ee84f0671b1""#)).is_ok());
+    }
+
+    #[test]
+    fn active_deserializes_from_boolean_string_and_null() {
+        let active = |json: &str| User::deserialize(&format!(r#"{{"schemas": [], "userName": "bjensen"{}}}"#, json)).map(|user| user.active);
+
+        assert_eq!(active(r#", "active": true"#).unwrap(), Some(true));
+        assert_eq!(active(r#", "active": false"#).unwrap(), Some(false));
+        assert_eq!(active(r#", "active": "true""#).unwrap(), Some(true));
+        assert_eq!(active(r#", "active": "FALSE""#).unwrap(), Some(false));
+        assert_eq!(active(r#", "active": null"#).unwrap(), None);
+        assert_eq!(active("").unwrap(), None);
+        assert!(matches!(active(r#", "active": "yes""#), Err(SCIMError::DeserializationError(_))));
+        assert!(matches!(active(r#", "active": 1"#), Err(SCIMError::DeserializationError(_))));
+    }
+
+    #[test]
+    fn multi_valued_helpers_work_across_attribute_types() {
+        fn primary_value<T: MultiValued>(values: &[T]) -> Option<&str> {
+            find_primary(values).and_then(MultiValued::value)
+        }
+
+        fn work_values<T: MultiValued>(values: &[T]) -> Vec<&str> {
+            values.iter().filter(|value| value.type_() == Some("work")).filter_map(MultiValued::value).collect()
+        }
+
+        let emails = [
+            Email { value: Some("bjensen@example.com".to_string()), type_: Some("work".to_string()), ..Default::default() },
+            Email { value: Some("babs@jensen.org".to_string()), type_: Some("home".to_string()), primary: Some(true), ..Default::default() },
+        ];
+        let phone_numbers = [
+            PhoneNumber { value: Some("555-555-5555".to_string()), type_: Some("work".to_string()), primary: Some(false), ..Default::default() },
+            PhoneNumber { value: Some("555-555-4444".to_string()), type_: Some("work".to_string()), ..Default::default() },
+        ];
+
+        assert_eq!(primary_value(&emails), Some("babs@jensen.org"));
+        assert_eq!(work_values(&emails), vec!["bjensen@example.com"]);
+        assert_eq!(primary_value(&phone_numbers), None);
+        assert_eq!(work_values(&phone_numbers), vec!["555-555-5555", "555-555-4444"]);
+        assert!(!phone_numbers[0].is_primary());
+    }
 
-        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+    #[test]
+    fn merge_overrides_set_attributes_and_keeps_the_rest() {
+        let email = |value: &str| Email { value: Some(value.to_string()), ..Default::default() };
+        let mut user = User {
+            id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+            user_name: "bjensen".to_string(),
+            display_name: Some("Barbara Jensen".to_string()),
+            emails: Some(vec![email("bjensen@example.com"), email("babs@jensen.org")]),
+            meta: Some(Meta { version: Some(r#"W/"a330bc54f0671c9""#.to_string()), ..Default::default() }),
+            ..Default::default()
+        };
+        let base = user.clone();
 
-        if let Err(e) = &user {
-            eprintln!("Deserialization failed: {:?}", e);
+        user.merge(&User {
+            schemas: vec![],
+            display_name: Some("Babs Jensen".to_string()),
+            meta: Some(Meta::default()),
+            ..Default::default()
+        });
+
+        assert_eq!(user.display_name.as_deref(), Some("Babs Jensen"));
+        assert_eq!(User { display_name: base.display_name.clone(), ..user.clone() }, base);
+
+        user.merge(&User { user_name: "babs".to_string(), emails: Some(vec![email("babs@example.org")]), ..Default::default() });
+        assert_eq!(user.user_name, "babs");
+        assert_eq!(user.emails, Some(vec![email("babs@example.org")]));
+        assert_eq!(user.id, base.id);
+        assert_eq!(user.meta, base.meta);
+    }
+
+    #[test]
+    fn merge_adds_extension_schemas() {
+        let mut user = User { user_name: "bjensen".to_string(), ..Default::default() };
+        let delta = User { user_name: String::new(), ..Default::default() }
+            .with_enterprise(EnterpriseUser { department: Some("Tour Operations".to_string()), ..Default::default() });
+
+        user.merge(&delta);
+        user.merge(&delta);
+
+        assert_eq!(user.schemas, vec![schema_uri::USER.to_string(), schema_uri::ENTERPRISE_USER.to_string()]);
+        assert!(user.validate_schema_consistency().is_ok());
+    }
+
+    #[test]
+    fn merge_combines_complex_attributes_per_sub_attribute() {
+        let mut base = User {
+            user_name: "bjensen".to_string(),
+            name: Some(Name { given_name: Some("Barbara".to_string()), family_name: Some("Jensen".to_string()), ..Default::default() }),
+            extensions: HashMap::from([("urn:example:2.0:User".to_string(), serde_json::json!({"badge": "701", "floor": 3}))]),
+            ..Default::default()
+        }
+        .with_enterprise(EnterpriseUser {
+            employee_number: Some("701984".to_string()),
+            manager: Some(Manager { value: Some("26118915".to_string()), ref_: None, display_name: Some("John Smith".to_string()) }),
+            ..Default::default()
+        });
+        let delta = User {
+            name: Some(Name { given_name: Some("Babs".to_string()), ..Default::default() }),
+            extensions: HashMap::from([("urn:example:2.0:User".to_string(), serde_json::json!({"floor": 4}))]),
+            ..Default::default()
         }
+        .with_enterprise(EnterpriseUser {
+            department: Some("Tour Operations".to_string()),
+            manager: Some(Manager { value: None, ref_: None, display_name: Some("John A. Smith".to_string()) }),
+            ..Default::default()
+        });
 
-        assert!(user.is_ok());
-        let user = user.unwrap();
-        assert_eq!(user.schemas, vec!["urn:ietf:params:scim:schemas:core:2.0:User"]);
-        assert_eq!(user.id, Some("2819c223-7f76-453a-919d-413861904646".to_string()));
-        assert_eq!(user.user_name, "bjensen@example.com");
-        assert_eq!(user.name.as_ref().unwrap().formatted, Some("Ms. Barbara J Jensen, III".to_string()));
-        assert_eq!(user.display_name, Some("Babs Jensen".to_string()));
-        assert_eq!(user.nick_name, Some("Babs".to_string()));
-        assert_eq!(user.profile_url, Some("https://login.example.com/bjensen".to_string()));
-        assert_eq!(user.emails.as_ref().unwrap().len(), 2);
-        assert_eq!(user.emails.as_ref().unwrap()[0].value, Some("bjensen@example.com".to_string()));
-        assert_eq!(user.emails.as_ref().unwrap()[0].type_, Some("work".to_string()));
-        assert_eq!(user.addresses.as_ref().unwrap().len(), 2);
-        assert_eq!(user.addresses.as_ref().unwrap()[0].type_.as_ref().unwrap(), "work");
-        assert_eq!(user.phone_numbers.as_ref().unwrap().len(), 2);
-        assert_eq!(user.phone_numbers.as_ref().unwrap()[0].value, Some("555-555-5555".to_string()));
-        assert_eq!(user.ims.as_ref().unwrap().len(), 1);
-        assert_eq!(user.ims.as_ref().unwrap()[0].value, Some("someaimhandle".to_string()));
-        assert_eq!(user.groups.as_ref().unwrap().len(), 3);
-        assert_eq!(user.groups.as_ref().unwrap()[0].value, Some("e9e30dba-f08f-4109-8486-d5c6a331660a".to_string()));
-        assert_eq!(user.x509_certificates.as_ref().unwrap().len(), 1);
-        assert_eq!(user.x509_certificates.as_ref().unwrap()[0].value, Some("MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo=".to_string()), "x509_certificates[0].value did not match expected value");
-        let meta = user.meta.unwrap();
-        assert_eq!(meta.resource_type, Some("User".to_string()));
-        assert_eq!(meta.created, Some("2010-01-23T04:56:22Z".to_string()));
-        assert_eq!(meta.last_modified, Some("2011-05-13T04:42:34Z".to_string()));
-        assert_eq!(meta.version, Some("W/\"a330bc54f0671c9\"".to_string()));
-        assert_eq!(meta.location, Some("https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646".to_string()));
+        merge_user(&mut base, &delta);
+
+        assert_eq!(
+            base.name,
+            Some(Name { given_name: Some("Babs".to_string()), family_name: Some("Jensen".to_string()), ..Default::default() })
+        );
+        let enterprise_user = base.enterprise_user.as_ref().unwrap();
+        assert_eq!(enterprise_user.employee_number.as_deref(), Some("701984"));
+        assert_eq!(enterprise_user.department.as_deref(), Some("Tour Operations"));
+        assert_eq!(
+            enterprise_user.manager,
+            Some(Manager { value: Some("26118915".to_string()), ref_: None, display_name: Some("John A. Smith".to_string()) })
+        );
+        assert_eq!(base.extensions["urn:example:2.0:User"], serde_json::json!({"badge": "701", "floor": 4}));
     }
 
+    #[cfg(feature = "schemars")]
     #[test]
-    fn user_deserialization_with_enterprise_user_extension() {
-        let json_data = r#"{
-            "schemas":
-            [
-                "urn:ietf:params:scim:schemas:core:2.0:User",
-                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"
-            ],
-            "id": "2819c223-7f76-453a-919d-413861904646",
-            "externalId": "701984",
-            "userName": "bjensen@example.com",
-            "name":
-            {
-                "formatted": "Ms. Barbara J Jensen, III",
-                "familyName": "Jensen",
-                "givenName": "Barbara",
-                "middleName": "Jane",
-                "honorificPrefix": "Ms.",
-                "honorificSuffix": "III"
-            },
-            "displayName": "Babs Jensen",
-            "nickName": "Babs",
-            "profileUrl": "https://login.example.com/bjensen",
-            "emails":
-            [
-                {
-                    "value": "bjensen@example.com",
-                    "type": "work",
-                    "primary": true
-                },
-                {
-                    "value": "babs@jensen.org",
-                    "type": "home"
-                }
-            ],
-            "addresses":
-            [
-                {
-                    "streetAddress": "100 Universal City Plaza",
-                    "locality": "Hollywood",
-                    "region": "CA",
-                    "postalCode": "91608",
-                    "country": "USA",
-                    "formatted": "100 Universal City Plaza\nHollywood, CA 91608 USA",
-                    "type": "work",
-                    "primary": true
-                },
-                {
-                    "streetAddress": "456 Hollywood Blvd",
-                    "locality": "Hollywood",
-                    "region": "CA",
-                    "postalCode": "91608",
-                    "country": "USA",
-                    "formatted": "456 Hollywood Blvd\nHollywood, CA 91608 USA",
-                    "type": "home"
-                }
-            ],
-            "phoneNumbers":
-            [
-                {
-                    "value": "555-555-5555",
-                    "type": "work"
-                },
-                {
-                    "value": "555-555-4444",
-                    "type": "mobile"
-                }
-            ],
-            "ims":
-            [
-                {
-                    "value": "someaimhandle",
-                    "type": "aim"
-                }
-            ],
-            "photos":
-            [
-                {
-                    "value": "https://photos.example.com/profilephoto/72930000000Ccne/F",
-                    "type": "photo"
-                },
-                {
-                    "value": "https://photos.example.com/profilephoto/72930000000Ccne/T",
-                    "type": "thumbnail"
-                }
-            ],
-            "userType": "Employee",
-            "title": "Tour Guide",
-            "preferredLanguage": "en-US",
-            "locale": "en-US",
-            "timezone": "America/Los_Angeles",
-            "active": true,
-            "password": "t1meMa$heen",
-            "groups":
-            [
-                {
-                    "value": "e9e30dba-f08f-4109-8486-d5c6a331660a",
-                    "$ref": "../Groups/e9e30dba-f08f-4109-8486-d5c6a331660a",
-                    "display": "Tour Guides"
-                },
-                {
-                    "value": "fc348aa8-3835-40eb-a20b-c726e15c55b5",
-                    "$ref": "../Groups/fc348aa8-3835-40eb-a20b-c726e15c55b5",
-                    "display": "Employees"
-                },
-                {
-                    "value": "71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
-                    "$ref": "../Groups/71ddacd2-a8e7-49b8-a5db-ae50d0a5bfd7",
-                    "display": "US Employees"
-                }
-            ],
-            "x509Certificates":
-            [
-                {
-                  "value": "MIIDQzCCAqygAwIBAgICEAAwDQYJKoZIhvcNAQEFBQAwTjELMAkGA1UEBhMCVVMxEzARBgNVBAgMCkNhbGlmb3JuaWExFDASBgNVBAoMC2V4YW1wbGUuY29tMRQwEgYDVQQDDAtleGFtcGxlLmNvbTAeFw0xMTEwMjIwNjI0MzFaFw0xMjEwMDQwNjI0MzFaMH8xCzAJBgNVBAYTAlVTMRMwEQYDVQQIDApDYWxpZm9ybmlhMRQwEgYDVQQKDAtleGFtcGxlLmNvbTEhMB8GA1UEAwwYTXMuIEJhcmJhcmEgSiBKZW5zZW4gSUlJMSIwIAYJKoZIhvcNAQkBFhNiamVuc2VuQGV4YW1wbGUuY29tMIIBIjANBgkqhkiG9w0BAQEFAAOCAQ8AMIIBCgKCAQEA7Kr+Dcds/JQ5GwejJFcBIP682X3xpjis56AK02bc1FLgzdLI8auoR+cC9/Vrh5t66HkQIOdA4unHh0AaZ4xL5PhVbXIPMB5vAPKpzz5iPSi8xO8SL7I7SDhcBVJhqVqr3HgllEG6UClDdHO7nkLuwXq8HcISKkbT5WFTVfFZzidPl8HZ7DhXkZIRtJwBweq4bvm3hM1Os7UQH05ZS6cVDgweKNwdLLrT51ikSQG3DYrl+ft781UQRIqxgwqCfXEuDiinPh0kkvIi5jivVu1Z9QiwlYEdRbLJ4zJQBmDrSGTMYn4lRc2HgHO4DqB/bnMVorHB0CC6AV1QoFK4GPe1LwIDAQABo3sweTAJBgNVHRMEAjAAMCwGCWCGSAGG+EIBDQQfFh1PcGVuU1NMIEdlbmVyYXRlZCBDZXJ0aWZpY2F0ZTAdBgNVHQ4EFgQU8pD0U0vsZIsaA16lL8En8bx0F/gwHwYDVR0jBBgwFoAUdGeKitcaF7gnzsNwDx708kqaVt0wDQYJKoZIhvcNAQEFBQADgYEAA81SsFnOdYJtNg5Tcq+/ByEDrBgnusx0jloUhByPMEVkoMZ3J7j1ZgI8rAbOkNngX8+pKfTiDz1RC4+dx8oU6Za+4NJXUjlL5CvV6BEYb1+QAEJwitTVvxB/A67g42/vzgAtoRUeDov1+GFiBZ+GNF/cAYKcMtGcrs2i97ZkJMo="
+    fn user_json_schema_uses_serialized_names() {
+        let schema = user_json_schema();
+        let properties = schema["properties"].as_object().unwrap();
 
-                }
-            ],
-            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User":
-            {
-                "employeeNumber": "701984",
-                "costCenter": "4130",
-                "organization": "Universal Studios",
-                "division": "Theme Park",
-                "department": "Tour Operations",
-                "manager":
-                {
-                    "value": "26118915-6090-4610-87e4-49d8ca9f808d",
-                    "$ref": "../Users/26118915-6090-4610-87e4-49d8ca9f808d",
-                    "displayName": "John Smith"
-                }
-            },
-            "meta":
-            {
-                "resourceType": "User",
-                "created": "2010-01-23T04:56:22Z",
-                "lastModified": "2011-05-13T04:42:34Z",
-                "version": "W/\"3694e05e9dff591\"",
-                "location": "https://example.com/v2/Users/2819c223-7f76-453a-919d-413861904646"
+        assert!(properties.contains_key("userName"));
+        assert!(!properties.contains_key("user_name"));
+        assert!(properties.contains_key("phoneNumbers"));
+        assert!(properties.contains_key(schema_uri::ENTERPRISE_USER));
+        assert_eq!(schema["required"], serde_json::json!(["schemas", "userName"]));
+        assert!(schema["definitions"]["Email"]["properties"].get("type").is_some());
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn string_backed_type_schemas_accept_wire_values() {
+        use crate::models::group::MemberType;
+
+        let schemas = [
+            (serde_json::to_value(schemars::schema_for!(EmailType)).unwrap(), serde_json::to_value(EmailType::Work).unwrap()),
+            (serde_json::to_value(schemars::schema_for!(PhoneType)).unwrap(), serde_json::to_value(PhoneType::Work).unwrap()),
+            (serde_json::to_value(schemars::schema_for!(ImType)).unwrap(), serde_json::to_value(ImType::from("aim".to_string())).unwrap()),
+            (serde_json::to_value(schemars::schema_for!(MemberType)).unwrap(), serde_json::to_value(MemberType::User).unwrap()),
+        ];
+        for (schema, value) in schemas {
+            assert_eq!(schema["type"], "string", "{}", schema);
+            assert!(schema.get("enum").is_none() && schema.get("oneOf").is_none(), "{}", schema);
+            assert!(value.is_string(), "{}", value);
+        }
+        assert_eq!(serde_json::to_value(EmailType::Work).unwrap(), "work");
+    }
+
+    #[test]
+    fn references_deserialize_from_plain_ref_key() {
+        let json_data = r#"{
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            "userName": "bjensen",
+            "groups": [{"value": "e9e30dba", "ref": "https://example.com/v2/Groups/e9e30dba"}],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {
+                "manager": {"value": "26118915", "ref": "../Users/26118915"}
             }
         }"#;
 
-        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+        let user = User::deserialize(json_data).unwrap();
 
-        if let Err(e) = &user {
-            eprintln!("Deserialization failed: {:?}", e);
-        }
-        assert!(user.is_ok());
-        let user = user.unwrap();
-        let enterprise_user = user.enterprise_user.unwrap();
-        std::assert_eq!(enterprise_user.employee_number, Some("701984".to_string()));
-        std::assert_eq!(enterprise_user.cost_center, Some("4130".to_string()));
-        std::assert_eq!(enterprise_user.organization, Some("Universal Studios".to_string()));
-        std::assert_eq!(enterprise_user.division, Some("Theme Park".to_string()));
-        std::assert_eq!(enterprise_user.department, Some("Tour Operations".to_string()));
-        let manager = enterprise_user.manager.unwrap();
-        std::assert_eq!(manager.value, Some("26118915-6090-4610-87e4-49d8ca9f808d".to_string()));
-        std::assert_eq!(manager.display_name, Some("John Smith".to_string()));
+        let group = &user.groups.as_ref().unwrap()[0];
+        assert_eq!(group.ref_.as_deref(), Some("https://example.com/v2/Groups/e9e30dba"));
+        let manager = user.enterprise_user.as_ref().and_then(|enterprise| enterprise.manager.as_ref()).unwrap();
+        assert_eq!(manager.ref_.as_deref(), Some("../Users/26118915"));
+
+        let serialized: Value = serde_json::from_str(&user.serialize().unwrap()).unwrap();
+        assert_eq!(serialized["groups"][0], serde_json::json!({"value": "e9e30dba", "$ref": "https://example.com/v2/Groups/e9e30dba"}));
+        assert_eq!(serialized["urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"]["manager"]["$ref"], "../Users/26118915");
     }
 
     #[test]
-    fn user_deserialization_without_enterprise_user_extension() {
+    fn deserialize_error_names_failing_attribute() {
         let json_data = r#"{
             "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
-            "id": "2819c223-7f76-453a-919d-413861904646",
-            "userName": "bjensen@example.com"
+            "userName": "bjensen",
+            "emails": "bjensen@example.com"
         }"#;
 
-        let user: Result<User, serde_json::Error> = serde_json::from_str(json_data);
+        match User::deserialize(json_data) {
+            Err(SCIMError::DeserializationError(err)) => {
+                let message = err.to_string();
+                assert!(message.starts_with("emails: invalid type: string"), "{}", message);
+                assert!(message.contains("line 4"), "{}", message);
+            }
+            other => panic!("expected DeserializationError, got {:?}", other),
+        }
 
-        if let Err(e) = &user {
-            eprintln!("Deserialization failed: {:?}", e);
+        let nested = serde_json::json!({"userName": "bjensen", "phoneNumbers": [{"value": "555-555-5555"}, {"value": 5555}]});
+        match User::try_from(nested) {
+            Err(SCIMError::DeserializationError(err)) => assert!(err.to_string().starts_with("phoneNumbers[1].value: "), "{}", err),
+            other => panic!("expected DeserializationError, got {:?}", other),
+        }
+        assert!(matches!(User::deserialize("{"), Err(SCIMError::DeserializationError(_))));
+    }
+}
+
+#[cfg(test)]
+mod round_trip_properties {
+    use pretty_assertions::assert_eq;
+    use proptest::collection::{hash_map, vec};
+    use proptest::option;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::models::enterprise_user::Manager;
+
+    fn text() -> impl Strategy<Value = Option<String>> {
+        option::of(any::<String>())
+    }
+
+    // Multi-valued attributes are either absent or non-empty: an empty array is omitted when serializing, so it
+    // cannot round-trip (and is not something a valid User carries).
+    fn multi_valued<T: fmt::Debug>(element: impl Strategy<Value = T>) -> impl Strategy<Value = Option<Vec<T>>> {
+        option::of(vec(element, 1..4))
+    }
+
+    // Builds an element from the `value`, `display`, `type` and `primary` sub-attributes most multi-valued
+    // attributes share.
+    type TypedValue<T> = fn(Option<String>, Option<String>, Option<String>, Option<bool>) -> T;
+
+    fn typed_value<T: fmt::Debug>(make: TypedValue<T>) -> impl Strategy<Value = Option<Vec<T>>> {
+        multi_valued((text(), text(), text(), option::of(any::<bool>())).prop_map(move |(value, display, type_, primary)| {
+            make(value, display, type_, primary)
+        }))
+    }
+
+    fn name() -> impl Strategy<Value = Name> {
+        (text(), text(), text(), text(), text(), text()).prop_map(
+            |(formatted, family_name, given_name, middle_name, honorific_prefix, honorific_suffix)| Name {
+                formatted,
+                family_name,
+                given_name,
+                middle_name,
+                honorific_prefix,
+                honorific_suffix,
+            },
+        )
+    }
+
+    fn address() -> impl Strategy<Value = Address> {
+        (text(), text(), text(), text(), text(), text(), text(), option::of(any::<bool>())).prop_map(
+            |(formatted, street_address, locality, region, postal_code, country, type_, primary)| Address {
+                formatted,
+                street_address,
+                locality,
+                region,
+                postal_code,
+                country,
+                type_,
+                primary,
+            },
+        )
+    }
+
+    fn group() -> impl Strategy<Value = Group> {
+        (text(), text(), text(), text()).prop_map(|(value, ref_, display, type_)| Group { value, ref_, display, type_ })
+    }
+
+    fn enterprise_user() -> impl Strategy<Value = EnterpriseUser> {
+        let manager = (text(), text(), text()).prop_map(|(value, ref_, display_name)| Manager { value, ref_, display_name });
+        (text(), text(), text(), text(), text(), option::of(manager)).prop_map(
+            |(employee_number, cost_center, organization, division, department, manager)| EnterpriseUser {
+                employee_number,
+                cost_center,
+                organization,
+                division,
+                department,
+                manager,
+            },
+        )
+    }
+
+    // Timestamps are left out so the strategy does not depend on the `chrono` feature.
+    fn meta() -> impl Strategy<Value = Meta> {
+        (text(), text(), text()).prop_map(|(resource_type, version, location)| Meta {
+            resource_type,
+            version,
+            location,
+            ..Default::default()
+        })
+    }
+
+    fn extensions() -> impl Strategy<Value = HashMap<String, Value>> {
+        let urn = "urn:example:params:scim:schemas:extension:[a-z]{1,8}:2\\.0:User";
+        let attributes = hash_map(any::<String>(), any::<String>().prop_map(Value::String), 0..3);
+        hash_map(urn, attributes.prop_map(|fields| Value::Object(fields.into_iter().collect())), 0..3)
+    }
+
+    fn user() -> impl Strategy<Value = User> {
+        let profile = (
+            vec(any::<String>(), 1..3),
+            text(),
+            any::<String>(),
+            option::of(name()),
+            text(),
+            text(),
+            text(),
+            text(),
+            text(),
+            text(),
+            text(),
+            text(),
+        );
+        let attributes = (
+            option::of(any::<bool>()),
+            text(),
+            typed_value(|value, display, type_, primary| Email { value, display, type_, primary }),
+            multi_valued(address()),
+            typed_value(|value, display, type_, primary| PhoneNumber { value, display, type_, primary }),
+            typed_value(|value, display, type_, primary| Im { value, display, type_, primary }),
+            typed_value(|value, display, type_, primary| Photo { value, display, type_, primary }),
+            multi_valued(group()),
+            typed_value(|value, display, type_, primary| Entitlement { value, display, type_, primary }),
+            typed_value(|value, display, type_, primary| Role { value, display, type_, primary }),
+            typed_value(|value, display, type_, primary| X509Certificate { value, display, type_, primary }),
+        );
+        let resource = (option::of(meta()), option::of(enterprise_user()), extensions());
+
+        (profile, attributes, resource).prop_map(
+            |(
+                (schemas, id, user_name, name, display_name, nick_name, profile_url, title, user_type, preferred_language, locale, timezone),
+                (active, password, emails, addresses, phone_numbers, ims, photos, groups, entitlements, roles, x509_certificates),
+                (meta, enterprise_user, extensions),
+            )| User {
+                schemas,
+                id,
+                user_name,
+                name,
+                display_name,
+                nick_name,
+                profile_url,
+                title,
+                user_type,
+                preferred_language,
+                locale,
+                timezone,
+                active,
+                password,
+                emails,
+                addresses,
+                phone_numbers,
+                ims,
+                photos,
+                groups,
+                entitlements,
+                roles,
+                x509_certificates,
+                meta,
+                enterprise_user,
+                extensions,
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn user_survives_json_round_trip(user in user()) {
+            let json = serde_json::to_string(&user).unwrap();
+            let round_tripped: User = serde_json::from_str(&json).unwrap();
+            assert_eq!(round_tripped, user);
+        }
+
+        #[test]
+        fn user_survives_serialize_deserialize_methods(user in user()) {
+            let round_tripped = User::deserialize(&user.serialize().unwrap()).unwrap();
+            assert_eq!(round_tripped, user);
         }
-        assert!(user.is_ok());
-        let user = user.unwrap();
-        assert!(user.enterprise_user.is_none());
     }
-}
\ No newline at end of file
+}
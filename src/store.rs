@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde_json::Value;
+
+use crate::models::others::{ListResponse, PatchOp};
+use crate::models::resource::ScimResource;
+use crate::models::scim_schema::{now, Meta};
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+use crate::utils::patch::apply_patch;
+use crate::utils::query::{apply_query, ScimQuery};
+
+/// A thread-safe, in-memory map of resources keyed by id, for prototyping SCIM servers and for tests.
+///
+/// The store owns the server-managed attributes: `create` assigns the id and `meta.created`, and every write
+/// updates `meta.lastModified`. Resources are cloned in and out, so callers never hold a lock.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::store::ResourceStore;
+///
+/// let store = ResourceStore::new();
+/// let created = store.create(User { user_name: "bjensen".to_string(), ..Default::default() }).unwrap();
+///
+/// let id = created.id.as_deref().unwrap();
+/// assert_eq!(store.get(id).unwrap().user_name, "bjensen");
+/// ```
+#[derive(Debug)]
+pub struct ResourceStore<T> {
+    resources: RwLock<HashMap<String, T>>,
+}
+
+impl<T> Default for ResourceStore<T> {
+    fn default() -> Self {
+        ResourceStore {
+            resources: RwLock::new(HashMap::new()),
+        }
+    }
+}
+
+fn not_found(id: &str) -> SCIMError {
+    SCIMError::NotFoundError(format!("resource {} not found", id))
+}
+
+impl<T: ScimResource + Clone> ResourceStore<T> {
+    /// Creates an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, HashMap<String, T>>, SCIMError> {
+        self.resources.read().map_err(|_| SCIMError::OtherError("resource store lock poisoned".to_string()))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, HashMap<String, T>>, SCIMError> {
+        self.resources.write().map_err(|_| SCIMError::OtherError("resource store lock poisoned".to_string()))
+    }
+
+    /// Stores a new resource under a freshly generated UUID, and returns it as stored.
+    ///
+    /// Any id the resource already has is replaced. `meta` is reset to `resourceType`, `created` and
+    /// `lastModified`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the store's lock is poisoned.
+    pub fn create(&self, mut resource: T) -> Result<T, SCIMError> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let timestamp = now();
+        resource.set_id(id.clone());
+        resource.set_meta(Meta {
+            resource_type: Some(resource.resource_type().to_string()),
+            created: Some(timestamp.to_owned()),
+            last_modified: Some(timestamp),
+            ..Default::default()
+        });
+        self.write()?.insert(id, resource.clone());
+        Ok(resource)
+    }
+
+    /// Returns the resource with the given id.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::NotFoundError` - If no resource has the id.
+    /// * `SCIMError::OtherError` - If the store's lock is poisoned.
+    pub fn get(&self, id: &str) -> Result<T, SCIMError> {
+        self.read()?.get(id).cloned().ok_or_else(|| not_found(id))
+    }
+
+    /// Replaces the resource with the given id (PUT), and returns it as stored.
+    ///
+    /// The replacement keeps the stored id and `meta`, with `meta.lastModified` set to now.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::NotFoundError` - If no resource has the id.
+    /// * `SCIMError::OtherError` - If the store's lock is poisoned.
+    pub fn replace(&self, id: &str, mut resource: T) -> Result<T, SCIMError> {
+        let mut resources = self.write()?;
+        let stored = resources.get_mut(id).ok_or_else(|| not_found(id))?;
+        resource.set_id(id.to_string());
+        let mut meta = stored.meta().cloned().unwrap_or_default();
+        meta.last_modified = Some(now());
+        resource.set_meta(meta);
+        *stored = resource.clone();
+        Ok(resource)
+    }
+
+    /// Removes the resource with the given id.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::NotFoundError` - If no resource has the id.
+    /// * `SCIMError::OtherError` - If the store's lock is poisoned.
+    pub fn delete(&self, id: &str) -> Result<(), SCIMError> {
+        self.write()?.remove(id).map(|_| ()).ok_or_else(|| not_found(id))
+    }
+
+    /// Returns the number of stored resources.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the store's lock is poisoned.
+    pub fn len(&self) -> Result<usize, SCIMError> {
+        Ok(self.read()?.len())
+    }
+
+    /// Returns `true` if the store holds no resources.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::OtherError` if the store's lock is poisoned.
+    pub fn is_empty(&self) -> Result<bool, SCIMError> {
+        Ok(self.read()?.is_empty())
+    }
+}
+
+// PATCH and list requests are answered by `apply_patch` and `apply_query`, which only support users.
+impl ResourceStore<User> {
+    /// Applies a PATCH request to the user with the given id, and returns the user as stored.
+    ///
+    /// The patch is applied atomically: if any operation fails, the stored user is left unchanged. On success
+    /// `meta.lastModified` is set to now.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::NotFoundError` - If no user has the id.
+    /// * Any error of `apply_patch`, e.g. `SCIMError::InvalidValue` for a write to a `readOnly` attribute.
+    /// * `SCIMError::OtherError` - If the store's lock is poisoned.
+    pub fn patch(&self, id: &str, patch: &PatchOp) -> Result<User, SCIMError> {
+        let mut resources = self.write()?;
+        let stored = resources.get_mut(id).ok_or_else(|| not_found(id))?;
+        let mut user = stored.clone();
+        apply_patch(&mut user, patch)?;
+        user.meta.get_or_insert_with(Meta::default).last_modified = Some(now());
+        *stored = user.clone();
+        Ok(user)
+    }
+
+    /// Answers a list request over the stored users with `apply_query`.
+    ///
+    /// Users are considered in the order of their ids, so that pagination is stable without `sortBy`.
+    ///
+    /// # Errors
+    ///
+    /// * Any error of `apply_query`, e.g. `SCIMError::InvalidPath` for an unknown `sortBy`.
+    /// * `SCIMError::OtherError` - If the store's lock is poisoned.
+    pub fn list(&self, query: &ScimQuery) -> Result<ListResponse<Value>, SCIMError> {
+        let mut users: Vec<User> = self.read()?.values().cloned().collect();
+        users.sort_by(|left, right| left.id.cmp(&right.id));
+        apply_query(users, query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::group::Group;
+
+    fn patch_from(json: &str) -> PatchOp {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn user_store_create_get_patch_delete() {
+        let store = ResourceStore::new();
+        let created = store
+            .create(User { id: Some("client-chosen".to_string()), user_name: "bjensen".to_string(), ..Default::default() })
+            .unwrap();
+        let id = created.id.clone().unwrap();
+        assert_ne!(id, "client-chosen");
+        assert_eq!(id.len(), 36);
+        let meta = created.meta.as_ref().unwrap();
+        assert_eq!(meta.resource_type.as_deref(), Some("User"));
+        assert!(meta.created.is_some());
+        assert_eq!(store.get(&id).unwrap(), created);
+
+        let patched = store
+            .patch(&id, &patch_from(r#"{
+                "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+                "Operations": [{"op": "replace", "path": "displayName", "value": "Babs Jensen"}]
+            }"#))
+            .unwrap();
+        assert_eq!(patched.display_name.as_deref(), Some("Babs Jensen"));
+        assert_eq!(store.get(&id).unwrap().display_name.as_deref(), Some("Babs Jensen"));
+
+        let rejected = store.patch(&id, &patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "replace", "path": "meta.created", "value": "2030-01-01T00:00:00Z"}]
+        }"#));
+        assert!(matches!(rejected, Err(SCIMError::InvalidValue(_))));
+        assert_eq!(store.get(&id).unwrap(), patched);
+
+        store.delete(&id).unwrap();
+        assert!(matches!(store.get(&id), Err(SCIMError::NotFoundError(_))));
+        assert!(matches!(store.delete(&id), Err(SCIMError::NotFoundError(_))));
+        assert!(store.is_empty().unwrap());
+    }
+
+    #[test]
+    fn user_store_replace_keeps_id_and_created() {
+        let store = ResourceStore::new();
+        let created = store.create(User { user_name: "bjensen".to_string(), ..Default::default() }).unwrap();
+        let id = created.id.clone().unwrap();
+
+        let replaced = store.replace(&id, User { user_name: "babs".to_string(), ..Default::default() }).unwrap();
+
+        assert_eq!(replaced.id.as_deref(), Some(id.as_str()));
+        assert_eq!(replaced.meta.as_ref().unwrap().created, created.meta.as_ref().unwrap().created);
+        assert_eq!(store.get(&id).unwrap().user_name, "babs");
+        assert!(matches!(store.replace("missing", User::default()), Err(SCIMError::NotFoundError(_))));
+    }
+
+    #[test]
+    fn user_store_lists_with_query() {
+        let store = ResourceStore::new();
+        for (user_name, active) in [("mpepperidge", true), ("bjensen", true), ("jsmith", false)] {
+            store.create(User { user_name: user_name.to_string(), active: Some(active), ..Default::default() }).unwrap();
+        }
+
+        let query = ScimQuery::from_query_string("filter=active%20eq%20true&sortBy=userName&attributes=userName").unwrap();
+        let response = store.list(&query).unwrap();
+
+        assert_eq!(response.total_results, 2);
+        let user_names: Vec<&Value> = response.resources.iter().map(|user| &user["userName"]).collect();
+        assert_eq!(user_names, vec!["bjensen", "mpepperidge"]);
+    }
+
+    #[test]
+    fn group_store_assigns_ids() {
+        let store = ResourceStore::new();
+        let created = store.create(Group { display_name: "Tour Guides".to_string(), ..Default::default() }).unwrap();
+
+        assert_ne!(created.id, "default_id");
+        assert_eq!(store.get(&created.id).unwrap().display_name, "Tour Guides");
+        assert_eq!(created.meta.as_ref().unwrap().resource_type.as_deref(), Some("Group"));
+    }
+}
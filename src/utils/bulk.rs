@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::models::bulk::BulkRequest;
+use crate::utils::error::SCIMError;
+
+const BULK_ID_PREFIX: &str = "bulkId:";
+
+/// Replaces every `bulkId:<id>` string inside `value` with the id assigned to `<id>`.
+fn resolve_value(value: &mut Value, assigned: &HashMap<String, String>) -> Result<(), SCIMError> {
+    match value {
+        Value::String(text) => {
+            if let Some(bulk_id) = text.strip_prefix(BULK_ID_PREFIX) {
+                let id = assigned
+                    .get(bulk_id)
+                    .ok_or_else(|| SCIMError::InvalidValue(format!("bulkId {} has not been assigned an id", bulk_id)))?;
+                *text = id.clone();
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                resolve_value(item, assigned)?;
+            }
+        }
+        Value::Object(fields) => {
+            for field in fields.values_mut() {
+                resolve_value(field, assigned)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Resolves `bulkId` references within a bulk request (RFC 7644 §3.7.2).
+///
+/// An operation's `data` can refer to a resource created earlier in the same request with a `bulkId:<id>`
+/// string, e.g. a Group member whose `value` is `"bulkId:qwerty"`. Every such string, at any depth, is replaced
+/// with the server-assigned id that `assigned` maps `<id>` to.
+///
+/// The request is only modified if every reference resolves.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidValue` if a referenced `bulkId` is missing from `assigned`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use scim_v2::models::bulk::{BulkOperation, BulkRequest};
+/// use scim_v2::utils::bulk::resolve_bulk_ids;
+///
+/// let mut request = BulkRequest {
+///     operations: vec![BulkOperation {
+///         method: "POST".to_string(),
+///         path: "/Groups".to_string(),
+///         data: Some(serde_json::json!({"members": [{"value": "bulkId:qwerty"}]})),
+///         ..Default::default()
+///     }],
+///     ..Default::default()
+/// };
+/// let assigned = HashMap::from([("qwerty".to_string(), "92b725cd-9465-4e7d-8c16-01f8e146b87a".to_string())]);
+///
+/// match resolve_bulk_ids(&mut request, &assigned) {
+///     Ok(_) => println!("Resolved BulkRequest: {:?}", request),
+///     Err(e) => println!("Bulk error: {}", e),
+/// }
+/// ```
+pub fn resolve_bulk_ids(request: &mut BulkRequest, assigned: &HashMap<String, String>) -> Result<(), SCIMError> {
+    let mut resolved = Vec::with_capacity(request.operations.len());
+    for operation in &request.operations {
+        let mut data = operation.data.clone();
+        if let Some(data) = &mut data {
+            resolve_value(data, assigned)?;
+        }
+        resolved.push(data);
+    }
+    for (operation, data) in request.operations.iter_mut().zip(resolved) {
+        operation.data = data;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+
+    fn user_and_group_request() -> BulkRequest {
+        BulkRequest::deserialize(&json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:BulkRequest"],
+            "Operations": [
+                {
+                    "method": "POST",
+                    "path": "/Users",
+                    "bulkId": "qwerty",
+                    "data": {
+                        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+                        "userName": "Alice"
+                    }
+                },
+                {
+                    "method": "POST",
+                    "path": "/Groups",
+                    "bulkId": "ytrewq",
+                    "data": {
+                        "schemas": ["urn:ietf:params:scim:schemas:core:2.0:Group"],
+                        "displayName": "Tour Guides",
+                        "members": [
+                            {"type": "User", "value": "bulkId:qwerty"},
+                            {"type": "User", "value": "2819c223-7f76-453a-919d-413861904646"}
+                        ]
+                    }
+                }
+            ]
+        })
+        .to_string())
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_bulk_ids_replaces_nested_references() {
+        let mut request = user_and_group_request();
+        let assigned = HashMap::from([("qwerty".to_string(), "92b725cd-9465-4e7d-8c16-01f8e146b87a".to_string())]);
+
+        resolve_bulk_ids(&mut request, &assigned).unwrap();
+
+        let members = &request.operations[1].data.as_ref().unwrap()["members"];
+        assert_eq!(members[0]["value"], "92b725cd-9465-4e7d-8c16-01f8e146b87a");
+        assert_eq!(members[1]["value"], "2819c223-7f76-453a-919d-413861904646");
+        assert_eq!(request.operations[0].data.as_ref().unwrap()["userName"], "Alice");
+    }
+
+    #[test]
+    fn resolve_bulk_ids_fails_for_unassigned_reference() {
+        let mut request = user_and_group_request();
+
+        let result = resolve_bulk_ids(&mut request, &HashMap::new());
+
+        assert!(matches!(result, Err(SCIMError::InvalidValue(_))));
+        assert_eq!(request.operations[1].data.as_ref().unwrap()["members"][0]["value"], "bulkId:qwerty");
+    }
+}
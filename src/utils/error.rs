@@ -1,20 +1,51 @@
+use std::error::Error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::io::Read;
 
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+/// Errors returned by the SCIM models and utilities.
+///
+/// New variants may be added in minor releases, so the enum is `#[non_exhaustive]`: a `match` on it outside this
+/// crate needs a wildcard arm. `http_status` and `scim_type` give the response mapping without matching at all.
+///
+/// # Example
+///
+/// ```rust
+/// use scim_v2::utils::error::SCIMError;
+///
+/// fn describe(error: &SCIMError) -> &'static str {
+///     match error {
+///         SCIMError::NotFoundError(_) => "not found",
+///         SCIMError::ConflictError(_) => "conflict",
+///         _ => "other",
+///     }
+/// }
+///
+/// assert_eq!(describe(&SCIMError::NotFoundError("2819c223".to_string())), "not found");
+/// ```
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum SCIMError {
     // Todo: Add 400 bad request SCIM Detail Error Keyword Values mentioned here: https://datatracker.ietf.org/doc/html/rfc7644#section-3.12
     ConflictError(String),
     DeserializationError(serde_json::Error),
     InvalidFieldValue(String),
+    InvalidFilter(String),
     InvalidJsonFormat,
+    InvalidPath(String),
+    InvalidValue(String),
     MissingRequiredField(String),
     NotFoundError(String),
     OtherError(String),
+    PreconditionFailed(String),
     RequestError(String),
     ResourceTypeNotFound(String),
     SchemaNotFound(String),
     SerializationError(serde_json::Error),
+    UnsupportedSchema(String),
 }
 
 impl Display for SCIMError {
@@ -23,14 +54,92 @@ impl Display for SCIMError {
             SCIMError::ConflictError(msg) => write!(f, "Conflict error: {}", msg),
             SCIMError::DeserializationError(e) => write!(f, "Deserialization error: {}", e),
             SCIMError::InvalidFieldValue(msg) => write!(f, "Invalid field value: {}", msg),
+            SCIMError::InvalidFilter(msg) => write!(f, "Invalid filter: {}", msg),
             SCIMError::InvalidJsonFormat => write!(f, "Invalid JSON format"),
+            SCIMError::InvalidPath(msg) => write!(f, "Invalid path: {}", msg),
+            SCIMError::InvalidValue(msg) => write!(f, "Invalid value: {}", msg),
             SCIMError::MissingRequiredField(msg) => write!(f, "Missing required field: {}", msg),
             SCIMError::NotFoundError(msg) => write!(f, "Not found error: {}", msg),
             SCIMError::OtherError(msg) => write!(f, "Other Error: {}", msg),
+            SCIMError::PreconditionFailed(msg) => write!(f, "Precondition failed: {}", msg),
             SCIMError::RequestError(msg) => write!(f, "Request error: {}", msg),
             SCIMError::ResourceTypeNotFound(msg) => write!(f, "Resource type not found: {}", msg),
             SCIMError::SchemaNotFound(msg) => write!(f, "Schema not found: {}", msg),
             SCIMError::SerializationError(e) => write!(f, "Serialization error: {}", e),
+            SCIMError::UnsupportedSchema(msg) => write!(f, "Unsupported schema: {}", msg),
+        }
+    }
+}
+
+/// `SCIMError` is `Send + Sync`, so it can be boxed as `Box<dyn Error + Send + Sync>` or wrapped by error
+/// libraries and moved across threads. `source` exposes the underlying `serde_json::Error`, if any.
+impl Error for SCIMError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SCIMError::DeserializationError(e) | SCIMError::SerializationError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl SCIMError {
+    /// Returns the HTTP status code a server should respond with for this error (RFC 7644 §3.12).
+    ///
+    /// Malformed or invalid requests map to 400, missing resources to 404, uniqueness conflicts
+    /// (`ConflictError`) to 409, failed `If-Match`/`If-None-Match` preconditions to 412, and serialization or
+    /// otherwise unexpected failures to 500.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use scim_v2::utils::error::SCIMError;
+    ///
+    /// let error = SCIMError::ConflictError("userName bjensen is already taken".to_string());
+    /// assert_eq!(error.http_status(), 409);
+    /// assert_eq!(error.scim_type(), Some("uniqueness"));
+    /// ```
+    pub fn http_status(&self) -> u16 {
+        match self {
+            SCIMError::ConflictError(_) => 409,
+            SCIMError::DeserializationError(_) => 400,
+            SCIMError::InvalidFieldValue(_) => 400,
+            SCIMError::InvalidFilter(_) => 400,
+            SCIMError::InvalidJsonFormat => 400,
+            SCIMError::InvalidPath(_) => 400,
+            SCIMError::InvalidValue(_) => 400,
+            SCIMError::MissingRequiredField(_) => 400,
+            SCIMError::NotFoundError(_) => 404,
+            SCIMError::OtherError(_) => 500,
+            SCIMError::PreconditionFailed(_) => 412,
+            SCIMError::RequestError(_) => 400,
+            SCIMError::ResourceTypeNotFound(_) => 404,
+            SCIMError::SchemaNotFound(_) => 404,
+            SCIMError::SerializationError(_) => 500,
+            SCIMError::UnsupportedSchema(_) => 400,
+        }
+    }
+
+    /// Returns the `scimType` detail keyword for this error (RFC 7644 §3.12), if one applies.
+    ///
+    /// Only 400 and 409 responses carry a `scimType`; errors without a fitting keyword return `None`.
+    pub fn scim_type(&self) -> Option<&'static str> {
+        match self {
+            SCIMError::ConflictError(_) => Some("uniqueness"),
+            SCIMError::DeserializationError(_) => Some("invalidSyntax"),
+            SCIMError::InvalidFieldValue(_) => Some("invalidValue"),
+            SCIMError::InvalidFilter(_) => Some("invalidFilter"),
+            SCIMError::InvalidJsonFormat => Some("invalidSyntax"),
+            SCIMError::InvalidPath(_) => Some("invalidPath"),
+            SCIMError::InvalidValue(_) => Some("invalidValue"),
+            SCIMError::MissingRequiredField(_) => Some("invalidValue"),
+            SCIMError::NotFoundError(_) => None,
+            SCIMError::OtherError(_) => None,
+            SCIMError::PreconditionFailed(_) => None,
+            SCIMError::RequestError(_) => None,
+            SCIMError::ResourceTypeNotFound(_) => None,
+            SCIMError::SchemaNotFound(_) => None,
+            SCIMError::SerializationError(_) => None,
+            SCIMError::UnsupportedSchema(_) => Some("invalidValue"),
         }
     }
 }
@@ -39,4 +148,138 @@ impl From<serde_json::Error> for SCIMError {
     fn from(err: serde_json::Error) -> SCIMError {
         SCIMError::DeserializationError(err)
     }
-}
\ No newline at end of file
+}
+
+/// Prefixes a deserialization error with the JSON path of the attribute that failed, e.g. `emails[0].value`, so
+/// that the failing attribute of a large body can be found. Errors at the top level are returned unchanged.
+///
+/// The prefixed error is rebuilt with `serde::de::Error::custom`, so the original line and column only survive in
+/// its message: `line()` and `column()` return 0 and `classify()` reports `Category::Data`.
+fn with_path(path: &serde_path_to_error::Path, err: serde_json::Error) -> SCIMError {
+    let path = path.to_string();
+    if path == "." {
+        return SCIMError::DeserializationError(err);
+    }
+    SCIMError::DeserializationError(serde::de::Error::custom(format!("{}: {}", path, err)))
+}
+
+/// Deserializes `json`, naming the failing JSON path in the error.
+///
+/// The path is only tracked when deserialization fails: the input is then parsed again as a `Value` and
+/// deserialized from that. The message text includes the line and column of the original error.
+pub(crate) fn from_json_str<T: DeserializeOwned>(json: &str) -> Result<T, SCIMError> {
+    serde_json::from_str(json).map_err(|err| {
+        let Ok(value) = serde_json::from_str::<Value>(json) else {
+            return SCIMError::DeserializationError(err);
+        };
+        match serde_path_to_error::deserialize::<_, T>(&value) {
+            Err(path_err) => with_path(path_err.path(), err),
+            Ok(_) => SCIMError::DeserializationError(err),
+        }
+    })
+}
+
+/// Deserializes JSON read from `reader`, naming the failing JSON path in the error.
+///
+/// A reader can't be read twice, so unlike `from_json_str` the path is tracked while the input is deserialized.
+pub(crate) fn from_json_reader<R: Read, T: DeserializeOwned>(reader: R) -> Result<T, SCIMError> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let value = serde_path_to_error::deserialize(&mut deserializer).map_err(|err| {
+        let path = err.path().clone();
+        with_path(&path, err.into_inner())
+    })?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserializes `value`, naming the failing JSON path in the error.
+///
+/// Like `from_json_str`, the path is only tracked when deserialization fails, by deserializing `value` again.
+pub(crate) fn from_json_value<T: DeserializeOwned>(value: Value) -> Result<T, SCIMError> {
+    T::deserialize(&value).map_err(|err| match serde_path_to_error::deserialize::<_, T>(&value) {
+        Err(path_err) => with_path(path_err.path(), err),
+        Ok(_) => SCIMError::DeserializationError(err),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn display_formats_path_filter_schema_and_value_errors() {
+        assert_eq!(SCIMError::InvalidPath("emails[type eq".to_string()).to_string(), "Invalid path: emails[type eq");
+        assert_eq!(SCIMError::InvalidFilter("userName zz \"bjensen\"".to_string()).to_string(), "Invalid filter: userName zz \"bjensen\"");
+        assert_eq!(SCIMError::UnsupportedSchema("urn:example:Device".to_string()).to_string(), "Unsupported schema: urn:example:Device");
+        assert_eq!(SCIMError::InvalidValue("bulkId qwerty".to_string()).to_string(), "Invalid value: bulkId qwerty");
+    }
+
+    #[test]
+    fn display_keeps_existing_messages() {
+        assert_eq!(SCIMError::MissingRequiredField("userName".to_string()).to_string(), "Missing required field: userName");
+        assert_eq!(SCIMError::InvalidFieldValue("emails: x".to_string()).to_string(), "Invalid field value: emails: x");
+    }
+
+    #[test]
+    fn http_status_and_scim_type_cover_each_variant() {
+        let json_error = || serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let message = || "detail".to_string();
+        let cases = vec![
+            (SCIMError::ConflictError(message()), 409, Some("uniqueness")),
+            (SCIMError::DeserializationError(json_error()), 400, Some("invalidSyntax")),
+            (SCIMError::InvalidFieldValue(message()), 400, Some("invalidValue")),
+            (SCIMError::InvalidFilter(message()), 400, Some("invalidFilter")),
+            (SCIMError::InvalidJsonFormat, 400, Some("invalidSyntax")),
+            (SCIMError::InvalidPath(message()), 400, Some("invalidPath")),
+            (SCIMError::InvalidValue(message()), 400, Some("invalidValue")),
+            (SCIMError::MissingRequiredField(message()), 400, Some("invalidValue")),
+            (SCIMError::NotFoundError(message()), 404, None),
+            (SCIMError::OtherError(message()), 500, None),
+            (SCIMError::PreconditionFailed(message()), 412, None),
+            (SCIMError::RequestError(message()), 400, None),
+            (SCIMError::ResourceTypeNotFound(message()), 404, None),
+            (SCIMError::SchemaNotFound(message()), 404, None),
+            (SCIMError::SerializationError(json_error()), 500, None),
+            (SCIMError::UnsupportedSchema(message()), 400, Some("invalidValue")),
+        ];
+
+        for (error, status, scim_type) in cases {
+            assert_eq!((error.http_status(), error.scim_type()), (status, scim_type), "{:?}", error);
+        }
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn scim_error_is_a_send_sync_error() {
+        assert_send_sync::<SCIMError>();
+
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(SCIMError::InvalidPath("emails[".to_string()));
+        assert_eq!(boxed.to_string(), "Invalid path: emails[");
+        assert!(boxed.source().is_none());
+
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let expected = json_error.to_string();
+        let boxed: Box<dyn Error + Send + Sync> = Box::new(SCIMError::DeserializationError(json_error));
+        assert_eq!(boxed.source().unwrap().to_string(), expected);
+        let handle = std::thread::spawn(move || boxed.to_string());
+        assert!(handle.join().unwrap().starts_with("Deserialization error: "));
+    }
+
+    #[test]
+    fn match_with_wildcard_arm_handles_any_variant() {
+        fn status_class(error: &SCIMError) -> &'static str {
+            match error {
+                SCIMError::NotFoundError(_) | SCIMError::ResourceTypeNotFound(_) => "not found",
+                SCIMError::ConflictError(_) => "conflict",
+                _ => "other",
+            }
+        }
+
+        assert_eq!(status_class(&SCIMError::NotFoundError("2819c223".to_string())), "not found");
+        assert_eq!(status_class(&SCIMError::ConflictError("bjensen".to_string())), "conflict");
+        assert_eq!(status_class(&SCIMError::InvalidJsonFormat), "other");
+    }
+}
@@ -0,0 +1,980 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use serde_json::Value;
+
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+
+/// An attribute reference inside a filter, e.g. `userName`, `name.familyName` or
+/// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttrPath {
+    pub urn: Option<String>,
+    pub attribute: String,
+    pub sub_attribute: Option<String>,
+}
+
+/// The comparison operators defined in RFC 7644 §3.4.2.2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+/// A parsed SCIM filter expression.
+///
+/// A value filter followed by a sub-attribute comparison, such as `emails[type eq "work"].value ew "example.com"`,
+/// is represented as a `ValuePath` whose inner filter also carries the sub-attribute comparison, so that both
+/// conditions must hold for the same element.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    Present(AttrPath),
+    Compare(AttrPath, CompareOp, Value),
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Not(Box<FilterExpr>),
+    ValuePath(AttrPath, Box<FilterExpr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Str(String),
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+/// Reads the escape sequence following a backslash in a string literal. Filter values are JSON strings
+/// (RFC 7644 §3.4.2.2), so the JSON escapes are supported, including UTF-16 surrogate pairs written as `\uXXXX`.
+fn unescape(chars: &mut Peekable<Chars<'_>>) -> Result<char, SCIMError> {
+    fn code_unit(chars: &mut Peekable<Chars<'_>>) -> Result<u32, SCIMError> {
+        let hex: String = chars.by_ref().take(4).collect();
+        // `from_str_radix` tolerates a leading sign, so the digits are checked up front.
+        match u32::from_str_radix(&hex, 16) {
+            Ok(unit) if hex.len() == 4 && hex.bytes().all(|b| b.is_ascii_hexdigit()) => Ok(unit),
+            _ => Err(SCIMError::InvalidFilter(format!("invalid unicode escape \\u{}", hex))),
+        }
+    }
+
+    let escaped = match chars.next() {
+        Some(c @ ('"' | '\\' | '/')) => c,
+        Some('b') => '\u{8}',
+        Some('f') => '\u{c}',
+        Some('n') => '\n',
+        Some('r') => '\r',
+        Some('t') => '\t',
+        Some('u') => {
+            let mut unit = code_unit(chars)?;
+            if (0xD800..0xDC00).contains(&unit) {
+                if chars.next() != Some('\\') || chars.next() != Some('u') {
+                    return Err(SCIMError::InvalidFilter("unpaired surrogate in unicode escape".to_string()));
+                }
+                let low = code_unit(chars)?;
+                if !(0xDC00..0xE000).contains(&low) {
+                    return Err(SCIMError::InvalidFilter("unpaired surrogate in unicode escape".to_string()));
+                }
+                unit = 0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00);
+            }
+            return char::from_u32(unit).ok_or_else(|| SCIMError::InvalidFilter("unpaired surrogate in unicode escape".to_string()));
+        }
+        Some(c) => return Err(SCIMError::InvalidFilter(format!("invalid escape \\{}", c))),
+        None => return Err(SCIMError::InvalidFilter("unterminated string literal".to_string())),
+    };
+    Ok(escaped)
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, SCIMError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::LParen,
+                    ')' => Token::RParen,
+                    '[' => Token::LBracket,
+                    _ => Token::RBracket,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => literal.push(unescape(&mut chars)?),
+                        Some(c) => literal.push(c),
+                        None => return Err(SCIMError::InvalidFilter("unterminated string literal".to_string())),
+                    }
+                }
+                tokens.push(Token::Str(literal));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || "()[]\"".contains(c) {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_attribute_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '$' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+impl AttrPath {
+    /// Parses an attribute path of the form `[urn:...:]attribute[.subAttribute]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `SCIMError::InvalidPath` if `path` is not a well-formed attribute path.
+    pub fn parse(path: &str) -> Result<Self, SCIMError> {
+        let invalid = || SCIMError::InvalidPath(path.to_string());
+        let (urn, rest) = if path.len() > 4 && path.get(..4).map_or(false, |prefix| prefix.eq_ignore_ascii_case("urn:")) {
+            let idx = path.rfind(':').ok_or_else(invalid)?;
+            (Some(path[..idx].to_string()), &path[idx + 1..])
+        } else {
+            (None, path)
+        };
+        let (attribute, sub_attribute) = match rest.split_once('.') {
+            Some((attribute, sub_attribute)) => (attribute, Some(sub_attribute)),
+            None => (rest, None),
+        };
+        if !is_attribute_name(attribute) || !sub_attribute.map_or(true, is_attribute_name) {
+            return Err(invalid());
+        }
+        Ok(AttrPath {
+            urn,
+            attribute: attribute.to_string(),
+            sub_attribute: sub_attribute.map(str::to_string),
+        })
+    }
+}
+
+impl AttrPath {
+    /// Returns `true` for the `dateTime` attributes `meta.created` and `meta.lastModified`.
+    fn is_datetime(&self) -> bool {
+        let core = self.urn.as_deref().map_or(true, |urn| urn.to_ascii_lowercase().starts_with("urn:ietf:params:scim:schemas:core:"));
+        let sub_attribute = self.sub_attribute.as_deref().unwrap_or_default();
+        core && self.attribute.eq_ignore_ascii_case("meta")
+            && (sub_attribute.eq_ignore_ascii_case("created") || sub_attribute.eq_ignore_ascii_case("lastModified"))
+    }
+}
+
+impl fmt::Display for AttrPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(urn) = &self.urn {
+            write!(f, "{}:", urn)?;
+        }
+        write!(f, "{}", self.attribute)?;
+        if let Some(sub_attribute) = &self.sub_attribute {
+            write!(f, ".{}", sub_attribute)?;
+        }
+        Ok(())
+    }
+}
+
+impl CompareOp {
+    fn parse(word: &str) -> Option<Self> {
+        match word.to_ascii_lowercase().as_str() {
+            "eq" => Some(CompareOp::Eq),
+            "ne" => Some(CompareOp::Ne),
+            "co" => Some(CompareOp::Co),
+            "sw" => Some(CompareOp::Sw),
+            "ew" => Some(CompareOp::Ew),
+            "gt" => Some(CompareOp::Gt),
+            "ge" => Some(CompareOp::Ge),
+            "lt" => Some(CompareOp::Lt),
+            "le" => Some(CompareOp::Le),
+            _ => None,
+        }
+    }
+}
+
+/// The nesting depth `parse_filter` allows: each parenthesised group, `not (...)` or value path `[...]` is a level.
+pub const DEFAULT_MAX_FILTER_DEPTH: usize = 20;
+
+/// The longest filter, in bytes, that `parse_filter` accepts. Long `and`/`or` chains also deepen the parsed
+/// expression, so this bounds the work done evaluating and dropping it as well as parsing it.
+pub const MAX_FILTER_LENGTH: usize = 8192;
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl Parser {
+    /// Runs `parse` one nesting level deeper, failing instead of recursing past `max_depth`.
+    fn nested(&mut self, parse: impl FnOnce(&mut Self) -> Result<FilterExpr, SCIMError>) -> Result<FilterExpr, SCIMError> {
+        if self.depth >= self.max_depth {
+            return Err(SCIMError::InvalidFilter("max depth exceeded".to_string()));
+        }
+        self.depth += 1;
+        let expr = parse(self);
+        self.depth -= 1;
+        expr
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), SCIMError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(SCIMError::InvalidFilter(format!("expected {:?}, found {:?}", expected, token))),
+            None => Err(SCIMError::InvalidFilter(format!("expected {:?}, found end of input", expected))),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, SCIMError> {
+        let mut expr = self.parse_and()?;
+        while self.peek_keyword("or") {
+            self.next();
+            expr = FilterExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, SCIMError> {
+        let mut expr = self.parse_unary()?;
+        while self.peek_keyword("and") {
+            self.next();
+            expr = FilterExpr::And(Box::new(expr), Box::new(self.parse_unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_unary(&mut self) -> Result<FilterExpr, SCIMError> {
+        if self.peek_keyword("not") {
+            self.next();
+            self.expect(Token::LParen)?;
+            let expr = self.nested(Self::parse_or)?;
+            self.expect(Token::RParen)?;
+            return Ok(FilterExpr::Not(Box::new(expr)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.next();
+            let expr = self.nested(Self::parse_or)?;
+            self.expect(Token::RParen)?;
+            return Ok(expr);
+        }
+        self.parse_attribute_expression()
+    }
+
+    fn parse_attribute_expression(&mut self) -> Result<FilterExpr, SCIMError> {
+        let path = match self.next() {
+            Some(Token::Word(word)) => {
+                AttrPath::parse(&word).map_err(|_| SCIMError::InvalidFilter(format!("invalid attribute path {}", word)))?
+            }
+            Some(token) => return Err(SCIMError::InvalidFilter(format!("expected attribute path, found {:?}", token))),
+            None => return Err(SCIMError::InvalidFilter("unexpected end of input".to_string())),
+        };
+
+        if self.peek() != Some(&Token::LBracket) {
+            return self.parse_comparison(path);
+        }
+        self.next();
+        let inner = self.nested(Self::parse_or)?;
+        self.expect(Token::RBracket)?;
+
+        // `emails[type eq "work"].value ew "example.com"`: the trailing comparison applies to the same element.
+        let sub_attribute = match self.peek() {
+            Some(Token::Word(word)) if word.starts_with('.') => word[1..].to_string(),
+            _ => return Ok(FilterExpr::ValuePath(path, Box::new(inner))),
+        };
+        self.next();
+        let element_path = AttrPath::parse(&sub_attribute)
+            .map_err(|_| SCIMError::InvalidFilter(format!("invalid sub-attribute {}", sub_attribute)))?;
+        let comparison = self.parse_comparison(element_path)?;
+        Ok(FilterExpr::ValuePath(path, Box::new(FilterExpr::And(Box::new(inner), Box::new(comparison)))))
+    }
+
+    fn parse_comparison(&mut self, path: AttrPath) -> Result<FilterExpr, SCIMError> {
+        let operator = match self.next() {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case("pr") => return Ok(FilterExpr::Present(path)),
+            Some(Token::Word(word)) => {
+                CompareOp::parse(&word).ok_or_else(|| SCIMError::InvalidFilter(format!("unknown operator {}", word)))?
+            }
+            Some(token) => return Err(SCIMError::InvalidFilter(format!("expected operator, found {:?}", token))),
+            None => return Err(SCIMError::InvalidFilter("expected operator, found end of input".to_string())),
+        };
+        let value = match self.next() {
+            Some(Token::Str(literal)) => Value::String(literal),
+            Some(Token::Word(word)) => parse_literal(&word)?,
+            Some(token) => return Err(SCIMError::InvalidFilter(format!("expected value, found {:?}", token))),
+            None => return Err(SCIMError::InvalidFilter("expected value, found end of input".to_string())),
+        };
+        if is_ordering(operator) && path.is_datetime() && value.as_str().and_then(parse_datetime).is_none() {
+            return Err(SCIMError::InvalidFilter(format!("invalid dateTime {} for {}", value, path)));
+        }
+        Ok(FilterExpr::Compare(path, operator, value))
+    }
+}
+
+fn parse_literal(word: &str) -> Result<Value, SCIMError> {
+    match word.to_ascii_lowercase().as_str() {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        "null" => return Ok(Value::Null),
+        _ => {}
+    }
+    serde_json::from_str::<serde_json::Number>(word)
+        .map(Value::Number)
+        .map_err(|_| SCIMError::InvalidFilter(format!("invalid value {}", word)))
+}
+
+/// Parses a SCIM filter expression (RFC 7644 §3.4.2.2) into a `FilterExpr`.
+///
+/// Operators and the `and`/`or`/`not` keywords are matched case-insensitively. `not` binds tighter than `and`,
+/// which binds tighter than `or`.
+///
+/// Filters usually come from untrusted clients, so nesting is limited to `DEFAULT_MAX_FILTER_DEPTH` levels and
+/// input to `MAX_FILTER_LENGTH` bytes; use `parse_filter_with_max_depth` for a different depth limit.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFilter` if the expression is malformed, nested too deeply or too long, or if `gt`,
+/// `ge`, `lt` or `le` compares `meta.created` or `meta.lastModified` with a value that is not an RFC 3339 timestamp.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::utils::filter::parse_filter;
+///
+/// match parse_filter(r#"userName eq "bjensen" and emails[type eq "work"]"#) {
+///     Ok(filter) => println!("Parsed filter: {:?}", filter),
+///     Err(e) => println!("Invalid filter: {}", e),
+/// }
+/// ```
+pub fn parse_filter(input: &str) -> Result<FilterExpr, SCIMError> {
+    parse_filter_with_max_depth(input, DEFAULT_MAX_FILTER_DEPTH)
+}
+
+/// Parses a SCIM filter expression like `parse_filter`, allowing at most `max_depth` levels of nesting.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFilter("max depth exceeded")` if the expression nests deeper than `max_depth`, and
+/// `SCIMError::InvalidFilter` if it is otherwise malformed or longer than `MAX_FILTER_LENGTH` bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::utils::filter::parse_filter_with_max_depth;
+///
+/// assert!(parse_filter_with_max_depth(r#"(userName eq "bjensen")"#, 1).is_ok());
+/// assert!(parse_filter_with_max_depth(r#"((userName eq "bjensen"))"#, 1).is_err());
+/// ```
+pub fn parse_filter_with_max_depth(input: &str, max_depth: usize) -> Result<FilterExpr, SCIMError> {
+    if input.len() > MAX_FILTER_LENGTH {
+        return Err(SCIMError::InvalidFilter("max length exceeded".to_string()));
+    }
+    let mut parser = Parser { tokens: tokenize(input)?, position: 0, depth: 0, max_depth };
+    let expr = parser.parse_or()?;
+    match parser.next() {
+        None => Ok(expr),
+        Some(token) => Err(SCIMError::InvalidFilter(format!("unexpected trailing token {:?}", token))),
+    }
+}
+
+fn lookup<'a>(value: &'a Value, name: &str) -> Option<&'a Value> {
+    value
+        .as_object()?
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value)
+}
+
+/// Finds an attribute that is not a core attribute in the resource's schema extensions, so that `department` can
+/// stand for `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`. Only an attribute found in
+/// exactly one extension is returned; if several extensions have it, the bare name is ambiguous.
+fn lookup_in_extensions<'a>(context: &'a Value, name: &str) -> Option<&'a Value> {
+    let mut found = context
+        .as_object()?
+        .iter()
+        .filter(|(key, _)| key.len() > 4 && key.get(..4).map_or(false, |prefix| prefix.eq_ignore_ascii_case("urn:")))
+        .filter_map(|(_, extension)| lookup(extension, name));
+    let attribute = found.next()?;
+    match found.next() {
+        Some(_) => None,
+        None => Some(attribute),
+    }
+}
+
+/// Collects the values `path` refers to within `context`, flattening multi-valued attributes.
+fn resolve<'a>(context: &'a Value, path: &AttrPath) -> Vec<&'a Value> {
+    // Passwords are write-only and never returned, so they must not be observable through a filter either.
+    if path.urn.is_none() && path.attribute.eq_ignore_ascii_case("password") {
+        return vec![];
+    }
+
+    let container = match &path.urn {
+        Some(urn) => match lookup(context, urn) {
+            Some(extension) => extension,
+            None if urn.to_ascii_lowercase().starts_with("urn:ietf:params:scim:schemas:core:") => context,
+            None => return vec![],
+        },
+        None => context,
+    };
+    let attribute = match (&path.urn, lookup(container, &path.attribute)) {
+        (None, None) => lookup_in_extensions(context, &path.attribute),
+        (_, attribute) => attribute,
+    };
+    let elements: Vec<&Value> = match attribute {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(Value::Null) | None => return vec![],
+        Some(value) => vec![value],
+    };
+    match &path.sub_attribute {
+        None => elements,
+        Some(sub_attribute) => elements
+            .into_iter()
+            .filter_map(|element| lookup(element, sub_attribute))
+            .flat_map(|value| match value {
+                Value::Array(items) => items.iter().collect(),
+                value => vec![value],
+            })
+            .collect(),
+    }
+}
+
+fn is_present(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(fields) => !fields.is_empty(),
+        _ => true,
+    }
+}
+
+fn is_ordering(operator: CompareOp) -> bool {
+    matches!(operator, CompareOp::Gt | CompareOp::Ge | CompareOp::Lt | CompareOp::Le)
+}
+
+/// Parses an RFC 3339 timestamp, e.g. `2011-05-13T04:42:34Z` or `2011-05-13T06:42:34.5+02:00`, into seconds and
+/// nanoseconds since the Unix epoch, so that timestamps with different offsets or precision compare correctly.
+#[cfg(feature = "chrono")]
+fn parse_datetime(value: &str) -> Option<(i64, u32)> {
+    let datetime = chrono::DateTime::parse_from_rfc3339(value).ok()?;
+    Some((datetime.timestamp(), datetime.timestamp_subsec_nanos()))
+}
+
+/// Parses an RFC 3339 timestamp, e.g. `2011-05-13T04:42:34Z` or `2011-05-13T06:42:34.5+02:00`, into seconds and
+/// nanoseconds since the Unix epoch, so that timestamps with different offsets or precision compare correctly.
+#[cfg(not(feature = "chrono"))]
+fn parse_datetime(value: &str) -> Option<(i64, u32)> {
+    fn number(digits: &str) -> Option<i64> {
+        if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    // Only ASCII is valid, which also keeps the slicing below on character boundaries.
+    if !value.is_ascii() {
+        return None;
+    }
+    let bytes = value.as_bytes();
+    if bytes.len() < 20 || bytes[4] != b'-' || bytes[7] != b'-' || !matches!(bytes[10], b'T' | b't') || bytes[13] != b':' || bytes[16] != b':' {
+        return None;
+    }
+    let (year, month, day) = (number(&value[..4])?, number(&value[5..7])?, number(&value[8..10])?);
+    let (hour, minute, second) = (number(&value[11..13])?, number(&value[14..16])?, number(&value[17..19])?);
+    let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap_year => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    };
+    if !(1..=12).contains(&month) || !(1..=days_in_month).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let mut rest = &value[19..];
+    let mut nanos = 0;
+    if let Some(fraction) = rest.strip_prefix('.') {
+        let digits = fraction.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            return None;
+        }
+        let padded = format!("{:0<9}", &fraction[..digits.min(9)]);
+        nanos = number(&padded)? as u32;
+        rest = &fraction[digits..];
+    }
+    let offset = match rest.as_bytes() {
+        [b'Z' | b'z'] => 0,
+        [sign @ (b'+' | b'-'), _, _, b':', _, _] => {
+            let (hours, minutes) = (number(&rest[1..3])?, number(&rest[4..6])?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3_600 + minutes * 60;
+            if *sign == b'-' {
+                -offset
+            } else {
+                offset
+            }
+        }
+        _ => return None,
+    };
+
+    let days = crate::models::scim_schema::days_from_civil(year, month, day);
+    Some((days * 86_400 + hour * 3_600 + minute * 60 + second - offset, nanos))
+}
+
+/// Orders a `dateTime` attribute against a timestamp by instant rather than lexically. Values that are not
+/// timestamps never match.
+fn compare_datetime(actual: &Value, operator: CompareOp, expected: &Value) -> bool {
+    let (actual, expected) = match (actual.as_str().and_then(parse_datetime), expected.as_str().and_then(parse_datetime)) {
+        (Some(actual), Some(expected)) => (actual, expected),
+        _ => return false,
+    };
+    match operator {
+        CompareOp::Gt => actual > expected,
+        CompareOp::Ge => actual >= expected,
+        CompareOp::Lt => actual < expected,
+        CompareOp::Le => actual <= expected,
+        _ => false,
+    }
+}
+
+fn compare(actual: &Value, operator: CompareOp, expected: &Value) -> bool {
+    // Multi-valued complex attributes compare against their `value` sub-attribute (RFC 7644 §3.4.2.2).
+    let actual = match actual {
+        Value::Object(_) => match lookup(actual, "value") {
+            Some(value) => value,
+            None => return false,
+        },
+        _ => actual,
+    };
+    match (actual, expected) {
+        (Value::String(actual), Value::String(expected)) => {
+            let actual = actual.to_lowercase();
+            let expected = expected.to_lowercase();
+            match operator {
+                CompareOp::Eq => actual == expected,
+                CompareOp::Ne => actual != expected,
+                CompareOp::Co => actual.contains(&expected),
+                CompareOp::Sw => actual.starts_with(&expected),
+                CompareOp::Ew => actual.ends_with(&expected),
+                CompareOp::Gt => actual > expected,
+                CompareOp::Ge => actual >= expected,
+                CompareOp::Lt => actual < expected,
+                CompareOp::Le => actual <= expected,
+            }
+        }
+        (Value::Number(actual), Value::Number(expected)) => {
+            let (actual, expected) = match (actual.as_f64(), expected.as_f64()) {
+                (Some(actual), Some(expected)) => (actual, expected),
+                _ => return false,
+            };
+            match operator {
+                CompareOp::Eq => actual == expected,
+                CompareOp::Ne => actual != expected,
+                CompareOp::Gt => actual > expected,
+                CompareOp::Ge => actual >= expected,
+                CompareOp::Lt => actual < expected,
+                CompareOp::Le => actual <= expected,
+                CompareOp::Co | CompareOp::Sw | CompareOp::Ew => false,
+            }
+        }
+        (Value::Bool(actual), Value::Bool(expected)) => match operator {
+            CompareOp::Eq => actual == expected,
+            CompareOp::Ne => actual != expected,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+impl FilterExpr {
+    /// Evaluates the filter against a `User`.
+    ///
+    /// Attribute paths resolve against the user's SCIM attributes (including extension attributes addressed by
+    /// their URN), string comparisons are case-insensitive, and a multi-valued attribute matches if any of its
+    /// elements does. `pr` is true when the attribute is set to a non-empty value. `gt`, `ge`, `lt` and `le` on
+    /// `meta.created` and `meta.lastModified` compare RFC 3339 timestamps as instants rather than as strings.
+    ///
+    /// Attributes that cannot be resolved never match rather than raising an error. This includes unknown
+    /// attribute names and `password`, which is write-only and therefore never matched. An attribute without a URN
+    /// prefix that is not a core attribute is looked up in the schema extensions, e.g. `department` in the
+    /// enterprise extension, as long as only one extension has it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use scim_v2::models::user::User;
+    /// use scim_v2::utils::filter::parse_filter;
+    ///
+    /// let user = User {
+    ///     user_name: "bjensen@example.com".to_string(),
+    ///     active: Some(true),
+    ///     ..Default::default()
+    /// };
+    /// let filter = parse_filter(r#"active eq true and userName sw "BJENSEN""#).unwrap();
+    /// assert!(filter.matches(&user));
+    /// ```
+    pub fn matches(&self, user: &User) -> bool {
+        match serde_json::to_value(user) {
+            Ok(resource) => self.matches_json(&resource),
+            Err(_) => false,
+        }
+    }
+
+    /// Evaluates the filter against the JSON representation of a resource.
+    pub fn matches_json(&self, resource: &Value) -> bool {
+        match self {
+            FilterExpr::Present(path) => resolve(resource, path).into_iter().any(is_present),
+            FilterExpr::Compare(path, operator, Value::Null) => {
+                let present = resolve(resource, path).into_iter().any(is_present);
+                match operator {
+                    CompareOp::Eq => !present,
+                    CompareOp::Ne => present,
+                    _ => false,
+                }
+            }
+            FilterExpr::Compare(path, CompareOp::Ne, expected) => {
+                !resolve(resource, path).into_iter().any(|actual| compare(actual, CompareOp::Eq, expected))
+            }
+            FilterExpr::Compare(path, operator, expected) if is_ordering(*operator) && path.is_datetime() => {
+                resolve(resource, path).into_iter().any(|actual| compare_datetime(actual, *operator, expected))
+            }
+            FilterExpr::Compare(path, operator, expected) => {
+                resolve(resource, path).into_iter().any(|actual| compare(actual, *operator, expected))
+            }
+            FilterExpr::And(left, right) => left.matches_json(resource) && right.matches_json(resource),
+            FilterExpr::Or(left, right) => left.matches_json(resource) || right.matches_json(resource),
+            FilterExpr::Not(inner) => !inner.matches_json(resource),
+            FilterExpr::ValuePath(path, inner) => {
+                let path = AttrPath { sub_attribute: None, ..path.clone() };
+                resolve(resource, &path).into_iter().any(|element| inner.matches_json(element))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::enterprise_user::EnterpriseUser;
+    use crate::models::user::{Email, Name};
+
+    fn users() -> Vec<User> {
+        vec![
+            User {
+                user_name: "bjensen@example.com".to_string(),
+                active: Some(true),
+                password: Some("t1meMa$heen".to_string()),
+                name: Some(Name {
+                    family_name: Some("Jensen".to_string()),
+                    ..Default::default()
+                }),
+                emails: Some(vec![
+                    Email {
+                        value: Some("bjensen@example.com".to_string()),
+                        type_: Some("work".to_string()),
+                        ..Default::default()
+                    },
+                    Email {
+                        value: Some("babs@jensen.org".to_string()),
+                        type_: Some("home".to_string()),
+                        ..Default::default()
+                    },
+                ]),
+                ..Default::default()
+            },
+            User {
+                user_name: "mpepperidge@example.org".to_string(),
+                active: Some(true),
+                emails: Some(vec![Email {
+                    value: Some("mandy@example.com".to_string()),
+                    type_: Some("home".to_string()),
+                    ..Default::default()
+                }]),
+                ..Default::default()
+            },
+            User {
+                user_name: "inactive@example.com".to_string(),
+                active: Some(false),
+                ..Default::default()
+            },
+        ]
+    }
+
+    fn matching_user_names(filter: &str) -> Vec<String> {
+        let filter = parse_filter(filter).unwrap();
+        users().into_iter().filter(|user| filter.matches(user)).map(|user| user.user_name).collect()
+    }
+
+    #[test]
+    fn parse_filter_builds_value_path_with_sub_attribute_comparison() {
+        let filter = parse_filter(r#"emails[type eq "work"].value ew "example.com""#).unwrap();
+        let emails = AttrPath { urn: None, attribute: "emails".to_string(), sub_attribute: None };
+        let type_ = AttrPath { urn: None, attribute: "type".to_string(), sub_attribute: None };
+        let value = AttrPath { urn: None, attribute: "value".to_string(), sub_attribute: None };
+        assert_eq!(
+            filter,
+            FilterExpr::ValuePath(
+                emails,
+                Box::new(FilterExpr::And(
+                    Box::new(FilterExpr::Compare(type_, CompareOp::Eq, Value::String("work".to_string()))),
+                    Box::new(FilterExpr::Compare(value, CompareOp::Ew, Value::String("example.com".to_string()))),
+                )),
+            )
+        );
+    }
+
+    #[test]
+    fn parse_filter_honours_precedence() {
+        let filter = parse_filter(r#"title pr or userType eq "Employee" and active eq true"#).unwrap();
+        assert!(matches!(filter, FilterExpr::Or(_, right) if matches!(*right, FilterExpr::And(_, _))));
+    }
+
+    #[test]
+    fn parse_filter_rejects_malformed_input() {
+        assert!(matches!(parse_filter("userName eq"), Err(SCIMError::InvalidFilter(_))));
+        assert!(matches!(parse_filter(r#"userName zz "x""#), Err(SCIMError::InvalidFilter(_))));
+        assert!(matches!(parse_filter(r#"(userName eq "x""#), Err(SCIMError::InvalidFilter(_))));
+        assert!(matches!(parse_filter(r#"userName eq "x" extra"#), Err(SCIMError::InvalidFilter(_))));
+    }
+
+    #[test]
+    fn matches_combined_filter_from_request() {
+        assert_eq!(
+            matching_user_names(r#"active eq true and emails[type eq "work"].value ew "example.com""#),
+            vec!["bjensen@example.com"]
+        );
+    }
+
+    #[test]
+    fn matches_eq_is_case_insensitive_for_strings() {
+        assert_eq!(matching_user_names(r#"userName eq "BJENSEN@example.COM""#), vec!["bjensen@example.com"]);
+        assert_eq!(matching_user_names(r#"name.familyName eq "jensen""#), vec!["bjensen@example.com"]);
+    }
+
+    #[test]
+    fn matches_present_operator() {
+        assert_eq!(matching_user_names("emails pr"), vec!["bjensen@example.com", "mpepperidge@example.org"]);
+        assert_eq!(matching_user_names("name pr"), vec!["bjensen@example.com"]);
+        assert!(matching_user_names("nickName pr").is_empty());
+    }
+
+    #[test]
+    fn matches_multi_valued_attribute_when_any_element_matches() {
+        assert_eq!(
+            matching_user_names(r#"emails[type eq "home"]"#),
+            vec!["bjensen@example.com", "mpepperidge@example.org"]
+        );
+        assert_eq!(matching_user_names(r#"emails co "jensen.org""#), vec!["bjensen@example.com"]);
+    }
+
+    #[test]
+    fn matches_not_and_or() {
+        assert_eq!(matching_user_names("not (active eq true)"), vec!["inactive@example.com"]);
+        assert_eq!(
+            matching_user_names(r#"userName sw "inactive" or name.familyName eq "Jensen""#),
+            vec!["bjensen@example.com", "inactive@example.com"]
+        );
+    }
+
+    #[test]
+    fn matches_unsupported_attributes_as_false() {
+        assert!(matching_user_names(r#"password eq "t1meMa$heen""#).is_empty());
+        assert!(matching_user_names("password pr").is_empty());
+        assert!(matching_user_names(r#"favoriteColor eq "blue""#).is_empty());
+    }
+
+    #[test]
+    fn parse_filter_rejects_deep_nesting_without_overflowing() {
+        let result = parse_filter(&"(".repeat(10_000));
+        assert!(matches!(result, Err(SCIMError::InvalidFilter(_))));
+
+        let nested = |depth: usize| format!("{}userName eq \"bjensen\"{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(parse_filter(&nested(DEFAULT_MAX_FILTER_DEPTH)).is_ok());
+        match parse_filter(&nested(DEFAULT_MAX_FILTER_DEPTH + 1)) {
+            Err(SCIMError::InvalidFilter(message)) => assert_eq!(message, "max depth exceeded"),
+            other => panic!("expected InvalidFilter, got {:?}", other),
+        }
+        assert!(parse_filter_with_max_depth(&nested(50), 50).is_ok());
+    }
+
+    #[test]
+    fn parse_filter_rejects_overlong_input() {
+        let filter = vec![r#"userName eq "bjensen""#; 1000].join(" or ");
+        match parse_filter(&filter) {
+            Err(SCIMError::InvalidFilter(message)) => assert_eq!(message, "max length exceeded"),
+            other => panic!("expected InvalidFilter, got {:?}", other),
+        }
+    }
+
+    fn user_modified_at(user_name: &str, last_modified: &str) -> Value {
+        serde_json::json!({"userName": user_name, "meta": {"resourceType": "User", "lastModified": last_modified}})
+    }
+
+    #[test]
+    fn matches_last_modified_as_datetime() {
+        let users = [
+            user_modified_at("before", "2009-12-31T23:59:59Z"),
+            user_modified_at("boundary", "2011-05-13T04:42:34Z"),
+            user_modified_at("after", "2011-05-13T04:42:34.001Z"),
+            // The same instant as the boundary, written with a +02:00 offset.
+            user_modified_at("offset", "2011-05-13T06:42:34+02:00"),
+        ];
+        let matching = |filter: &str| -> Vec<&str> {
+            let filter = parse_filter(filter).unwrap();
+            users.iter().filter(|user| filter.matches_json(user)).map(|user| user["userName"].as_str().unwrap()).collect()
+        };
+
+        assert_eq!(matching(r#"meta.lastModified gt "2011-05-13T04:42:34Z""#), vec!["after"]);
+        assert_eq!(matching(r#"meta.lastModified ge "2011-05-13T04:42:34Z""#), vec!["boundary", "after", "offset"]);
+        assert_eq!(matching(r#"meta.lastModified lt "2011-05-13T04:42:34Z""#), vec!["before"]);
+        assert_eq!(matching(r#"meta.lastModified le "2011-05-13T00:42:34-04:00""#), vec!["before", "boundary", "offset"]);
+        assert_eq!(matching(r#"meta.lastModified gt "2010-01-01T00:00:00Z""#), vec!["boundary", "after", "offset"]);
+    }
+
+    #[test]
+    fn parse_filter_rejects_invalid_datetime_for_datetime_attribute() {
+        for value in [
+            r#""2011-05-13""#,
+            r#""yesterday""#,
+            r#""2011-02-30T00:00:00Z""#,
+            r#""2011-05-13T04:42:34""#,
+            "20110513",
+            r#""2011-05-13T04:42:3éZ""#,
+            r#""2011-05-13T04:42:34.5+0é:00""#,
+        ] {
+            let filter = format!("meta.lastModified gt {}", value);
+            assert!(matches!(parse_filter(&filter), Err(SCIMError::InvalidFilter(_))), "{}", filter);
+        }
+        assert!(matches!(parse_filter(r#"meta.created le "not a date""#), Err(SCIMError::InvalidFilter(_))));
+        assert!(parse_filter(r#"meta.lastModified eq "2011-05-13""#).is_ok());
+        assert!(parse_filter(r#"title gt "2011-05-13""#).is_ok());
+    }
+
+    #[test]
+    fn matches_enterprise_attributes_by_urn_and_bare_name() {
+        let users = [
+            User {
+                user_name: "bjensen".to_string(),
+                enterprise_user: Some(EnterpriseUser { department: Some("Tour Operations".to_string()), ..Default::default() }),
+                ..Default::default()
+            },
+            User {
+                user_name: "jsmith".to_string(),
+                enterprise_user: Some(EnterpriseUser { department: Some("Finance".to_string()), ..Default::default() }),
+                ..Default::default()
+            },
+            User { user_name: "mpepperidge".to_string(), ..Default::default() },
+        ];
+        let matching = |filter: &str| -> Vec<String> {
+            let filter = parse_filter(filter).unwrap();
+            users.iter().filter(|user| filter.matches(user)).map(|user| user.user_name.clone()).collect()
+        };
+
+        assert_eq!(matching(r#"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department eq "Tour Operations""#), vec!["bjensen"]);
+        assert_eq!(matching(r#"department eq "tour operations""#), vec!["bjensen"]);
+        assert_eq!(matching("department pr"), vec!["bjensen", "jsmith"]);
+        assert_eq!(matching(r#"not (department eq "Finance")"#), vec!["bjensen", "mpepperidge"]);
+    }
+
+    #[test]
+    fn bare_extension_attribute_must_be_unambiguous() {
+        let resource = serde_json::json!({
+            "userName": "bjensen",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"},
+            "urn:example:params:scim:schemas:extension:2.0:User": {"department": "Tour Operations"}
+        });
+
+        assert!(!parse_filter(r#"department eq "Tour Operations""#).unwrap().matches_json(&resource));
+        assert!(parse_filter(r#"urn:example:params:scim:schemas:extension:2.0:User:department eq "Tour Operations""#)
+            .unwrap()
+            .matches_json(&resource));
+    }
+
+    #[test]
+    fn bare_extension_lookup_skips_non_ascii_keys() {
+        let resource = serde_json::json!({
+            "abcé": {"department": "Tour Operations"},
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"}
+        });
+
+        assert!(!parse_filter("department pr").unwrap().matches_json(&serde_json::json!({"abcé": {}})));
+        assert!(parse_filter(r#"department eq "Tour Operations""#).unwrap().matches_json(&resource));
+    }
+
+    #[test]
+    fn parse_filter_unescapes_string_literals() {
+        let filter = parse_filter(r#"displayName eq "John \"Babs\" Jensen""#).unwrap();
+        let display_name = AttrPath { urn: None, attribute: "displayName".to_string(), sub_attribute: None };
+        assert_eq!(filter, FilterExpr::Compare(display_name, CompareOp::Eq, Value::String(r#"John "Babs" Jensen"#.to_string())));
+
+        let literal = |filter: &str| match parse_filter(filter).unwrap() {
+            FilterExpr::Compare(_, _, Value::String(literal)) => literal,
+            other => panic!("expected a string comparison, got {:?}", other),
+        };
+        assert_eq!(literal(r#"title eq "C:\\Tours\\""#), r"C:\Tours\");
+        assert_eq!(literal(r#"title eq "a\/b\tc\u00e9\ud83d\ude00""#), "a/b\tc\u{e9}\u{1f600}");
+
+        let user = User { user_name: "bjensen".to_string(), display_name: Some(r#"John "Babs" Jensen"#.to_string()), ..Default::default() };
+        assert!(parse_filter(r#"displayName eq "john \"babs\" jensen""#).unwrap().matches(&user));
+    }
+
+    #[test]
+    fn parse_filter_rejects_unterminated_and_malformed_literals() {
+        for filter in [
+            r#"displayName eq "John"#,
+            r#"displayName eq "John \"Babs\""#,
+            r#"displayName eq "John \"#,
+            r#"displayName eq "John \x""#,
+            r#"displayName eq "\u12""#,
+            r#"displayName eq "\ud83d""#,
+            r#"displayName eq "\u+0e9""#,
+        ] {
+            assert!(matches!(parse_filter(filter), Err(SCIMError::InvalidFilter(_))), "{}", filter);
+        }
+    }
+}
@@ -0,0 +1,1105 @@
+use serde_json::{Map, Value};
+
+use crate::models::others::{PatchOp, PatchOperations};
+use crate::models::schema_uri;
+use crate::models::scim_schema::{get_schemas, Mutability, Schema};
+use crate::models::user::{User, ATTRIBUTE_NAMES};
+use crate::utils::error::{from_json_value, SCIMError};
+use crate::utils::filter::{parse_filter, AttrPath, CompareOp, FilterExpr};
+
+const MULTI_VALUED_ATTRIBUTES: &[&str] = &[
+    "schemas", "emails", "addresses", "phoneNumbers", "ims", "photos", "groups", "entitlements", "roles",
+    "x509Certificates",
+];
+
+// Multi-valued attributes whose elements are identified by `value` alone. Identity providers commonly re-send the
+// full list on every `add`, so an element whose `value` is already present is skipped even if other members differ.
+const VALUE_KEYED_ATTRIBUTES: &[&str] = &["roles", "entitlements"];
+
+// Attributes common to every resource (RFC 7643 §3.1) that the service provider alone manages.
+const READ_ONLY_COMMON_ATTRIBUTES: &[&str] = &["id", "meta"];
+
+// Attributes that `diff_users` never emits operations for: they are either server-managed (`groups` is readOnly,
+// and so rejected by `apply_patch`) or write-only.
+const DIFF_SKIPPED_ATTRIBUTES: &[&str] = &["schemas", "id", "meta", "groups", "password"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operation {
+    Add,
+    Remove,
+    Replace,
+}
+
+impl Operation {
+    fn parse(op: &str) -> Result<Self, SCIMError> {
+        // Some providers (notably Azure AD) send capitalised op values, so match case-insensitively.
+        match op.to_ascii_lowercase().as_str() {
+            "add" => Ok(Operation::Add),
+            "remove" => Ok(Operation::Remove),
+            "replace" => Ok(Operation::Replace),
+            _ => Err(SCIMError::InvalidFieldValue(format!("op: unsupported operation {}", op))),
+        }
+    }
+}
+
+/// A parsed PATCH `path` of the form `[urn:...:]attribute[[valueFilter]][.subAttribute]`: an attribute path, as
+/// in filters, optionally selecting elements of a multi-valued attribute.
+#[derive(Debug, PartialEq)]
+struct PatchPath {
+    attr: AttrPath,
+    filter: Option<FilterExpr>,
+}
+
+fn parse_path(path: &str, root: &Map<String, Value>) -> Result<PatchPath, SCIMError> {
+    let path = path.trim();
+    let invalid = || SCIMError::InvalidPath(path.to_string());
+
+    // A bare extension URN addresses the whole extension object rather than an attribute inside it.
+    let is_extension = path.eq_ignore_ascii_case(schema_uri::ENTERPRISE_USER)
+        || root.keys().any(|key| key.starts_with("urn:") && key.eq_ignore_ascii_case(path));
+    if is_extension {
+        let attr = AttrPath { urn: None, attribute: path.to_string(), sub_attribute: None };
+        return Ok(PatchPath { attr, filter: None });
+    }
+
+    // Split off a value filter first, since it may itself contain `:` or `.` (e.g. `emails[value ew ".org"]`).
+    let (head, filter, tail) = match path.find('[') {
+        Some(open) => {
+            let close = path.rfind(']').filter(|&close| close > open).ok_or_else(invalid)?;
+            let filter = parse_filter(&path[open + 1..close]).map_err(|_| invalid())?;
+            (&path[..open], Some(filter), &path[close + 1..])
+        }
+        None => (path, None, ""),
+    };
+
+    // The filter sits between the attribute and the sub-attribute, so `emails[...].value` is `emails.value`
+    // filtered; anything else after the filter is malformed.
+    if filter.is_some() && !(tail.is_empty() || tail.starts_with('.')) {
+        return Err(invalid());
+    }
+    let attr = AttrPath::parse(&format!("{}{}", head, tail)).map_err(|_| invalid())?;
+    if filter.is_some() && attr.sub_attribute.is_some() && tail.is_empty() {
+        return Err(invalid());
+    }
+    Ok(PatchPath { attr, filter })
+}
+
+fn canonical_name(container: &Map<String, Value>, name: &str) -> String {
+    if let Some(key) = container.keys().find(|key| key.eq_ignore_ascii_case(name)) {
+        return key.clone();
+    }
+    ATTRIBUTE_NAMES
+        .iter()
+        .find(|known| known.eq_ignore_ascii_case(name))
+        .map_or_else(|| name.to_string(), |known| known.to_string())
+}
+
+fn is_multi_valued(name: &str) -> bool {
+    MULTI_VALUED_ATTRIBUTES.iter().any(|known| known.eq_ignore_ascii_case(name))
+}
+
+/// Adds `value` to `container[key]` following RFC 7644 §3.5.2.1: multi-valued attributes are appended to
+/// (skipping values that are already present), complex attributes are merged, and anything else is set.
+fn add_value(container: &mut Map<String, Value>, key: &str, value: Value) {
+    let keyed_by_value = VALUE_KEYED_ATTRIBUTES.iter().any(|known| known.eq_ignore_ascii_case(key));
+    match container.get_mut(key) {
+        Some(Value::Array(existing)) => {
+            let items = match value {
+                Value::Array(items) => items,
+                other => vec![other],
+            };
+            for item in items {
+                let same_value = |element: &Value| item.get("value").map_or(false, |v| element.get("value") == Some(v));
+                let duplicate =
+                    existing.iter().any(|element| element == &item || (keyed_by_value && same_value(element)));
+                if !duplicate {
+                    existing.push(item);
+                }
+            }
+        }
+        Some(Value::Object(existing)) if value.is_object() => {
+            if let Value::Object(fields) = value {
+                for (name, field) in fields {
+                    let name = canonical_name(existing, &name);
+                    add_value(existing, &name, field);
+                }
+            }
+        }
+        _ => {
+            let value = match value {
+                Value::Array(_) => value,
+                other if is_multi_valued(key) => Value::Array(vec![other]),
+                other => other,
+            };
+            container.insert(key.to_string(), value);
+        }
+    }
+}
+
+fn set_value(container: &mut Map<String, Value>, key: &str, operation: Operation, value: Value) {
+    match operation {
+        Operation::Add => add_value(container, key, value),
+        Operation::Replace => {
+            let value = match value {
+                Value::Array(_) => value,
+                other if is_multi_valued(key) => Value::Array(vec![other]),
+                other => other,
+            };
+            container.insert(key.to_string(), value);
+        }
+        Operation::Remove => {
+            container.remove(key);
+        }
+    }
+}
+
+/// Finds the object that holds the attribute named by `path`: the resource itself for core attributes,
+/// or the extension object for URN-qualified paths. Missing extension objects are created unless removing.
+fn resolve_container<'a>(
+    root: &'a mut Map<String, Value>,
+    path: &PatchPath,
+    operation: Operation,
+) -> Option<&'a mut Map<String, Value>> {
+    let urn = match &path.attr.urn {
+        Some(urn) if !urn.eq_ignore_ascii_case(schema_uri::USER) => urn,
+        _ => return Some(root),
+    };
+    let key = canonical_name(root, urn);
+    if operation == Operation::Remove && !root.contains_key(&key) {
+        return None;
+    }
+    let extension = root.entry(key).or_insert_with(|| Value::Object(Map::new()));
+    if extension.is_null() {
+        *extension = Value::Object(Map::new());
+    }
+    extension.as_object_mut()
+}
+
+/// Applies an operation to the elements of the multi-valued attribute `container[attribute]` that match `filter`,
+/// or to their `sub_attribute` when one is given.
+///
+/// Removing whole elements drops them from the attribute (and the attribute itself once it is empty). Replacing
+/// whole elements overwrites each match with `value`. An `add` must name a sub-attribute; when no element
+/// matches, a new one is appended, built from the filter's `eq` comparisons plus the sub-attribute (so
+/// `emails[type eq "work"].value` adds `{"type": "work", "value": ...}`). A `replace` that matches nothing fails,
+/// while a `remove` that matches nothing succeeds without changes.
+fn apply_with_filter(
+    container: &mut Map<String, Value>,
+    attribute: &str,
+    filter: &FilterExpr,
+    sub_attribute: Option<&str>,
+    operation: Operation,
+    value: Value,
+    raw_path: &str,
+) -> Result<(), SCIMError> {
+    let no_target = || SCIMError::InvalidPath(format!("{}: no target", raw_path));
+    if operation == Operation::Add && sub_attribute.is_some() && !container.contains_key(attribute) {
+        container.insert(attribute.to_string(), Value::Array(Vec::new()));
+    }
+    let elements = match container.get_mut(attribute) {
+        Some(Value::Array(elements)) => elements,
+        _ if operation == Operation::Remove => return Ok(()),
+        _ => return Err(no_target()),
+    };
+
+    match (sub_attribute, operation) {
+        (None, Operation::Remove) => {
+            elements.retain(|element| !filter.matches_json(element));
+            if elements.is_empty() {
+                container.remove(attribute);
+            }
+        }
+        (None, Operation::Replace) => {
+            let mut matched = false;
+            for element in elements.iter_mut().filter(|element| filter.matches_json(element)) {
+                *element = value.clone();
+                matched = true;
+            }
+            if !matched {
+                return Err(no_target());
+            }
+        }
+        (None, Operation::Add) => {
+            return Err(SCIMError::InvalidPath(format!("{}: add requires a sub-attribute after a filter", raw_path)));
+        }
+        (Some(sub_attribute), _) => {
+            let mut matched = false;
+            for element in elements.iter_mut().filter(|element| filter.matches_json(element)) {
+                if let Value::Object(element) = element {
+                    let key = canonical_name(element, sub_attribute);
+                    set_value(element, &key, operation, value.clone());
+                    matched = true;
+                }
+            }
+            if !matched {
+                match operation {
+                    Operation::Remove => {}
+                    Operation::Replace => return Err(no_target()),
+                    Operation::Add => {
+                        let mut element = element_from_filter(filter).ok_or_else(|| {
+                            SCIMError::InvalidPath(format!("{}: no match, and the filter does not describe a new element", raw_path))
+                        })?;
+                        element.insert(sub_attribute.to_string(), value);
+                        elements.push(Value::Object(element));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Builds the element a filter made only of `eq` comparisons joined by `and` describes, e.g. `{"type": "work"}`
+/// for `type eq "work"`. Returns `None` for any other filter.
+fn element_from_filter(filter: &FilterExpr) -> Option<Map<String, Value>> {
+    match filter {
+        FilterExpr::Compare(path, CompareOp::Eq, value) if path.urn.is_none() && path.sub_attribute.is_none() => {
+            Some(Map::from_iter([(path.attribute.clone(), value.clone())]))
+        }
+        FilterExpr::And(left, right) => {
+            let mut element = element_from_filter(left)?;
+            element.extend(element_from_filter(right)?);
+            Some(element)
+        }
+        _ => None,
+    }
+}
+
+fn apply_with_path(
+    root: &mut Map<String, Value>,
+    raw_path: &str,
+    operation: Operation,
+    value: Option<&Value>,
+) -> Result<(), SCIMError> {
+    let path = parse_path(raw_path, root)?;
+    let value = match (operation, value) {
+        (Operation::Remove, _) => Value::Null,
+        (_, Some(value)) => value.clone(),
+        (_, None) => return Err(SCIMError::MissingRequiredField("value".to_string())),
+    };
+
+    let container = match resolve_container(root, &path, operation) {
+        Some(container) => container,
+        None => return Ok(()),
+    };
+    let attribute = canonical_name(container, &path.attr.attribute);
+
+    if let Some(filter) = &path.filter {
+        let sub_attribute = path.attr.sub_attribute.as_deref();
+        return apply_with_filter(container, &attribute, filter, sub_attribute, operation, value, raw_path);
+    }
+
+    let sub_attribute = match &path.attr.sub_attribute {
+        Some(sub_attribute) => sub_attribute,
+        None => {
+            set_value(container, &attribute, operation, value);
+            return Ok(());
+        }
+    };
+
+    if operation != Operation::Remove && !container.contains_key(&attribute) {
+        if is_multi_valued(&attribute) {
+            return Err(SCIMError::InvalidPath(format!("{}: no target for sub-attribute", raw_path)));
+        }
+        container.insert(attribute.clone(), Value::Object(Map::new()));
+    }
+    match container.get_mut(&attribute) {
+        Some(Value::Array(elements)) => {
+            for element in elements.iter_mut().filter_map(Value::as_object_mut) {
+                let key = canonical_name(element, sub_attribute);
+                set_value(element, &key, operation, value.clone());
+            }
+        }
+        Some(Value::Object(element)) => {
+            let key = canonical_name(element, sub_attribute);
+            set_value(element, &key, operation, value);
+        }
+        Some(_) if operation != Operation::Remove => {
+            return Err(SCIMError::InvalidPath(format!("{}: {} is not a complex attribute", raw_path, attribute)));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Checks that the attribute at `path` may be written by a client, according to its `mutability` in `schema`.
+///
+/// `path` is a PATCH path such as `displayName`, `name.givenName` or `emails[type eq "work"].value`. The common
+/// attributes `id` and `meta` are always read-only. A path qualified with a URN other than `schema`'s id, or naming
+/// an attribute the schema does not define, is not this schema's to judge and is allowed.
+///
+/// # Errors
+///
+/// * `SCIMError::InvalidValue` - If the attribute, or the sub-attribute the path names, is `readOnly`.
+/// * `SCIMError::InvalidPath` - If `path` cannot be parsed.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::scim_schema::core_user_schema;
+/// use scim_v2::utils::patch::check_mutability;
+///
+/// let schema = core_user_schema();
+/// assert!(check_mutability("displayName", &schema).is_ok());
+/// assert!(check_mutability("groups", &schema).is_err());
+/// ```
+pub fn check_mutability(path: &str, schema: &Schema) -> Result<(), SCIMError> {
+    let parsed = parse_path(path, &Map::new())?.attr;
+    let read_only = || Err(SCIMError::InvalidValue(format!("{} is readOnly", path)));
+
+    if parsed.urn.as_deref().map_or(false, |urn| !urn.eq_ignore_ascii_case(&schema.id)) {
+        return Ok(());
+    }
+    if READ_ONLY_COMMON_ATTRIBUTES.iter().any(|name| name.eq_ignore_ascii_case(&parsed.attribute)) {
+        return read_only();
+    }
+    let attribute = match schema.attributes.iter().find(|attribute| attribute.name.eq_ignore_ascii_case(&parsed.attribute)) {
+        Some(attribute) => attribute,
+        None => return Ok(()),
+    };
+    if attribute.mutability == Some(Mutability::ReadOnly) {
+        return read_only();
+    }
+    let sub_attribute = parsed.sub_attribute.as_ref().and_then(|name| {
+        attribute.sub_attributes.iter().flatten().find(|sub_attribute| sub_attribute.name.eq_ignore_ascii_case(name))
+    });
+    if sub_attribute.map_or(false, |sub_attribute| sub_attribute.mutability == Some(Mutability::ReadOnly)) {
+        return read_only();
+    }
+    Ok(())
+}
+
+/// Checks `path` against the schema it addresses: the schema whose id qualifies it, or the core schema (first).
+fn check_path_mutability(path: &str, schemas: &[Schema]) -> Result<(), SCIMError> {
+    let lowercase = path.to_ascii_lowercase();
+    let schema = schemas
+        .iter()
+        .skip(1)
+        .find(|schema| lowercase.starts_with(&format!("{}:", schema.id.to_ascii_lowercase())))
+        .unwrap_or(&schemas[0]);
+    check_mutability(path, schema)
+}
+
+fn apply_operation(root: &mut Map<String, Value>, operation: &PatchOperations, schemas: &[Schema]) -> Result<(), SCIMError> {
+    let kind = Operation::parse(&operation.op)?;
+    if let Some(path) = &operation.path {
+        check_path_mutability(path, schemas)?;
+        return apply_with_path(root, path, kind, operation.value.as_ref());
+    }
+
+    if kind == Operation::Remove {
+        return Err(SCIMError::InvalidPath("remove operation requires a path".to_string()));
+    }
+    let fields = match &operation.value {
+        Some(Value::Object(fields)) => fields,
+        _ => return Err(SCIMError::InvalidFieldValue("value: expected an object when path is omitted".to_string())),
+    };
+    for name in fields.keys().filter(|name| !name.starts_with("urn:")) {
+        check_path_mutability(name, schemas)?;
+    }
+    for (name, field) in fields {
+        let key = canonical_name(root, name);
+        set_value(root, &key, kind, field.clone());
+    }
+    Ok(())
+}
+
+/// Applies a SCIM PATCH request (RFC 7644 §3.5.2) to a `User` in place.
+///
+/// Each operation is applied in order to the JSON representation of the user, which is then deserialized
+/// back into the `User`. Attribute names in paths are matched case-insensitively, and extension attributes
+/// can be addressed with their fully-qualified URN (e.g. `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`).
+///
+/// * `add` appends to multi-valued attributes, merges into complex attributes and sets everything else.
+/// * `replace` overwrites the attribute at `path`. A sub-attribute path such as `name.givenName` only overwrites
+///   that sub-attribute, creating the complex attribute if it is absent, while `name` replaces it as a whole.
+/// * `remove` clears the attribute at `path`.
+///
+/// Operations that would write a `readOnly` attribute (see `check_mutability`), such as `meta.created` or
+/// `groups`, are rejected.
+///
+/// A `path` may select elements of a multi-valued attribute with a value filter, e.g. `roles[value eq "admin"]`
+/// or `emails[type eq "work"].value`. `add` to `roles` or `entitlements` skips elements whose `value` is
+/// already present.
+///
+/// When an operation omits `path`, its `value` must be an object whose members are applied to the user as if
+/// each had been targeted individually.
+///
+/// The user is only modified if every operation succeeds.
+///
+/// # Errors
+///
+/// * `SCIMError::InvalidPath` - If a `path` cannot be parsed, or a `remove` has no `path`.
+/// * `SCIMError::InvalidFieldValue` - If an `op` is not one of add/remove/replace, or a path-less `value` is not an object.
+/// * `SCIMError::MissingRequiredField` - If an `add` or `replace` has no `value`.
+/// * `SCIMError::InvalidValue` - If an operation targets a `readOnly` attribute.
+/// * `SCIMError::DeserializationError` - If the patched document is no longer a valid `User`.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::others::PatchOp;
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::patch::apply_patch;
+///
+/// let mut user = User {
+///     user_name: "bjensen@example.com".to_string(),
+///     ..Default::default()
+/// };
+/// let patch: PatchOp = serde_json::from_str(r#"{
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+///     "Operations": [{"op": "replace", "path": "displayName", "value": "Babs Jensen"}]
+/// }"#).unwrap();
+///
+/// match apply_patch(&mut user, &patch) {
+///     Ok(_) => println!("Patched User: {:?}", user),
+///     Err(e) => println!("Patch error: {}", e),
+/// }
+/// ```
+pub fn apply_patch(user: &mut User, patch: &PatchOp) -> Result<(), SCIMError> {
+    let mut document = serde_json::to_value(&*user).map_err(SCIMError::SerializationError)?;
+    let root = document.as_object_mut().ok_or(SCIMError::InvalidJsonFormat)?;
+    let schemas = get_schemas(vec!["user", "enterprise_user"])?;
+    for operation in &patch.operations {
+        apply_operation(root, operation, &schemas)?;
+    }
+    *user = from_json_value(document)?;
+    Ok(())
+}
+
+fn diff_operation(op: &str, path: &str, value: Option<Value>) -> PatchOperations {
+    PatchOperations { op: op.to_string(), path: Some(path.to_string()), value }
+}
+
+/// Computes the PATCH request that turns `current` into `desired`.
+///
+/// Attributes are compared on the users' JSON representations, and an operation is emitted only for
+/// attributes that differ, so equal users produce a `PatchOp` with no operations:
+///
+/// * An attribute that is absent from `desired` is removed.
+/// * A multi-valued attribute that only gained values gets an `add` of the new values. If any value was
+///   dropped, the whole attribute is replaced instead, since a value filter would be needed to remove it.
+/// * Any other change, including to a complex attribute or an extension, is a `replace`.
+///
+/// `schemas`, `id`, `meta`, `groups` and `password` are never compared: the server manages them, or, for
+/// `password`, never returns it. Group memberships change through the groups' `members` instead.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::patch::diff_users;
+///
+/// let current = User { user_name: "bjensen".to_string(), ..Default::default() };
+/// let desired = User {
+///     user_name: "bjensen".to_string(),
+///     display_name: Some("Babs Jensen".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let patch = diff_users(&current, &desired);
+/// assert_eq!(patch.operations.len(), 1);
+/// assert_eq!(patch.operations[0].op, "replace");
+/// ```
+pub fn diff_users(current: &User, desired: &User) -> PatchOp {
+    let (current, desired) = (Value::from(current), Value::from(desired));
+    let empty = Map::new();
+    let current = current.as_object().unwrap_or(&empty);
+    let desired = desired.as_object().unwrap_or(&empty);
+
+    let mut keys: Vec<&String> = current.keys().chain(desired.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut operations = Vec::new();
+    for key in keys.into_iter().filter(|key| !DIFF_SKIPPED_ATTRIBUTES.contains(&key.as_str())) {
+        match (current.get(key), desired.get(key)) {
+            (Some(_), None) => operations.push(diff_operation("remove", key, None)),
+            (None, Some(Value::Array(items))) => {
+                operations.push(diff_operation("add", key, Some(Value::Array(items.clone()))))
+            }
+            (Some(Value::Array(before)), Some(Value::Array(after))) if before != after => {
+                if before.iter().all(|item| after.contains(item)) {
+                    let added = after.iter().filter(|item| !before.contains(item)).cloned().collect();
+                    operations.push(diff_operation("add", key, Some(Value::Array(added))));
+                } else {
+                    operations.push(diff_operation("replace", key, Some(Value::Array(after.clone()))));
+                }
+            }
+            (before, Some(after)) if before != Some(after) => {
+                operations.push(diff_operation("replace", key, Some(after.clone())))
+            }
+            _ => {}
+        }
+    }
+
+    PatchOp {
+        schemas: vec![schema_uri::PATCH_OP.to_string()],
+        operations,
+    }
+}
+
+/// Converts a JSON Pointer (RFC 6901) into a SCIM attribute path, e.g. `/name/givenName` into `name.givenName`.
+///
+/// Returns `None` for the empty pointer, which refers to the whole resource. The boolean is `true` when the pointer
+/// ends in `-`, the JSON Patch way of appending to an array.
+fn scim_path_from_pointer(pointer: &str) -> Result<(Option<String>, bool), SCIMError> {
+    if pointer.is_empty() {
+        return Ok((None, false));
+    }
+    let mut segments: Vec<String> = pointer
+        .strip_prefix('/')
+        .ok_or_else(|| SCIMError::InvalidPath(format!("{}: JSON Pointer must start with /", pointer)))?
+        .split('/')
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    let append = segments.len() > 1 && segments.last().map_or(false, |segment| segment == "-");
+    if append {
+        segments.pop();
+    }
+    if segments.iter().any(|segment| segment == "-" || (!segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()))) {
+        return Err(SCIMError::InvalidPath(format!("{}: array indices cannot be expressed as a SCIM path", pointer)));
+    }
+
+    // `/urn:...:User/employeeNumber` addresses an extension attribute, written `urn:...:User:employeeNumber`.
+    let urn = if segments[0].starts_with("urn:") && segments.len() > 1 { Some(segments.remove(0)) } else { None };
+    if segments.len() > 2 || segments.iter().any(String::is_empty) {
+        return Err(SCIMError::InvalidPath(format!("{}: not expressible as a SCIM path", pointer)));
+    }
+    let attribute_path = segments.join(".");
+    let path = match urn {
+        Some(urn) => format!("{}:{}", urn, attribute_path),
+        None => attribute_path,
+    };
+    Ok((Some(path), append))
+}
+
+/// Translates JSON Patch (RFC 6902) operations into an equivalent SCIM `PatchOp`.
+///
+/// `add`, `remove` and `replace` are supported. Pointers become SCIM paths: `/name/givenName` becomes
+/// `name.givenName`, `/urn:ietf:params:scim:schemas:extension:enterprise:2.0:User/department` becomes
+/// `urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`, and the empty pointer becomes an
+/// operation without a path. An `add` to `/emails/-` appends, so it becomes an `add` of `[value]` to `emails`.
+///
+/// # Errors
+///
+/// * `SCIMError::InvalidValue` - If an operation is not an object with string `op` and `path` members, or uses
+///   `move`, `copy`, `test` or an unknown op, none of which SCIM PATCH can express.
+/// * `SCIMError::InvalidPath` - If a pointer indexes into an array (`/emails/0`), which would need a value
+///   filter that cannot be derived from the index, or nests deeper than a sub-attribute.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::utils::patch::scim_patch_from_json_patch;
+///
+/// let ops = vec![serde_json::json!({"op": "replace", "path": "/name/givenName", "value": "Barbara"})];
+///
+/// match scim_patch_from_json_patch(&ops) {
+///     Ok(patch) => println!("SCIM PatchOp: {:?}", patch),
+///     Err(e) => println!("Cannot convert JSON Patch: {}", e),
+/// }
+/// ```
+pub fn scim_patch_from_json_patch(ops: &[Value]) -> Result<PatchOp, SCIMError> {
+    let mut operations = Vec::with_capacity(ops.len());
+    for operation in ops {
+        let member = |name: &str| {
+            operation
+                .get(name)
+                .and_then(Value::as_str)
+                .ok_or_else(|| SCIMError::InvalidValue(format!("JSON Patch operation without a string {}: {}", name, operation)))
+        };
+        let op = member("op")?;
+        if !["add", "remove", "replace"].contains(&op) {
+            return Err(SCIMError::InvalidValue(format!("{}: unsupported JSON Patch operation", op)));
+        }
+        let (path, append) = scim_path_from_pointer(member("path")?)?;
+        let value = operation.get("value").cloned();
+        let value = match (append, value) {
+            (true, _) if op != "add" => {
+                return Err(SCIMError::InvalidPath(format!("{}: only add can append to an array", member("path")?)))
+            }
+            (true, Some(value)) => Some(Value::Array(vec![value])),
+            (_, value) => value,
+        };
+        operations.push(PatchOperations { op: op.to_string(), path, value });
+    }
+
+    Ok(PatchOp {
+        schemas: vec![schema_uri::PATCH_OP.to_string()],
+        operations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::models::user::{Email, Entitlement, Group, Name, Role};
+
+    fn patch_from(json: &str) -> PatchOp {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn user_with_work_email() -> User {
+        User {
+            user_name: "bjensen@example.com".to_string(),
+            display_name: Some("Babs".to_string()),
+            emails: Some(vec![Email {
+                value: Some("bjensen@example.com".to_string()),
+                type_: Some("work".to_string()),
+                ..Default::default()
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn patch_op_deserializes_rfc_example() {
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "add",
+                "path": "members",
+                "value": [{"display": "Babs Jensen", "$ref": "https://example.com/v2/Users/2819c223", "value": "2819c223"}]
+            }]
+        }"#);
+        assert_eq!(patch.operations.len(), 1);
+        assert_eq!(patch.operations[0].op, "add");
+        assert_eq!(patch.operations[0].path, Some("members".to_string()));
+        assert!(patch.operations[0].value.as_ref().unwrap().is_array());
+    }
+
+    #[test]
+    fn apply_patch_replace_overwrites_display_name() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "replace", "path": "displayName", "value": "Barbara Jensen"}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+        assert_eq!(user.display_name, Some("Barbara Jensen".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_add_appends_to_emails() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "Add", "path": "emails", "value": [{"value": "babs@jensen.org", "type": "home"}]}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+        let emails = user.emails.unwrap();
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[0].value, Some("bjensen@example.com".to_string()));
+        assert_eq!(emails[1].value, Some("babs@jensen.org".to_string()));
+        assert_eq!(emails[1].type_, Some("home".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_remove_clears_field() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "remove", "path": "emails"}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+        assert!(user.emails.is_none());
+        assert_eq!(user.display_name, Some("Babs".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_without_path_merges_value_object() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "add",
+                "value": {
+                    "nickName": "Babs",
+                    "emails": [{"value": "babs@jensen.org", "type": "home"}],
+                    "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"}
+                }
+            }]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+        assert_eq!(user.nick_name, Some("Babs".to_string()));
+        assert_eq!(user.emails.unwrap().len(), 2);
+        assert_eq!(user.enterprise_user.unwrap().department, Some("Tour Operations".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_replace_extension_attribute_by_urn_path() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "replace",
+                "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:costCenter",
+                "value": "4130"
+            }]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+        assert_eq!(user.enterprise_user.unwrap().cost_center, Some("4130".to_string()));
+    }
+
+    #[test]
+    fn apply_patch_returns_invalid_path_for_unparseable_path() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "replace", "path": "display name!", "value": "Barbara"}]
+        }"#);
+
+        let result = apply_patch(&mut user, &patch);
+        assert!(matches!(result, Err(SCIMError::InvalidPath(_))));
+        assert_eq!(user.display_name, Some("Babs".to_string()));
+    }
+
+    #[test]
+    fn parse_path_agrees_with_filter_attribute_paths() {
+        let root = Map::new();
+        for path in ["name.givenName", "URN:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager.value", "emails"] {
+            assert_eq!(parse_path(path, &root).unwrap().attr, AttrPath::parse(path).unwrap(), "{}", path);
+        }
+
+        let filtered = parse_path(r#"emails[type eq "work"].value"#, &root).unwrap();
+        assert_eq!(filtered.attr, AttrPath::parse("emails.value").unwrap());
+        assert!(filtered.filter.is_some());
+
+        for path in [r#"emails.type[value pr]"#, r#"emails[value pr]value"#, "abcé.value", "é", "urné:x"] {
+            assert!(matches!(parse_path(path, &root), Err(SCIMError::InvalidPath(_))), "{}", path);
+        }
+    }
+
+    #[test]
+    fn apply_patch_remove_without_path_is_rejected() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "remove"}]
+        }"#);
+
+        assert!(matches!(apply_patch(&mut user, &patch), Err(SCIMError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn diff_users_of_identical_users_is_empty() {
+        let user = user_with_work_email();
+
+        assert!(diff_users(&user, &user.clone()).operations.is_empty());
+    }
+
+    #[test]
+    fn diff_users_replaces_changed_display_name() {
+        let current = user_with_work_email();
+        let mut desired = current.clone();
+        desired.display_name = Some("Barbara".to_string());
+        desired.id = Some("2819c223-7f76-453a-919d-413861904646".to_string());
+        desired.password = Some("t1meMa$heen".to_string());
+
+        let patch = diff_users(&current, &desired);
+
+        assert_eq!(patch.operations.len(), 1);
+        assert_eq!(patch.operations[0].op, "replace");
+        assert_eq!(patch.operations[0].path.as_deref(), Some("displayName"));
+        assert_eq!(patch.operations[0].value, Some(Value::String("Barbara".to_string())));
+    }
+
+    #[test]
+    fn diff_users_adds_new_email() {
+        let current = user_with_work_email();
+        let mut desired = current.clone();
+        desired.emails.as_mut().unwrap().push(Email {
+            value: Some("babs@jensen.org".to_string()),
+            type_: Some("home".to_string()),
+            ..Default::default()
+        });
+
+        let patch = diff_users(&current, &desired);
+
+        assert_eq!(patch.operations.len(), 1);
+        assert_eq!(patch.operations[0].op, "add");
+        assert_eq!(patch.operations[0].path.as_deref(), Some("emails"));
+        assert_eq!(patch.operations[0].value, Some(serde_json::json!([{"value": "babs@jensen.org", "type": "home"}])));
+
+        let mut patched = current.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, desired);
+    }
+
+    #[test]
+    fn diff_users_removes_cleared_attributes() {
+        let current = user_with_work_email();
+        let mut desired = current.clone();
+        desired.display_name = None;
+        desired.emails = None;
+
+        let patch = diff_users(&current, &desired);
+
+        let ops: Vec<(&str, Option<&str>)> =
+            patch.operations.iter().map(|operation| (operation.op.as_str(), operation.path.as_deref())).collect();
+        assert_eq!(ops, vec![("remove", Some("displayName")), ("remove", Some("emails"))]);
+
+        let mut patched = current.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, desired);
+    }
+
+    #[test]
+    fn diff_users_skips_read_only_groups() {
+        let group = |value: &str| Group { value: Some(value.to_string()), ..Default::default() };
+        let current = User { groups: Some(vec![group("e9e30dba")]), ..user_with_work_email() };
+        let desired = User { groups: Some(vec![group("fc348aa8")]), display_name: Some("Babs Jensen".to_string()), ..current.clone() };
+
+        let patch = diff_users(&current, &desired);
+
+        let paths: Vec<Option<&str>> = patch.operations.iter().map(|operation| operation.path.as_deref()).collect();
+        assert_eq!(paths, vec![Some("displayName")]);
+        let mut patched = current.clone();
+        apply_patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched, User { groups: current.groups.clone(), ..desired });
+    }
+
+    fn user_with_roles() -> User {
+        User {
+            user_name: "bjensen@example.com".to_string(),
+            roles: Some(vec![
+                Role { value: Some("admin".to_string()), display: Some("Administrator".to_string()), ..Default::default() },
+                Role { value: Some("auditor".to_string()), ..Default::default() },
+            ]),
+            ..Default::default()
+        }
+    }
+
+    fn role_values(user: &User) -> Vec<&str> {
+        user.roles.iter().flatten().filter_map(|role| role.value.as_deref()).collect()
+    }
+
+    #[test]
+    fn apply_patch_add_existing_role_is_a_no_op() {
+        let mut user = user_with_roles();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "add", "path": "roles", "value": [{"value": "admin"}, {"value": "auditor", "primary": false}]}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+
+        assert_eq!(user, user_with_roles());
+    }
+
+    #[test]
+    fn apply_patch_add_new_entitlement_appends() {
+        let mut user = User {
+            user_name: "bjensen@example.com".to_string(),
+            entitlements: Some(vec![Entitlement { value: Some("vpn".to_string()), ..Default::default() }]),
+            ..Default::default()
+        };
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "add", "path": "entitlements", "value": [{"value": "vpn"}, {"value": "wiki"}]}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+
+        let values: Vec<&str> = user.entitlements.iter().flatten().filter_map(|e| e.value.as_deref()).collect();
+        assert_eq!(values, vec!["vpn", "wiki"]);
+    }
+
+    #[test]
+    fn apply_patch_remove_by_value_filter_drops_matching_role() {
+        let mut user = user_with_roles();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "remove", "path": "roles[value eq \"admin\"]"}]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+
+        assert_eq!(role_values(&user), vec!["auditor"]);
+    }
+
+    #[test]
+    fn apply_patch_replaces_sub_attribute_of_filtered_elements() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {"op": "replace", "path": "emails[type eq \"work\"].value", "value": "babs@example.org"},
+                {"op": "remove", "path": "emails[type eq \"home\"]"}
+            ]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+
+        assert_eq!(user.emails.unwrap()[0].value.as_deref(), Some("babs@example.org"));
+    }
+
+    #[test]
+    fn apply_patch_rejects_read_only_attributes() {
+        let mut user = user_with_work_email();
+        for operation in [
+            r#"{"op": "replace", "path": "meta.created", "value": "2030-01-01T00:00:00Z"}"#,
+            r#"{"op": "add", "path": "groups", "value": [{"value": "e9e30dba"}]}"#,
+            r#"{"op": "replace", "value": {"id": "spoofed"}}"#,
+            r#"{"op": "replace", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager.displayName", "value": "John Smith"}"#,
+        ] {
+            let patch = patch_from(&format!(
+                r#"{{"schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"], "Operations": [{}]}}"#,
+                operation
+            ));
+            assert!(matches!(apply_patch(&mut user, &patch), Err(SCIMError::InvalidValue(_))), "{}", operation);
+        }
+        assert_eq!(user, user_with_work_email());
+    }
+
+    #[test]
+    fn check_mutability_allows_read_write_and_rejects_read_only() {
+        let schema = crate::models::scim_schema::core_user_schema();
+
+        assert!(check_mutability("displayName", &schema).is_ok());
+        assert!(check_mutability("emails[type eq \"work\"].value", &schema).is_ok());
+        match check_mutability("meta.created", &schema) {
+            Err(SCIMError::InvalidValue(message)) => assert_eq!(message, "meta.created is readOnly"),
+            other => panic!("expected InvalidValue, got {:?}", other),
+        }
+        assert!(check_mutability("groups.display", &schema).is_err());
+        assert!(check_mutability("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager", &schema).is_ok());
+    }
+
+    #[test]
+    fn scim_patch_from_json_patch_converts_replace_and_remove() {
+        let ops = vec![
+            serde_json::json!({"op": "replace", "path": "/name/givenName", "value": "Barbara"}),
+            serde_json::json!({"op": "remove", "path": "/urn:ietf:params:scim:schemas:extension:enterprise:2.0:User/department"}),
+            serde_json::json!({"op": "add", "path": "/emails/-", "value": {"value": "babs@jensen.org"}}),
+            serde_json::json!({"op": "replace", "path": "", "value": {"displayName": "Babs"}}),
+        ];
+
+        let patch = scim_patch_from_json_patch(&ops).unwrap();
+
+        assert_eq!(serde_json::to_value(&patch).unwrap(), serde_json::json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {"op": "replace", "path": "name.givenName", "value": "Barbara"},
+                {"op": "remove", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department"},
+                {"op": "add", "path": "emails", "value": [{"value": "babs@jensen.org"}]},
+                {"op": "replace", "value": {"displayName": "Babs"}}
+            ]
+        }));
+
+        let mut user = user_with_work_email();
+        apply_patch(&mut user, &patch).unwrap();
+        assert_eq!(user.name.unwrap().given_name.as_deref(), Some("Barbara"));
+        assert_eq!(user.emails.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn scim_patch_from_json_patch_rejects_unsupported_operations() {
+        let move_op = serde_json::json!({"op": "move", "from": "/nickName", "path": "/displayName"});
+        match scim_patch_from_json_patch(&[move_op]) {
+            Err(SCIMError::InvalidValue(message)) => assert_eq!(message, "move: unsupported JSON Patch operation"),
+            other => panic!("expected InvalidValue, got {:?}", other),
+        }
+
+        let indexed = serde_json::json!({"op": "remove", "path": "/emails/0"});
+        assert!(matches!(scim_patch_from_json_patch(&[indexed]), Err(SCIMError::InvalidPath(_))));
+        let no_path = serde_json::json!({"op": "remove"});
+        assert!(matches!(scim_patch_from_json_patch(&[no_path]), Err(SCIMError::InvalidValue(_))));
+    }
+
+    #[test]
+    fn apply_patch_add_to_filtered_sub_attribute_creates_missing_element() {
+        let mut user = user_with_work_email();
+        let patch = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [
+                {"op": "add", "path": "emails[type eq \"home\" and primary eq false].value", "value": "babs@jensen.org"},
+                {"op": "add", "path": "phoneNumbers[type eq \"mobile\"].value", "value": "+15555555555"}
+            ]
+        }"#);
+
+        apply_patch(&mut user, &patch).unwrap();
+
+        let emails = user.emails.unwrap();
+        assert_eq!(emails.len(), 2);
+        assert_eq!(emails[1], Email {
+            value: Some("babs@jensen.org".to_string()),
+            type_: Some("home".to_string()),
+            primary: Some(false),
+            ..Default::default()
+        });
+        assert_eq!(user.phone_numbers.unwrap()[0].type_.as_deref(), Some("mobile"));
+    }
+
+    #[test]
+    fn apply_patch_filtered_sub_attribute_without_match() {
+        let mut user = user_with_work_email();
+        let remove = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "remove", "path": "emails[type eq \"home\"].value"}]
+        }"#);
+        apply_patch(&mut user, &remove).unwrap();
+        assert_eq!(user, user_with_work_email());
+
+        let replace = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "replace", "path": "emails[type eq \"home\"].value", "value": "babs@jensen.org"}]
+        }"#);
+        assert!(matches!(apply_patch(&mut user, &replace), Err(SCIMError::InvalidPath(_))));
+
+        let add = patch_from(r#"{
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{"op": "add", "path": "emails[value co \"example.org\"].display", "value": "Babs"}]
+        }"#);
+        assert!(matches!(apply_patch(&mut user, &add), Err(SCIMError::InvalidPath(_))));
+        assert_eq!(user, user_with_work_email());
+    }
+
+    fn replace(path: &str, value: Value) -> PatchOp {
+        PatchOp {
+            schemas: vec!["urn:ietf:params:scim:api:messages:2.0:PatchOp".to_string()],
+            operations: vec![PatchOperations { op: "replace".to_string(), path: Some(path.to_string()), value: Some(value) }],
+        }
+    }
+
+    #[test]
+    fn apply_patch_replaces_single_name_sub_attribute() {
+        let mut user = User {
+            name: Some(Name { given_name: Some("Babs".to_string()), family_name: Some("Jensen".to_string()), ..Default::default() }),
+            ..user_with_work_email()
+        };
+
+        apply_patch(&mut user, &replace("name.givenName", serde_json::json!("Barbara"))).unwrap();
+
+        let name = user.name.as_ref().unwrap();
+        assert_eq!(name.given_name.as_deref(), Some("Barbara"));
+        assert_eq!(name.family_name.as_deref(), Some("Jensen"));
+
+        let mut without_name = user_with_work_email();
+        apply_patch(&mut without_name, &replace("NAME.givenName", serde_json::json!("Barbara"))).unwrap();
+        assert_eq!(without_name.name, Some(Name { given_name: Some("Barbara".to_string()), ..Default::default() }));
+    }
+
+    #[test]
+    fn apply_patch_replaces_whole_name() {
+        let mut user = User {
+            name: Some(Name { given_name: Some("Babs".to_string()), family_name: Some("Jensen".to_string()), ..Default::default() }),
+            ..user_with_work_email()
+        };
+
+        apply_patch(&mut user, &replace("name", serde_json::json!({"givenName": "Barbara", "formatted": "Ms. Barbara Jensen"}))).unwrap();
+
+        assert_eq!(
+            user.name,
+            Some(Name { given_name: Some("Barbara".to_string()), formatted: Some("Ms. Barbara Jensen".to_string()), ..Default::default() })
+        );
+    }
+}
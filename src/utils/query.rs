@@ -0,0 +1,869 @@
+use std::cmp::Ordering;
+
+use serde_json::{Map, Value};
+
+use crate::models::others::ListResponse;
+use crate::models::schema_uri;
+use crate::models::scim_schema::{get_schemas, Returned, Schema};
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+use crate::utils::filter::{parse_filter, AttrPath, FilterExpr};
+
+// Attributes common to every resource (RFC 7643 §3.1). The published User schema does not list them.
+const COMMON_ATTRIBUTES: &[&str] = &["schemas", "id", "externalId", "meta"];
+const META_SUB_ATTRIBUTES: &[&str] = &["resourceType", "created", "lastModified", "location", "version"];
+
+// Attributes that are returned regardless of `attributes` and `excludedAttributes`.
+const ALWAYS_RETURNED: &[&str] = &["schemas", "id", "meta"];
+
+/// An attribute referenced by a query parameter, using the names the `User` model serializes with.
+/// `extension` is the URN of the schema extension the attribute belongs to, if any, and `complex` is set when
+/// the selection refers to a whole complex attribute or extension rather than a single value.
+#[derive(Debug)]
+struct Selection {
+    extension: Option<String>,
+    attribute: String,
+    sub_attribute: Option<String>,
+    complex: bool,
+}
+
+/// Loads the schemas a `User` is described by, with the core User schema first.
+fn user_schemas() -> Result<Vec<Schema>, SCIMError> {
+    get_schemas(vec!["user", "enterprise_user"])
+}
+
+/// Resolves `path` against the User schemas, case-insensitively, returning the canonical attribute names.
+fn resolve_selection(path: &str, schemas: &[Schema]) -> Result<Selection, SCIMError> {
+    let invalid = || SCIMError::InvalidPath(path.to_string());
+
+    // A bare extension URN selects the whole extension object.
+    if let Some(extension) = schemas[1..].iter().find(|schema| schema.id.eq_ignore_ascii_case(path)) {
+        return Ok(Selection { extension: None, attribute: extension.id.clone(), sub_attribute: None, complex: true });
+    }
+
+    let parsed = AttrPath::parse(path)?;
+    let schema = match &parsed.urn {
+        Some(urn) => schemas.iter().find(|schema| schema.id.eq_ignore_ascii_case(urn)).ok_or_else(invalid)?,
+        None => &schemas[0],
+    };
+    let extension = if schema.id == schema_uri::USER { None } else { Some(schema.id.clone()) };
+
+    let (attribute, sub_attributes): (String, Vec<&str>) =
+        match schema.attributes.iter().find(|attribute| attribute.name.eq_ignore_ascii_case(&parsed.attribute)) {
+            Some(attribute) => (
+                attribute.name.clone(),
+                attribute.sub_attributes.iter().flatten().map(|sub_attribute| sub_attribute.name.as_str()).collect(),
+            ),
+            None if extension.is_none() => {
+                let name = COMMON_ATTRIBUTES
+                    .iter()
+                    .find(|name| name.eq_ignore_ascii_case(&parsed.attribute))
+                    .ok_or_else(invalid)?;
+                let sub_attributes = if *name == "meta" { META_SUB_ATTRIBUTES.to_vec() } else { vec![] };
+                (name.to_string(), sub_attributes)
+            }
+            None => return Err(invalid()),
+        };
+
+    let sub_attribute = match &parsed.sub_attribute {
+        Some(sub_attribute) => Some(
+            sub_attributes
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(sub_attribute))
+                .ok_or_else(invalid)?
+                .to_string(),
+        ),
+        None => None,
+    };
+    let complex = sub_attribute.is_none() && !sub_attributes.is_empty();
+    Ok(Selection { extension, attribute, sub_attribute, complex })
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => fields.is_empty(),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// Keeps only the `keep` sub-attributes of a complex attribute, or of each element of a multi-valued one.
+fn retain_sub_attributes(value: &mut Value, keep: &[&str]) {
+    match value {
+        Value::Object(fields) => fields.retain(|key, _| keep.contains(&key.as_str())),
+        Value::Array(items) => {
+            items.iter_mut().for_each(|item| retain_sub_attributes(item, keep));
+            items.retain(|item| !is_empty(item));
+        }
+        _ => {}
+    }
+}
+
+fn remove_sub_attribute(value: &mut Value, sub_attribute: &str) {
+    match value {
+        Value::Object(fields) => {
+            fields.remove(sub_attribute);
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| remove_sub_attribute(item, sub_attribute)),
+        _ => {}
+    }
+}
+
+fn retain_selected(container: &mut Map<String, Value>, extension: Option<&str>, selections: &[Selection]) {
+    container.retain(|key, value| {
+        if extension.is_none() && ALWAYS_RETURNED.contains(&key.as_str()) {
+            return true;
+        }
+        let matching: Vec<&Selection> = selections
+            .iter()
+            .filter(|selection| selection.extension.as_deref() == extension && selection.attribute == *key)
+            .collect();
+        if matching.iter().any(|selection| selection.sub_attribute.is_none()) {
+            return true;
+        }
+        let sub_attributes: Vec<&str> = matching.iter().filter_map(|selection| selection.sub_attribute.as_deref()).collect();
+        if !sub_attributes.is_empty() {
+            retain_sub_attributes(value, &sub_attributes);
+            return !is_empty(value);
+        }
+        // Attributes inside an extension can be selected without selecting the extension as a whole.
+        if extension.is_none() && selections.iter().any(|selection| selection.extension.as_deref() == Some(key.as_str())) {
+            if let Value::Object(fields) = value {
+                retain_selected(fields, Some(key.as_str()), selections);
+                return !fields.is_empty();
+            }
+        }
+        false
+    });
+}
+
+fn remove_selected(root: &mut Map<String, Value>, selections: &[Selection]) {
+    for selection in selections {
+        let container = match &selection.extension {
+            None if ALWAYS_RETURNED.contains(&selection.attribute.as_str()) => continue,
+            None => &mut *root,
+            Some(extension) => match root.get_mut(extension) {
+                Some(Value::Object(fields)) => fields,
+                _ => continue,
+            },
+        };
+        match &selection.sub_attribute {
+            None => {
+                container.remove(&selection.attribute);
+            }
+            Some(sub_attribute) => {
+                if let Some(value) = container.get_mut(&selection.attribute) {
+                    remove_sub_attribute(value, sub_attribute);
+                }
+            }
+        }
+    }
+}
+
+/// Removes attributes whose schema says they are never returned, such as `password`.
+fn remove_never_returned(root: &mut Map<String, Value>, schemas: &[Schema]) {
+    for (index, schema) in schemas.iter().enumerate() {
+        let container = if index == 0 {
+            Some(&mut *root)
+        } else {
+            root.get_mut(&schema.id).and_then(Value::as_object_mut)
+        };
+        if let Some(container) = container {
+            for attribute in schema.attributes.iter().filter(|attribute| attribute.returned == Some(Returned::Never)) {
+                container.remove(&attribute.name);
+            }
+        }
+    }
+}
+
+/// Serializes a `User` and applies the `attributes` / `excludedAttributes` query parameters to it (RFC 7644 §3.4.2.5).
+///
+/// With a non-empty `attributes`, only the listed attributes are returned. With a non-empty `excluded`, every
+/// attribute except those listed is returned. `schemas`, `id` and `meta` are always returned, and attributes the
+/// schema marks as never returned (such as `password`) are always omitted.
+///
+/// Entries may name a sub-attribute (`name.givenName`, `emails.value`), an extension attribute by its
+/// fully-qualified URN (`urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department`), or a whole
+/// extension by its schema URN. Names are matched case-insensitively.
+///
+/// # Errors
+///
+/// * `SCIMError::InvalidPath` - If an entry does not name an attribute of the User or Enterprise User schema.
+/// * `SCIMError::RequestError` - If both `attributes` and `excluded` are non-empty, as they are mutually exclusive.
+/// * `SCIMError::SerializationError` - If the user cannot be serialized.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::query::project_user;
+///
+/// let user = User {
+///     id: Some("2819c223-7f76-453a-919d-413861904646".to_string()),
+///     user_name: "bjensen@example.com".to_string(),
+///     display_name: Some("Babs Jensen".to_string()),
+///     ..Default::default()
+/// };
+///
+/// let projected = project_user(&user, &["displayName".to_string()], &[]).unwrap();
+/// assert_eq!(projected["displayName"], "Babs Jensen");
+/// assert!(projected.get("userName").is_none());
+/// ```
+pub fn project_user(user: &User, attributes: &[String], excluded: &[String]) -> Result<Value, SCIMError> {
+    if !attributes.is_empty() && !excluded.is_empty() {
+        return Err(SCIMError::RequestError("attributes and excludedAttributes are mutually exclusive".to_string()));
+    }
+
+    let schemas = user_schemas()?;
+    let attributes = resolve_selections(attributes, &schemas)?;
+    let excluded = resolve_selections(excluded, &schemas)?;
+
+    let value = serde_json::to_value(user).map_err(SCIMError::SerializationError)?;
+    Ok(project_with(&value, &attributes, &excluded, &schemas))
+}
+
+fn resolve_selections(paths: &[String], schemas: &[Schema]) -> Result<Vec<Selection>, SCIMError> {
+    paths.iter().map(|path| resolve_selection(path, schemas)).collect()
+}
+
+/// Projects a serialized user onto selections already resolved against `schemas`, so that a page of users can
+/// share one schema lookup.
+fn project_with(value: &Value, attributes: &[Selection], excluded: &[Selection], schemas: &[Schema]) -> Value {
+    let mut value = value.clone();
+    if let Value::Object(root) = &mut value {
+        if !attributes.is_empty() {
+            retain_selected(root, None, attributes);
+        }
+        remove_selected(root, excluded);
+        remove_never_returned(root, schemas);
+    }
+    value
+}
+
+/// Picks the value `selection` refers to for sorting. Multi-valued attributes sort by their primary value, or by
+/// their first value if none is marked primary (RFC 7644 §3.4.2.3).
+fn sort_value<'a>(root: &'a Value, selection: &Selection) -> Option<&'a Value> {
+    let container = match &selection.extension {
+        Some(extension) => root.get(extension)?,
+        None => root,
+    };
+    let value = match container.get(&selection.attribute)? {
+        Value::Array(items) => items
+            .iter()
+            .find(|item| item.get("primary") == Some(&Value::Bool(true)))
+            .or_else(|| items.first())?,
+        value => value,
+    };
+    let value = match &selection.sub_attribute {
+        Some(sub_attribute) => value.get(sub_attribute)?,
+        None => value,
+    };
+    if value.is_null() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+fn compare_values(left: &Value, right: &Value) -> Ordering {
+    match (left, right) {
+        // SCIM string comparisons are case-insensitive unless the attribute is caseExact.
+        (Value::String(left), Value::String(right)) => left.to_lowercase().cmp(&right.to_lowercase()),
+        (Value::Number(left), Value::Number(right)) => left
+            .as_f64()
+            .partial_cmp(&right.as_f64())
+            .unwrap_or(Ordering::Equal),
+        (Value::Bool(left), Value::Bool(right)) => left.cmp(right),
+        _ => Ordering::Equal,
+    }
+}
+
+/// Sorts users by the attribute at `sort_by`, implementing the `sortBy` and `sortOrder` query parameters
+/// (RFC 7644 §3.4.2.3).
+///
+/// `sort_by` is a simple attribute path such as `userName`, `name.familyName` or `meta.created`. Strings are
+/// compared case-insensitively, multi-valued attributes sort by their primary (or first) value, and users
+/// without a value for the attribute sort last regardless of direction. The sort is stable.
+///
+/// # Errors
+///
+/// * `SCIMError::InvalidPath` - If `sort_by` does not name an attribute of the User or Enterprise User schema, or
+///   names a complex attribute (such as `name` or `emails`) without one of its sub-attributes.
+/// * `SCIMError::SerializationError` - If a user cannot be serialized.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::query::sort_users;
+///
+/// let mut users = vec![
+///     User { user_name: "jsmith".to_string(), ..Default::default() },
+///     User { user_name: "Bjensen".to_string(), ..Default::default() },
+/// ];
+///
+/// sort_users(&mut users, "userName", true).unwrap();
+/// assert_eq!(users[0].user_name, "Bjensen");
+/// ```
+pub fn sort_users(users: &mut Vec<User>, sort_by: &str, ascending: bool) -> Result<(), SCIMError> {
+    let selection = sort_selection(sort_by, &user_schemas()?)?;
+    sort_with(users, &selection, ascending)
+}
+
+/// Sorts users by a selection already resolved by `sort_selection`.
+fn sort_with(users: &mut Vec<User>, selection: &Selection, ascending: bool) -> Result<(), SCIMError> {
+    let values = users
+        .iter()
+        .map(|user| serde_json::to_value(user).map(|value| sort_value(&value, selection).cloned()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SCIMError::SerializationError)?;
+
+    let mut keyed: Vec<(Option<Value>, User)> = values.into_iter().zip(users.drain(..)).collect();
+    keyed.sort_by(|(left, _), (right, _)| {
+        match (left, right) {
+            (Some(left), Some(right)) if ascending => compare_values(left, right),
+            (Some(left), Some(right)) => compare_values(right, left),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    });
+    users.extend(keyed.into_iter().map(|(_, user)| user));
+    Ok(())
+}
+
+/// Resolves `sort_by` to a sortable (simple) attribute of the User or Enterprise User schema.
+fn sort_selection(sort_by: &str, schemas: &[Schema]) -> Result<Selection, SCIMError> {
+    let selection = resolve_selection(sort_by, schemas)?;
+    if selection.complex {
+        return Err(SCIMError::InvalidPath(sort_by.to_string()));
+    }
+    Ok(selection)
+}
+
+/// Returns the page of `resources` selected by the `startIndex` and `count` query parameters (RFC 7644 §3.4.2.4).
+///
+/// `start_index` is 1-based. The response reports the total number of `resources` in `total_results` and echoes
+/// `start_index`, while `items_per_page` is the number of resources actually returned. A `start_index` of 0 or
+/// past the end, or a `count` of 0, returns no resources but still reports the total.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::query::paginate;
+///
+/// let users: Vec<User> = (1..=5)
+///     .map(|n| User { user_name: format!("user{}", n), ..Default::default() })
+///     .collect();
+///
+/// let page = paginate(users, 3, 2);
+/// assert_eq!(page.total_results, 5);
+/// assert_eq!(page.items_per_page, 2);
+/// assert_eq!(page.resources[0].user_name, "user3");
+/// ```
+pub fn paginate(resources: Vec<User>, start_index: usize, count: usize) -> ListResponse<User> {
+    let total_results = resources.len();
+    let page = if start_index == 0 {
+        vec![]
+    } else {
+        resources.into_iter().skip(start_index - 1).take(count).collect()
+    };
+    ListResponse::new(page, total_results as i64, start_index as i64)
+}
+
+/// The direction requested by the `sortOrder` query parameter (RFC 7644 §3.4.2.3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// The query parameters of a SCIM list request (RFC 7644 §3.4.2): filtering, sorting, pagination and attribute
+/// projection.
+///
+/// Parameters that were not given are `None` (or empty), leaving the defaults to the caller. The fields map onto
+/// `FilterExpr::matches`, `sort_users`, `paginate` and `project_user`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ScimQuery {
+    pub filter: Option<FilterExpr>,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<SortOrder>,
+    pub start_index: Option<usize>,
+    pub count: Option<usize>,
+    pub attributes: Vec<String>,
+    pub excluded_attributes: Vec<String>,
+}
+
+/// Decodes a `application/x-www-form-urlencoded` component: `+` is a space and `%XX` is a byte.
+fn url_decode(component: &str) -> Result<String, SCIMError> {
+    let invalid = || SCIMError::RequestError(format!("invalid percent-encoding: {}", component));
+    let bytes = component.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = component.get(i + 1..i + 3).filter(|hex| hex.bytes().all(|b| b.is_ascii_hexdigit())).ok_or_else(invalid)?;
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid())?);
+                i += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| invalid())
+}
+
+fn parse_attribute_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::trim).filter(|name| !name.is_empty()).map(str::to_string).collect()
+}
+
+fn parse_integer(name: &str, value: &str) -> Result<i64, SCIMError> {
+    value.trim().parse().map_err(|_| SCIMError::RequestError(format!("{}: expected an integer, got {}", name, value)))
+}
+
+impl ScimQuery {
+    /// Parses the query string of a SCIM list request, e.g. `?filter=userName%20eq%20%22bjensen%22&count=10`.
+    ///
+    /// The leading `?` is optional, keys and values are URL-decoded, parameter names are matched
+    /// case-insensitively and unknown parameters are ignored. As RFC 7644 §3.4.2.4 specifies, a `startIndex`
+    /// below 1 is treated as 1 and a negative `count` as 0.
+    ///
+    /// # Errors
+    ///
+    /// * `SCIMError::InvalidFilter` - If `filter` cannot be parsed.
+    /// * `SCIMError::RequestError` - If a value is badly encoded, `startIndex` or `count` is not an integer,
+    ///   `sortOrder` is neither `ascending` nor `descending`, or both `attributes` and `excludedAttributes` are given.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use scim_v2::utils::query::{ScimQuery, SortOrder};
+    ///
+    /// let query = ScimQuery::from_query_string("?filter=userName%20eq%20%22bjensen%22&sortBy=name.familyName&sortOrder=descending&count=10").unwrap();
+    /// assert!(query.filter.is_some());
+    /// assert_eq!(query.sort_order, Some(SortOrder::Descending));
+    /// assert_eq!(query.count, Some(10));
+    /// ```
+    pub fn from_query_string(query_string: &str) -> Result<ScimQuery, SCIMError> {
+        let query_string = query_string.strip_prefix('?').unwrap_or(query_string);
+        let mut query = ScimQuery::default();
+        for pair in query_string.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let (name, value) = (url_decode(name)?, url_decode(value)?);
+            match name.to_ascii_lowercase().as_str() {
+                "filter" => query.filter = Some(parse_filter(&value)?),
+                "sortby" => query.sort_by = Some(value),
+                "sortorder" => {
+                    query.sort_order = match value.to_ascii_lowercase().as_str() {
+                        "ascending" => Some(SortOrder::Ascending),
+                        "descending" => Some(SortOrder::Descending),
+                        _ => return Err(SCIMError::RequestError(format!("sortOrder: unsupported value {}", value))),
+                    }
+                }
+                "startindex" => query.start_index = Some(parse_integer(&name, &value)?.max(1) as usize),
+                "count" => query.count = Some(parse_integer(&name, &value)?.max(0) as usize),
+                "attributes" => query.attributes = parse_attribute_list(&value),
+                "excludedattributes" => query.excluded_attributes = parse_attribute_list(&value),
+                _ => {}
+            }
+        }
+        if !query.attributes.is_empty() && !query.excluded_attributes.is_empty() {
+            return Err(SCIMError::RequestError("attributes and excludedAttributes are mutually exclusive".to_string()));
+        }
+        Ok(query)
+    }
+}
+
+/// Answers a list request: filters, sorts, paginates and projects `users` as `query` asks, in that order
+/// (RFC 7644 §3.4.2), producing the `ListResponse` a server returns.
+///
+/// `totalResults` counts every user that matches the filter, not just the returned page. Without `sortBy` the
+/// users keep their order, `sortOrder` defaults to ascending, `startIndex` defaults to 1 and, without `count`,
+/// every remaining user is returned. `count=0` asks for `totalResults` alone (RFC 7644 §3.4.2.4): the filter is
+/// still applied, but the users are neither sorted nor projected.
+///
+/// # Errors
+///
+/// Returns the errors of `sort_users` and `project_user`, e.g. `SCIMError::InvalidPath` for an unknown `sortBy`
+/// or `attributes` entry.
+///
+/// # Examples
+///
+/// ```rust
+/// use scim_v2::models::user::User;
+/// use scim_v2::utils::query::{apply_query, ScimQuery};
+///
+/// let users = vec![
+///     User { user_name: "bjensen".to_string(), active: Some(true), ..Default::default() },
+///     User { user_name: "jsmith".to_string(), active: Some(false), ..Default::default() },
+/// ];
+/// let query = ScimQuery::from_query_string("filter=active%20eq%20true&attributes=userName").unwrap();
+///
+/// let response = apply_query(users, &query).unwrap();
+/// assert_eq!(response.total_results, 1);
+/// assert_eq!(response.resources[0]["userName"], "bjensen");
+/// ```
+pub fn apply_query(users: Vec<User>, query: &ScimQuery) -> Result<ListResponse<Value>, SCIMError> {
+    query_users(users, query, sort_with)
+}
+
+/// `apply_query`, with the sort step supplied by the caller so that tests can observe when it runs.
+fn query_users<S>(users: Vec<User>, query: &ScimQuery, sort: S) -> Result<ListResponse<Value>, SCIMError>
+where
+    S: FnOnce(&mut Vec<User>, &Selection, bool) -> Result<(), SCIMError>,
+{
+    if !query.attributes.is_empty() && !query.excluded_attributes.is_empty() {
+        return Err(SCIMError::RequestError("attributes and excludedAttributes are mutually exclusive".to_string()));
+    }
+    // The schemas and selections are resolved once for the whole query rather than once per user.
+    let schemas = user_schemas()?;
+    let attributes = resolve_selections(&query.attributes, &schemas)?;
+    let excluded = resolve_selections(&query.excluded_attributes, &schemas)?;
+    let sort_by = query.sort_by.as_deref().map(|sort_by| sort_selection(sort_by, &schemas)).transpose()?;
+
+    let mut users: Vec<User> = match &query.filter {
+        Some(filter) => users.into_iter().filter(|user| filter.matches(user)).collect(),
+        None => users,
+    };
+    if query.count == Some(0) {
+        // Only `totalResults` was asked for, so there is no page to sort or project.
+        let start_index = query.start_index.unwrap_or(1) as i64;
+        return Ok(ListResponse::new(vec![], users.len() as i64, start_index));
+    }
+    if let Some(sort_by) = &sort_by {
+        sort(&mut users, sort_by, query.sort_order != Some(SortOrder::Descending))?;
+    }
+
+    let page = paginate(users, query.start_index.unwrap_or(1), query.count.unwrap_or(usize::MAX));
+    let resources = page
+        .resources
+        .iter()
+        .map(|user| serde_json::to_value(user).map(|value| project_with(&value, &attributes, &excluded, &schemas)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SCIMError::SerializationError)?;
+    Ok(ListResponse::new(resources, page.total_results, page.start_index))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::enterprise_user::Manager;
+
+    fn sample_user() -> User {
+        serde_json::from_value(json!({
+            "schemas": [schema_uri::USER, schema_uri::ENTERPRISE_USER],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen@example.com",
+            "name": {"givenName": "Barbara", "familyName": "Jensen"},
+            "displayName": "Babs Jensen",
+            "password": "t1meMa$heen",
+            "emails": [
+                {"value": "bjensen@example.com", "type": "work", "primary": true},
+                {"value": "babs@jensen.org", "type": "home"}
+            ],
+            "meta": {"resourceType": "User", "location": "https://example.com/v2/Users/2819c223"},
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations", "costCenter": "4130"}
+        }))
+        .unwrap()
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn project_user_includes_only_requested_attributes() {
+        let attributes = strings(&["userName", "name.givenName", "EMAILS.value", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department"]);
+        let projected = project_user(&sample_user(), &attributes, &[]).unwrap();
+
+        assert_eq!(projected, json!({
+            "schemas": [schema_uri::USER, schema_uri::ENTERPRISE_USER],
+            "id": "2819c223-7f76-453a-919d-413861904646",
+            "userName": "bjensen@example.com",
+            "name": {"givenName": "Barbara"},
+            "emails": [{"value": "bjensen@example.com"}, {"value": "babs@jensen.org"}],
+            "meta": {"resourceType": "User", "location": "https://example.com/v2/Users/2819c223"},
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {"department": "Tour Operations"}
+        }));
+    }
+
+    #[test]
+    fn project_user_keeps_single_enterprise_sub_attribute_and_schema_urn() {
+        let mut user = sample_user();
+        user.enterprise_user.as_mut().unwrap().manager =
+            Some(Manager { value: Some("26118915".to_string()), ref_: None, display_name: Some("John Smith".to_string()) });
+        let attributes = strings(&[
+            "userName",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department",
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager.displayName",
+        ]);
+
+        let projected = project_user(&user, &attributes, &[]).unwrap();
+
+        assert_eq!(projected["schemas"], json!([schema_uri::USER, schema_uri::ENTERPRISE_USER]));
+        assert_eq!(projected["userName"], "bjensen@example.com");
+        assert_eq!(projected[schema_uri::ENTERPRISE_USER], json!({"department": "Tour Operations", "manager": {"displayName": "John Smith"}}));
+    }
+
+    #[test]
+    fn project_user_accepts_fully_qualified_core_attributes() {
+        let attributes = strings(&["urn:ietf:params:scim:schemas:core:2.0:User:name.familyName"]);
+        let projected = project_user(&sample_user(), &attributes, &[]).unwrap();
+
+        assert_eq!(projected["name"], json!({"familyName": "Jensen"}));
+        assert!(projected.get("userName").is_none());
+        assert!(projected.get(schema_uri::ENTERPRISE_USER).is_none());
+        assert_eq!(projected["schemas"], json!([schema_uri::USER, schema_uri::ENTERPRISE_USER]));
+    }
+
+    #[test]
+    fn project_user_removes_excluded_attributes() {
+        let excluded = strings(&["emails", "name.familyName", "id", "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"]);
+        let projected = project_user(&sample_user(), &[], &excluded).unwrap();
+
+        assert!(projected.get("emails").is_none());
+        assert!(projected.get(schema_uri::ENTERPRISE_USER).is_none());
+        assert_eq!(projected["name"]["givenName"], "Barbara");
+        assert!(projected["name"].get("familyName").is_none());
+        assert_eq!(projected["id"], "2819c223-7f76-453a-919d-413861904646");
+        assert_eq!(projected["displayName"], "Babs Jensen");
+    }
+
+    #[test]
+    fn project_user_never_returns_password() {
+        let projected = project_user(&sample_user(), &[], &[]).unwrap();
+        assert!(projected.get("password").is_none());
+
+        let projected = project_user(&sample_user(), &strings(&["password"]), &[]).unwrap();
+        assert!(projected.get("password").is_none());
+    }
+
+    #[test]
+    fn project_user_rejects_both_attributes_and_excluded_attributes() {
+        let result = project_user(&sample_user(), &strings(&["userName"]), &strings(&["emails"]));
+        assert!(matches!(result, Err(SCIMError::RequestError(_))));
+    }
+
+    #[test]
+    fn project_user_rejects_unknown_attributes() {
+        for path in ["nickname.first", "favouriteColour", "urn:example:params:scim:schemas:extension:2.0:User:department"] {
+            let result = project_user(&sample_user(), &strings(&[path]), &[]);
+            assert!(matches!(result, Err(SCIMError::InvalidPath(_))), "{}", path);
+        }
+    }
+
+    fn users_named(names: &[(&str, Option<&str>, Option<&str>)]) -> Vec<User> {
+        names
+            .iter()
+            .map(|(user_name, family_name, created)| {
+                serde_json::from_value(json!({
+                    "schemas": [schema_uri::USER],
+                    "userName": user_name,
+                    "name": {"familyName": family_name},
+                    "meta": {"created": created}
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn user_names(users: &[User]) -> Vec<&str> {
+        users.iter().map(|user| user.user_name.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_users_orders_user_name_case_insensitively() {
+        let mut users = users_named(&[("mike", None, None), ("Alice", None, None), ("bob", None, None)]);
+
+        sort_users(&mut users, "userName", true).unwrap();
+        assert_eq!(user_names(&users), vec!["Alice", "bob", "mike"]);
+
+        sort_users(&mut users, "USERNAME", false).unwrap();
+        assert_eq!(user_names(&users), vec!["mike", "bob", "Alice"]);
+    }
+
+    #[test]
+    fn sort_users_orders_meta_created_with_missing_values_last() {
+        let mut users = users_named(&[
+            ("new", None, Some("2011-05-13T04:42:34Z")),
+            ("unknown", None, None),
+            ("old", None, Some("2010-01-23T04:56:22Z")),
+        ]);
+
+        sort_users(&mut users, "meta.created", true).unwrap();
+        assert_eq!(user_names(&users), vec!["old", "new", "unknown"]);
+
+        sort_users(&mut users, "meta.created", false).unwrap();
+        assert_eq!(user_names(&users), vec!["new", "old", "unknown"]);
+    }
+
+    #[test]
+    fn sort_users_is_stable() {
+        let mut users = users_named(&[("a", Some("Jensen"), None), ("b", Some("Adams"), None), ("c", Some("jensen"), None)]);
+
+        sort_users(&mut users, "name.familyName", true).unwrap();
+        assert_eq!(user_names(&users), vec!["b", "a", "c"]);
+    }
+
+    #[test]
+    fn sort_users_rejects_unknown_or_complex_attributes() {
+        let mut users = users_named(&[("a", None, None)]);
+        for path in ["favouriteColour", "name", "emails", "meta"] {
+            let result = sort_users(&mut users, path, true);
+            assert!(matches!(result, Err(SCIMError::InvalidPath(_))), "{}", path);
+        }
+        assert_eq!(users.len(), 1);
+    }
+
+    fn numbered_users(total: usize) -> Vec<User> {
+        (1..=total).map(|n| User { user_name: format!("user{}", n), ..Default::default() }).collect()
+    }
+
+    #[test]
+    fn paginate_returns_first_page() {
+        let page = paginate(numbered_users(5), 1, 2);
+        assert_eq!(user_names(&page.resources), vec!["user1", "user2"]);
+        assert_eq!(page.total_results, 5);
+        assert_eq!(page.start_index, 1);
+        assert_eq!(page.items_per_page, 2);
+    }
+
+    #[test]
+    fn paginate_returns_middle_and_partial_last_page() {
+        let page = paginate(numbered_users(5), 3, 2);
+        assert_eq!(user_names(&page.resources), vec!["user3", "user4"]);
+        assert_eq!(page.start_index, 3);
+
+        let page = paginate(numbered_users(5), 5, 2);
+        assert_eq!(user_names(&page.resources), vec!["user5"]);
+        assert_eq!(page.items_per_page, 1);
+    }
+
+    #[test]
+    fn paginate_returns_empty_page_out_of_range() {
+        for (start_index, count) in [(0, 2), (6, 2), (1, 0)] {
+            let page = paginate(numbered_users(5), start_index, count);
+            assert!(page.resources.is_empty());
+            assert_eq!(page.items_per_page, 0);
+            assert_eq!(page.total_results, 5);
+            assert_eq!(page.start_index, start_index as i64);
+        }
+    }
+
+    #[test]
+    fn scim_query_parses_okta_query_string() {
+        let query = ScimQuery::from_query_string(
+            "?filter=userName%20eq%20%22test.user%40okta.local%22&startIndex=1&count=100&attributes=userName,emails.value",
+        )
+        .unwrap();
+
+        assert_eq!(query.filter, Some(parse_filter(r#"userName eq "test.user@okta.local""#).unwrap()));
+        assert_eq!(query.start_index, Some(1));
+        assert_eq!(query.count, Some(100));
+        assert_eq!(query.attributes, vec!["userName", "emails.value"]);
+        assert!(query.excluded_attributes.is_empty());
+        assert_eq!(query.sort_by, None);
+    }
+
+    #[test]
+    fn scim_query_parses_sorting_and_clamps_pagination() {
+        let query = ScimQuery::from_query_string("sortBy=name.familyName&sortOrder=Descending&startIndex=0&count=-5&unknown=1")
+            .unwrap();
+
+        assert_eq!(query.sort_by.as_deref(), Some("name.familyName"));
+        assert_eq!(query.sort_order, Some(SortOrder::Descending));
+        assert_eq!(query.start_index, Some(1));
+        assert_eq!(query.count, Some(0));
+        assert_eq!(query.filter, None);
+    }
+
+    #[test]
+    fn scim_query_rejects_invalid_parameters() {
+        assert!(matches!(
+            ScimQuery::from_query_string("attributes=userName&excludedAttributes=emails"),
+            Err(SCIMError::RequestError(_))
+        ));
+        assert!(matches!(ScimQuery::from_query_string("count=ten"), Err(SCIMError::RequestError(_))));
+        assert!(matches!(ScimQuery::from_query_string("filter=%ZZ"), Err(SCIMError::RequestError(_))));
+        assert!(matches!(ScimQuery::from_query_string("filter=%+1"), Err(SCIMError::RequestError(_))));
+        assert!(matches!(ScimQuery::from_query_string("filter=userName+zz+%22x%22"), Err(SCIMError::InvalidFilter(_))));
+    }
+
+    #[test]
+    fn apply_query_filters_sorts_paginates_and_projects() {
+        let users: Vec<User> = [("mpepperidge", true), ("bjensen", true), ("jsmith", false), ("adoe", true), ("zwu", true)]
+            .iter()
+            .enumerate()
+            .map(|(i, (user_name, active))| User {
+                id: Some(format!("id-{}", i)),
+                user_name: user_name.to_string(),
+                title: Some("Tour Guide".to_string()),
+                active: Some(*active),
+                ..Default::default()
+            })
+            .collect();
+        let query = ScimQuery::from_query_string(
+            "filter=active+eq+true&sortBy=userName&sortOrder=descending&startIndex=2&count=2&attributes=userName",
+        )
+        .unwrap();
+
+        let response = apply_query(users, &query).unwrap();
+
+        assert_eq!(response.total_results, 4);
+        assert_eq!(response.items_per_page, 2);
+        assert_eq!(response.start_index, 2);
+        assert_eq!(response.resources, vec![
+            json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "id": "id-0", "userName": "mpepperidge"}),
+            json!({"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "id": "id-1", "userName": "bjensen"}),
+        ]);
+    }
+
+    #[test]
+    fn apply_query_resolves_attributes_before_paging() {
+        let query = ScimQuery { attributes: strings(&["favoriteColor"]), ..Default::default() };
+        assert!(matches!(apply_query(vec![], &query), Err(SCIMError::InvalidPath(_))));
+
+        let query = ScimQuery { attributes: strings(&["userName"]), excluded_attributes: strings(&["emails"]), ..Default::default() };
+        assert!(matches!(apply_query(vec![sample_user()], &query), Err(SCIMError::RequestError(_))));
+
+        let query = ScimQuery { excluded_attributes: strings(&["emails", "name"]), ..Default::default() };
+        let response = apply_query(vec![sample_user(), sample_user()], &query).unwrap();
+        let expected = project_user(&sample_user(), &[], &query.excluded_attributes).unwrap();
+        assert_eq!(response.resources, vec![expected.clone(), expected]);
+    }
+
+    #[test]
+    fn apply_query_with_zero_count_reports_total_without_sorting() {
+        let users: Vec<User> = (0..50)
+            .map(|i| User { user_name: format!("user{:02}", 49 - i), active: Some(i % 2 == 0), ..Default::default() })
+            .collect();
+        let sorts = Cell::new(0);
+        let counting_sort = |users: &mut Vec<User>, selection: &Selection, ascending: bool| {
+            sorts.set(sorts.get() + 1);
+            sort_with(users, selection, ascending)
+        };
+        let query = ScimQuery::from_query_string("filter=active%20eq%20true&sortBy=userName&count=0").unwrap();
+
+        let response = query_users(users.clone(), &query, counting_sort).unwrap();
+
+        assert!(response.resources.is_empty());
+        assert_eq!(response.total_results, 25);
+        assert_eq!(sorts.get(), 0);
+
+        let query = ScimQuery::from_query_string("filter=active%20eq%20true&sortBy=userName&count=1").unwrap();
+        let response = query_users(users.clone(), &query, counting_sort).unwrap();
+        assert_eq!(sorts.get(), 1);
+        assert_eq!(response.resources[0]["userName"], "user01");
+
+        let query = ScimQuery::from_query_string("sortBy=favoriteColor&count=0").unwrap();
+        assert!(matches!(apply_query(users, &query), Err(SCIMError::InvalidPath(_))));
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::utils::error::SCIMError;
+use crate::utils::filter::{AttrPath, CompareOp, FilterExpr};
+
+/// Looks up the column `path` is stored in. Keys are matched case-insensitively, like attribute names.
+fn column<'a>(path: &AttrPath, mapping: &HashMap<&str, &'a str>) -> Result<&'a str, SCIMError> {
+    let name = path.to_string();
+    mapping
+        .iter()
+        .find(|(attribute, _)| attribute.eq_ignore_ascii_case(&name))
+        .map(|(_, column)| *column)
+        .ok_or_else(|| SCIMError::InvalidFilter(format!("unmapped attribute {}", name)))
+}
+
+/// Escapes the `LIKE` wildcards `%` and `_`, and the escape character itself, so they match literally.
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn bind_value(value: &Value) -> Result<String, SCIMError> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(flag) => Ok(flag.to_string()),
+        _ => Err(SCIMError::InvalidFilter(format!("unsupported value {}", value))),
+    }
+}
+
+fn translate(expr: &FilterExpr, mapping: &HashMap<&str, &str>, binds: &mut Vec<String>) -> Result<String, SCIMError> {
+    match expr {
+        FilterExpr::Present(path) => Ok(format!("{} IS NOT NULL", column(path, mapping)?)),
+        FilterExpr::Compare(path, operator, Value::Null) => {
+            let column = column(path, mapping)?;
+            match operator {
+                CompareOp::Eq => Ok(format!("{} IS NULL", column)),
+                CompareOp::Ne => Ok(format!("{} IS NOT NULL", column)),
+                _ => Err(SCIMError::InvalidFilter(format!("{} cannot be compared with null using {:?}", path, operator))),
+            }
+        }
+        FilterExpr::Compare(path, operator, value) => {
+            let column = column(path, mapping)?;
+            let (condition, bind) = match operator {
+                CompareOp::Co | CompareOp::Sw | CompareOp::Ew => {
+                    let text = match value {
+                        Value::String(text) => escape_like(text),
+                        _ => return Err(SCIMError::InvalidFilter(format!("{:?} requires a string value", operator))),
+                    };
+                    let pattern = match operator {
+                        CompareOp::Co => format!("%{}%", text),
+                        CompareOp::Sw => format!("{}%", text),
+                        _ => format!("%{}", text),
+                    };
+                    (format!("{} LIKE ? ESCAPE '\\'", column), pattern)
+                }
+                // A user without the attribute is not equal to any value, as in `FilterExpr::matches`, while SQL
+                // leaves `NULL <> ?` unknown.
+                CompareOp::Ne => (format!("({0} <> ? OR {0} IS NULL)", column), bind_value(value)?),
+                _ => {
+                    let sql_operator = match operator {
+                        CompareOp::Eq => "=",
+                        CompareOp::Gt => ">",
+                        CompareOp::Ge => ">=",
+                        CompareOp::Lt => "<",
+                        _ => "<=",
+                    };
+                    (format!("{} {} ?", column, sql_operator), bind_value(value)?)
+                }
+            };
+            binds.push(bind);
+            Ok(condition)
+        }
+        FilterExpr::And(left, right) => {
+            Ok(format!("({} AND {})", translate(left, mapping, binds)?, translate(right, mapping, binds)?))
+        }
+        FilterExpr::Or(left, right) => {
+            Ok(format!("({} OR {})", translate(left, mapping, binds)?, translate(right, mapping, binds)?))
+        }
+        // `NOT` of an unknown (NULL) comparison is still unknown, and would drop the row; `IS NOT TRUE` keeps it,
+        // as `FilterExpr::matches` does for users the inner filter does not match.
+        FilterExpr::Not(inner) => Ok(format!("({}) IS NOT TRUE", translate(inner, mapping, binds)?)),
+        FilterExpr::ValuePath(path, _) => {
+            Err(SCIMError::InvalidFilter(format!("value filter on {} cannot be translated to SQL", path)))
+        }
+    }
+}
+
+/// Translates a parsed filter into a parameterized SQL `WHERE` fragment, so that servers backed by a SQL database
+/// can push filtering down to it.
+///
+/// `mapping` maps attribute paths as written in filters (`userName`, `emails.value`, or a fully-qualified
+/// extension attribute) to column names, which are inserted into the SQL verbatim and so must come from the
+/// server, never from the client. Values are never inserted: each becomes a `?` placeholder and is returned, in
+/// order, as a bind value.
+///
+/// `eq`, `ne`, `gt`, `ge`, `lt` and `le` become the corresponding SQL comparisons, and `co`, `sw` and `ew` become
+/// `LIKE` with `%` and `_` in the value escaped. `pr` becomes `IS NOT NULL`, as do `ne null`, while `eq null`
+/// becomes `IS NULL`. Case sensitivity follows the column's collation rather than the attribute's `caseExact`.
+///
+/// Rows are selected as `FilterExpr::matches` would select users, including rows where a column is `NULL`: `ne`
+/// also matches `NULL` (`(col <> ? OR col IS NULL)`), and `not` becomes `(...) IS NOT TRUE` rather than `NOT (...)`,
+/// which SQL's three-valued logic would make drop rows whose inner condition is unknown.
+///
+/// # Errors
+///
+/// Returns `SCIMError::InvalidFilter` if the filter refers to an attribute that `mapping` has no column for,
+/// uses a value filter such as `emails[type eq "work"]`, or compares a value a placeholder cannot carry.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use scim_v2::utils::filter::parse_filter;
+/// use scim_v2::utils::sql::filter_to_sql;
+///
+/// let mapping = HashMap::from([("userName", "user_name"), ("active", "active")]);
+/// let filter = parse_filter(r#"userName sw "b" and active eq true"#).unwrap();
+///
+/// let (sql, binds) = filter_to_sql(&filter, &mapping).unwrap();
+/// assert_eq!(sql, r"(user_name LIKE ? ESCAPE '\' AND active = ?)");
+/// assert_eq!(binds, vec!["b%", "true"]);
+/// ```
+pub fn filter_to_sql(expr: &FilterExpr, mapping: &HashMap<&str, &str>) -> Result<(String, Vec<String>), SCIMError> {
+    let mut binds = Vec::new();
+    let sql = translate(expr, mapping, &mut binds)?;
+    Ok((sql, binds))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::utils::filter::parse_filter;
+
+    fn mapping() -> HashMap<&'static str, &'static str> {
+        HashMap::from([
+            ("userName", "users.user_name"),
+            ("emails.value", "users.email"),
+            ("meta.lastModified", "users.last_modified"),
+            ("urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department", "users.department"),
+        ])
+    }
+
+    fn to_sql(filter: &str) -> Result<(String, Vec<String>), SCIMError> {
+        filter_to_sql(&parse_filter(filter).unwrap(), &mapping())
+    }
+
+    #[test]
+    fn translates_user_name_eq() {
+        assert_eq!(to_sql(r#"userName eq "bjensen""#).unwrap(), ("users.user_name = ?".to_string(), vec!["bjensen".to_string()]));
+    }
+
+    #[test]
+    fn translates_contains_to_escaped_like() {
+        assert_eq!(
+            to_sql(r#"emails.value co "b_jensen%""#).unwrap(),
+            (r"users.email LIKE ? ESCAPE '\'".to_string(), vec![r"%b\_jensen\%%".to_string()])
+        );
+    }
+
+    #[test]
+    fn translates_logical_operators_presence_and_null() {
+        let (sql, binds) = to_sql(
+            r#"(USERNAME sw "b" or not (emails.value pr)) and meta.lastModified gt "2011-05-13T04:42:34Z" and urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:department eq null"#,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            r"(((users.user_name LIKE ? ESCAPE '\' OR (users.email IS NOT NULL) IS NOT TRUE) AND users.last_modified > ?) AND users.department IS NULL)"
+        );
+        assert_eq!(binds, vec!["b%", "2011-05-13T04:42:34Z"]);
+    }
+
+    #[test]
+    fn rejects_unmapped_attributes_and_value_filters() {
+        for filter in [r#"displayName eq "Babs""#, r#"emails[type eq "work"]"#, r#"userName gt null"#] {
+            assert!(matches!(to_sql(filter), Err(SCIMError::InvalidFilter(_))), "{}", filter);
+        }
+    }
+
+    #[test]
+    fn translates_ne_and_not_to_match_null_columns() {
+        assert_eq!(
+            to_sql(r#"userName ne "bjensen""#).unwrap(),
+            ("(users.user_name <> ? OR users.user_name IS NULL)".to_string(), vec!["bjensen".to_string()])
+        );
+        assert_eq!(
+            to_sql(r#"not (emails.value co "example")"#).unwrap(),
+            (r"(users.email LIKE ? ESCAPE '\') IS NOT TRUE".to_string(), vec!["%example%".to_string()])
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+use serde::de::{DeserializeOwned, IgnoredAny};
+
+use crate::models::user::User;
+use crate::utils::error::SCIMError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Start,
+    Members,
+    Resources { first: bool },
+    Done,
+}
+
+/// An iterator over the `Resources` of a `ListResponse` of users, read incrementally from a `Read`.
+///
+/// Created by `stream_list_response`. Only one resource is held in memory at a time. Members of the list
+/// response other than `Resources` are skipped, except `totalResults`, which is available from `total_results`
+/// once it has been read.
+pub struct ListResponseStream<R> {
+    reader: BufReader<R>,
+    state: State,
+    total_results: Option<i64>,
+}
+
+/// Streams the users in a `ListResponse` without reading the whole document into memory.
+///
+/// Each item is the next element of `Resources`. An element that is valid JSON but not a valid `User` yields a
+/// `SCIMError::DeserializationError` and the stream moves on to the next element; a malformed document or an I/O
+/// failure yields an error and ends the stream.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::io::Cursor;
+///
+/// use scim_v2::utils::stream::stream_list_response;
+///
+/// let json = r#"{
+///     "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+///     "totalResults": 1,
+///     "Resources": [{"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen"}]
+/// }"#;
+///
+/// let mut stream = stream_list_response(Cursor::new(json));
+/// for user in &mut stream {
+///     match user {
+///         Ok(user) => println!("User: {}", user.user_name),
+///         Err(e) => println!("Error reading user: {}", e),
+///     }
+/// }
+/// assert_eq!(stream.total_results(), Some(1));
+/// ```
+pub fn stream_list_response<R: Read>(reader: R) -> ListResponseStream<R> {
+    ListResponseStream {
+        reader: BufReader::new(reader),
+        state: State::Start,
+        total_results: None,
+    }
+}
+
+fn io_error(error: io::Error) -> SCIMError {
+    SCIMError::OtherError(error.to_string())
+}
+
+fn json_error(error: serde_json::Error) -> SCIMError {
+    if error.is_io() {
+        SCIMError::OtherError(error.to_string())
+    } else if error.is_data() {
+        SCIMError::DeserializationError(error)
+    } else {
+        SCIMError::InvalidJsonFormat
+    }
+}
+
+/// Hands the bytes of a `BufReader` to serde_json one at a time, holding back the last one.
+///
+/// serde_json reads one byte past a number or literal to find where it ends. A held-back byte is only consumed
+/// once the deserializer reports it as part of the value, so the separator after a value stays in the `BufReader`.
+struct ValueReader<'a, R> {
+    reader: &'a mut BufReader<R>,
+    held: bool,
+    read: usize,
+}
+
+impl<R: Read> Read for ValueReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.held {
+            self.reader.consume(1);
+            self.held = false;
+        }
+        match self.reader.fill_buf()?.first() {
+            Some(&byte) => {
+                buf[0] = byte;
+                self.held = true;
+                self.read += 1;
+                Ok(1)
+            }
+            None => Ok(0),
+        }
+    }
+}
+
+impl<R: Read> ListResponseStream<R> {
+    /// Returns `totalResults`, if it has been read.
+    ///
+    /// `totalResults` may appear after `Resources` in the document, so this is only guaranteed to be populated
+    /// once the stream has been drained.
+    pub fn total_results(&self) -> Option<i64> {
+        self.total_results
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, SCIMError> {
+        Ok(self.reader.fill_buf().map_err(io_error)?.first().copied())
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, SCIMError> {
+        loop {
+            match self.peek()? {
+                Some(byte) if byte.is_ascii_whitespace() => self.reader.consume(1),
+                other => return Ok(other),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<(), SCIMError> {
+        match self.skip_whitespace()? {
+            Some(byte) if byte == expected => {
+                self.reader.consume(1);
+                Ok(())
+            }
+            _ => Err(SCIMError::InvalidJsonFormat),
+        }
+    }
+
+    /// Deserializes the next JSON value with serde_json's streaming deserializer.
+    fn read_value<T: DeserializeOwned>(&mut self) -> Result<T, SCIMError> {
+        let mut source = ValueReader { reader: &mut self.reader, held: false, read: 0 };
+        let mut values = serde_json::Deserializer::from_reader(&mut source).into_iter::<T>();
+        let value = values.next().ok_or(SCIMError::InvalidJsonFormat)?;
+        let offset = values.byte_offset();
+        let value = value.map_err(json_error)?;
+        if source.held && offset == source.read {
+            source.reader.consume(1);
+        }
+        Ok(value)
+    }
+
+    /// Consumes the separator after a member of the top-level object.
+    fn end_member(&mut self) -> Result<(), SCIMError> {
+        match self.skip_whitespace()? {
+            Some(b',') => self.reader.consume(1),
+            Some(b'}') => {
+                self.reader.consume(1);
+                self.state = State::Done;
+            }
+            _ => return Err(SCIMError::InvalidJsonFormat),
+        }
+        Ok(())
+    }
+
+    /// Advances to the next element of `Resources`, returning it as an untyped value.
+    fn next_resource(&mut self) -> Result<Option<serde_json::Value>, SCIMError> {
+        loop {
+            match self.state {
+                State::Start => {
+                    self.expect(b'{')?;
+                    self.state = State::Members;
+                    if self.skip_whitespace()? == Some(b'}') {
+                        self.reader.consume(1);
+                        self.state = State::Done;
+                    }
+                }
+                State::Members => {
+                    let key: String = self.read_value()?;
+                    self.expect(b':')?;
+                    if key.eq_ignore_ascii_case("Resources") {
+                        self.expect(b'[')?;
+                        self.state = State::Resources { first: true };
+                        continue;
+                    }
+                    if key.eq_ignore_ascii_case("totalResults") {
+                        self.total_results = Some(self.read_value()?);
+                    } else {
+                        self.read_value::<IgnoredAny>()?;
+                    }
+                    self.end_member()?;
+                }
+                State::Resources { first } => {
+                    match self.skip_whitespace()? {
+                        Some(b']') => {
+                            self.reader.consume(1);
+                            self.state = State::Members;
+                            self.end_member()?;
+                            continue;
+                        }
+                        Some(b',') if !first => self.reader.consume(1),
+                        _ if first => {}
+                        _ => return Err(SCIMError::InvalidJsonFormat),
+                    }
+                    self.state = State::Resources { first: false };
+                    return self.read_value().map(Some);
+                }
+                State::Done => return Ok(None),
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for ListResponseStream<R> {
+    type Item = Result<User, SCIMError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_resource() {
+            Ok(Some(value)) => Some(User::try_from(value)),
+            Ok(None) => None,
+            Err(error) => {
+                self.state = State::Done;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    const LIST_RESPONSE: &str = r#"{
+        "schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"],
+        "itemsPerPage": 3,
+        "startIndex": 1,
+        "Resources": [
+            {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "bjensen", "name": {"formatted": "Ms. Barbara J \"Babs\" Jensen"}},
+            {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "jsmith", "emails": [{"value": "jsmith@example.com"}]},
+            {"schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"], "userName": "mpepperidge"}
+        ],
+        "totalResults": 42
+    }"#;
+
+    #[test]
+    fn stream_list_response_yields_each_user() {
+        let mut stream = stream_list_response(Cursor::new(LIST_RESPONSE));
+
+        let users: Vec<User> = (&mut stream).collect::<Result<_, _>>().unwrap();
+
+        let user_names: Vec<&str> = users.iter().map(|user| user.user_name.as_str()).collect();
+        assert_eq!(user_names, vec!["bjensen", "jsmith", "mpepperidge"]);
+        assert_eq!(users[0].name.as_ref().unwrap().formatted.as_deref(), Some("Ms. Barbara J \"Babs\" Jensen"));
+        assert_eq!(stream.total_results(), Some(42));
+    }
+
+    #[test]
+    fn stream_list_response_handles_empty_resources() {
+        let json = r#"{"schemas": ["urn:ietf:params:scim:api:messages:2.0:ListResponse"], "totalResults": 0, "Resources": []}"#;
+        let mut stream = stream_list_response(Cursor::new(json));
+
+        assert!(stream.next().is_none());
+        assert_eq!(stream.total_results(), Some(0));
+    }
+
+    #[test]
+    fn stream_list_response_keeps_separators_after_scalar_members() {
+        let json = r#"{"totalResults":2,"itemsPerPage":2.5e0,"partial":false,"next":null,"Resources":[{"schemas":[],"userName":"bjensen"},{"schemas":[],"userName":"jsmith"}]}"#;
+        let mut stream = stream_list_response(Cursor::new(json));
+
+        let user_names: Vec<String> = (&mut stream).map(|user| user.unwrap().user_name).collect();
+
+        assert_eq!(user_names, vec!["bjensen", "jsmith"]);
+        assert_eq!(stream.total_results(), Some(2));
+    }
+
+    #[test]
+    fn stream_list_response_skips_invalid_users_and_stops_on_malformed_json() {
+        let json = r#"{"Resources": [{"userName": 42}, {"schemas": [], "userName": "bjensen"}, {"#;
+        let results: Vec<Result<User, SCIMError>> = stream_list_response(Cursor::new(json)).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(matches!(&results[0], Err(SCIMError::DeserializationError(err)) if err.to_string().starts_with("userName: ")));
+        assert_eq!(results[1].as_ref().unwrap().user_name, "bjensen");
+        assert!(matches!(results[2], Err(SCIMError::InvalidJsonFormat)));
+    }
+}